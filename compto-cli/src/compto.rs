@@ -0,0 +1,372 @@
+mod config;
+mod instructions;
+
+use std::{fmt::Display, path::PathBuf, process::Command};
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::Transaction,
+};
+
+use comptoken::{
+    accounts::{GlobalDataAccount, UserDataAccount, UserIdentityAccount},
+    audit::AuditReport,
+    query,
+};
+
+use config::Profile;
+use instructions::{Addresses, InitializeSettings, MetadataField, ProofScheme, QuerySelection};
+
+#[derive(Debug, Parser)]
+#[command(name = "compto", about = "Operate the comptoken program")]
+struct Cli {
+    /// config file containing cluster profiles [default: ~/.config/compto/config.toml]
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// profile to use from the config file
+    #[arg(long, short, global = true, default_value = "localnet")]
+    profile: String,
+    #[arg(long, short, global = true, value_enum, default_value_t = OutputFormat::Display)]
+    output: OutputFormat,
+    #[command(subcommand)]
+    command: CliCommand,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Display,
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+enum CliCommand {
+    /// Deploy both programs and initialize them, creating the mint
+    Bootstrap {
+        /// assume both programs are already deployed
+        #[arg(long)]
+        skip_deploy: bool,
+        #[command(flatten)]
+        settings: InitializeSettings,
+    },
+    /// Update a field of the comptoken metadata, the payer must be the admin
+    UpdateMetadata {
+        #[arg(value_enum)]
+        field: MetadataField,
+        value: String,
+    },
+    /// Switch the proof scheme at the next blockhash rollover, the payer must be the admin
+    SetProofScheme {
+        #[arg(value_enum)]
+        scheme: ProofScheme,
+    },
+    /// Run the daily distribution, paying the bounty to a comptoken wallet
+    Distribute { wallet: Pubkey },
+    /// Show whether the daily distribution is due
+    DistributionStatus,
+    /// Check the supply, bank solvency, blockhash freshness, and distribution schedule, failing if any check fails
+    Audit,
+    /// Show the comptoken global data
+    GlobalData,
+    /// Show part of the global data through the paginated query instruction
+    Query { selection: QuerySelection },
+    /// Show the user data of a comptoken wallet
+    UserData { wallet: Pubkey },
+    /// Show the identity shared by every comptoken wallet of an owner
+    UserIdentity { owner: Pubkey },
+    /// Create the user data account of a comptoken wallet owned by the payer, and the payer's identity if needed
+    CreateUserData {
+        wallet: Pubkey,
+        /// number of proofs the account can store per day
+        #[arg(long, default_value_t = 1)]
+        capacity: usize,
+    },
+    /// Show the interest and UBI a comptoken wallet would get by claiming now
+    Owed { wallet: Pubkey },
+    /// Claim the interest and UBI owed to a comptoken wallet
+    Claim { wallet: Pubkey },
+    /// Transfer comptokens, claiming anything owed to either wallet first
+    Transfer { source: Pubkey, destination: Pubkey, amount: u64 },
+}
+
+struct CliContext {
+    profile: Profile,
+    rpc: RpcClient,
+    payer: Keypair,
+    addresses: Addresses,
+    output: OutputFormat,
+}
+
+#[derive(Serialize)]
+struct TransactionOutput {
+    description: &'static str,
+    signature: String,
+}
+
+#[derive(Serialize)]
+struct DistributionStatus {
+    is_due: bool,
+    next_distribution_time: i64,
+    distribution_bounty: u64,
+}
+
+impl DistributionStatus {
+    fn from_return_data(data: &[u8]) -> Result<Self> {
+        if data.len() != 17 {
+            bail!("unexpected distribution status: {data:?}");
+        }
+        Ok(DistributionStatus {
+            is_due: data[0] != 0,
+            next_distribution_time: i64::from_le_bytes(data[1..9].try_into()?),
+            distribution_bounty: u64::from_le_bytes(data[9..17].try_into()?),
+        })
+    }
+}
+
+impl Display for DistributionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Due: {}", self.is_due)?;
+        writeln!(f, "Next Distribution: {}", self.next_distribution_time)?;
+        write!(f, "Distribution Bounty: {}", self.distribution_bounty)
+    }
+}
+
+#[derive(Serialize)]
+struct OwedComptokens {
+    interest: u64,
+    ubi: u64,
+}
+
+impl OwedComptokens {
+    fn from_return_data(data: &[u8]) -> Result<Self> {
+        if data.len() != 16 {
+            bail!("unexpected owed comptokens: {data:?}");
+        }
+        Ok(OwedComptokens {
+            interest: u64::from_le_bytes(data[0..8].try_into()?),
+            ubi: u64::from_le_bytes(data[8..16].try_into()?),
+        })
+    }
+}
+
+impl Display for OwedComptokens {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Interest: {}", self.interest)?;
+        write!(f, "UBI: {}", self.ubi)
+    }
+}
+
+impl Display for TransactionOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.description, self.signature)
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let profile = Profile::load(cli.config.as_ref(), &cli.profile)?;
+    let context = CliContext {
+        rpc: RpcClient::new_with_commitment(profile.json_rpc_url.clone(), profile.commitment()?),
+        payer: profile.payer()?,
+        addresses: Addresses::from_profile(&profile)?,
+        output: cli.output,
+        profile,
+    };
+
+    match cli.command {
+        CliCommand::Bootstrap { skip_deploy, settings } => bootstrap(&context, skip_deploy, &settings),
+        CliCommand::UpdateMetadata { field, value } => {
+            let instruction =
+                instructions::update_comptoken_metadata(&context.addresses, &context.payer.pubkey(), field, &value);
+            let signature = context.send(&[instruction], &[])?;
+            context.print_transaction("update metadata", signature)
+        }
+        CliCommand::SetProofScheme { scheme } => {
+            let instruction = instructions::set_proof_scheme(&context.addresses, &context.payer.pubkey(), scheme);
+            let signature = context.send(&[instruction], &[])?;
+            context.print_transaction("set proof scheme", signature)
+        }
+        CliCommand::Distribute { wallet } => {
+            let instruction = instructions::daily_distribution_event(&context.addresses, &wallet);
+            let signature = context.send(&[instruction], &[])?;
+            context.print_transaction("daily distribution", signature)
+        }
+        CliCommand::DistributionStatus => {
+            let data = context.simulate(&[instructions::get_distribution_status(&context.addresses)])?;
+            context.print(&DistributionStatus::from_return_data(&data)?)
+        }
+        CliCommand::Audit => {
+            let data = context.simulate(&[instructions::audit(&context.addresses)])?;
+            let report = AuditReport::from_bytes(&data).ok_or_else(|| anyhow!("unexpected audit report: {data:?}"))?;
+            context.print(&report)?;
+            if !report.passed() {
+                bail!("audit failed");
+            }
+            Ok(())
+        }
+        CliCommand::GlobalData => {
+            let data = context.rpc.get_account_data(&context.addresses.global_data)?;
+            context.print(&GlobalDataAccount::from_bytes(&data)?)
+        }
+        CliCommand::Query { selection } => {
+            let selector = selection.into();
+            let result = query::fetch(selector, |page| {
+                context.simulate(&[instructions::query_global_data(&context.addresses, selector, page)])
+            })?;
+            context.print(&result)
+        }
+        CliCommand::UserData { wallet } => {
+            let data = context.rpc.get_account_data(&context.addresses.user_data(&wallet))?;
+            context.print(&UserDataAccount::from_bytes(&data)?)
+        }
+        CliCommand::UserIdentity { owner } => {
+            let data = context.rpc.get_account_data(&context.addresses.user_identity(&owner))?;
+            context.print(&UserIdentityAccount::from_bytes(&data)?)
+        }
+        CliCommand::CreateUserData { wallet, capacity } => create_user_data(&context, &wallet, capacity),
+        CliCommand::Owed { wallet } => {
+            let data = context.rpc.get_account_data(&context.addresses.user_data(&wallet))?;
+            let user_identity = UserDataAccount::from_bytes(&data)?.user_identity;
+            let instruction = instructions::preview_owed_comptokens(&context.addresses, &wallet, &user_identity);
+            let data = context.simulate(&[instruction])?;
+            context.print(&OwedComptokens::from_return_data(&data)?)
+        }
+        CliCommand::Claim { wallet } => {
+            let data = context.rpc.get_account_data(&context.addresses.user_data(&wallet))?;
+            let user_identity = UserDataAccount::from_bytes(&data)?.user_identity;
+            let instruction = instructions::get_owed_comptokens(&context.addresses, &wallet, &user_identity);
+            let signature = context.send(&[instruction], &[])?;
+            context.print_transaction("claim", signature)
+        }
+        CliCommand::Transfer { source, destination, amount } => transfer(&context, &source, &destination, amount),
+    }
+}
+
+impl CliContext {
+    fn print<T: Serialize + Display>(&self, value: &T) -> Result<()> {
+        match self.output {
+            OutputFormat::Display => println!("{value}"),
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+        }
+        Ok(())
+    }
+
+    fn print_transaction(&self, description: &'static str, signature: Signature) -> Result<()> {
+        self.print(&TransactionOutput { description, signature: signature.to_string() })
+    }
+
+    fn send(&self, instructions: &[Instruction], extra_signers: &[&Keypair]) -> Result<Signature> {
+        let mut signers = vec![&self.payer];
+        signers.extend(extra_signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &signers,
+            self.rpc.get_latest_blockhash()?,
+        );
+        Ok(self.rpc.send_and_confirm_transaction(&transaction)?)
+    }
+
+    /// simulates `instructions`, returning the return data
+    fn simulate(&self, instructions: &[Instruction]) -> Result<Vec<u8>> {
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &[&self.payer],
+            self.rpc.get_latest_blockhash()?,
+        );
+        let result = self.rpc.simulate_transaction(&transaction)?.value;
+        if let Some(err) = result.err {
+            bail!("simulation failed: {err}");
+        }
+        let (data, _) = result.return_data.ok_or_else(|| anyhow!("no return data"))?.data;
+        Ok(BASE64_STANDARD.decode(data)?)
+    }
+
+    fn account_exists(&self, address: &Pubkey) -> Result<bool> {
+        Ok(self.rpc.get_account_with_commitment(address, self.rpc.commitment())?.value.is_some())
+    }
+}
+
+fn bootstrap(context: &CliContext, skip_deploy: bool, settings: &InitializeSettings) -> Result<()> {
+    let addresses = &context.addresses;
+    let payer = context.payer.pubkey();
+
+    if !skip_deploy {
+        for (program_keypair, program_so) in context.profile.program_keypairs_and_binaries()? {
+            deploy(&context.profile, &program_keypair, &program_so)?;
+        }
+    }
+
+    if !context.account_exists(&addresses.global_data)? {
+        // creates the mint and the transfer hook's extra account metas too
+        let mint = context.profile.mint_keypair()?;
+        if mint.pubkey() != addresses.mint {
+            bail!("`mint` does not match `mint_keypair`");
+        }
+        let instruction = instructions::initialize_comptoken_program(addresses, &payer, settings);
+        let signature = context.send(&[instruction], &[&mint])?;
+        context.print_transaction("initialize comptoken program", signature)?;
+    }
+    Ok(())
+}
+
+fn deploy(profile: &Profile, program_keypair: &PathBuf, program_so: &PathBuf) -> Result<()> {
+    let mut command = Command::new("solana");
+    command.args(["program", "deploy", "--url", &profile.json_rpc_url, "--program-id"]);
+    command.arg(program_keypair).arg(program_so);
+    if let Some(keypair) = &profile.keypair {
+        command.arg("--keypair").arg(keypair);
+    }
+    let status = command.status().context("failed to run `solana program deploy`")?;
+    if !status.success() {
+        bail!("failed to deploy {}", program_so.display());
+    }
+    Ok(())
+}
+
+fn create_user_data(context: &CliContext, wallet: &Pubkey, capacity: usize) -> Result<()> {
+    if capacity == 0 {
+        bail!("capacity must be at least 1");
+    }
+    let payer = context.payer.pubkey();
+    let mut instructions = Vec::new();
+    if !context.account_exists(&context.addresses.user_identity(&payer))? {
+        instructions.push(instructions::create_user_identity(&context.addresses, &payer));
+    }
+    instructions.push(instructions::create_user_data_account(&context.addresses, &payer, wallet, capacity as u64));
+    let signature = context.send(&instructions, &[])?;
+    context.print_transaction("create user data", signature)
+}
+
+fn transfer(context: &CliContext, source: &Pubkey, destination: &Pubkey, amount: u64) -> Result<()> {
+    // the transfer hook rejects transfers unless both wallets have claimed everything they are owed
+    let data = context.rpc.get_account_data(&context.addresses.global_data)?;
+    let last_distribution_day =
+        GlobalDataAccount::from_bytes(&data)?.daily_distribution_data.last_daily_distribution_time;
+    let mut instructions = Vec::new();
+    for wallet in [source, destination] {
+        let data = context.rpc.get_account_data(&context.addresses.user_data(wallet))?;
+        let user_data = UserDataAccount::from_bytes(&data)?;
+        // a wallet is current once it has claimed up to the last distribution, the same as on-chain
+        if user_data.last_interest_payout_date < last_distribution_day {
+            instructions.push(instructions::get_owed_comptokens(&context.addresses, wallet, &user_data.user_identity));
+        }
+    }
+    instructions.push(instructions::transfer(
+        &context.addresses,
+        source,
+        destination,
+        &context.payer.pubkey(),
+        amount,
+    )?);
+    let signature = context.send(&instructions, &[])?;
+    context.print_transaction("transfer", signature)
+}
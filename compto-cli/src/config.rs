@@ -0,0 +1,164 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+};
+
+// profiles are read from `~/.config/compto/config.toml` unless `--config` is given, e.g.
+//
+//  [profiles.localnet]
+//  json_rpc_url = "http://localhost:8899"
+//  keypair = "~/.config/solana/id.json"
+//  comptoken_program_id = "..."
+//  transfer_hook_program_id = "..."
+//  mint = "..."
+//
+// every comptoken instance is keyed by its mint, so `mint` picks which instance the profile talks to
+#[derive(Debug, Default, Deserialize)]
+pub struct ConfigFile {
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Profile {
+    pub json_rpc_url: String,
+    pub keypair: Option<PathBuf>,
+    #[serde(default = "default_commitment")]
+    pub commitment: String,
+    pub comptoken_program_id: Option<String>,
+    pub transfer_hook_program_id: Option<String>,
+    // may be left out if `mint_keypair` is given
+    pub mint: Option<String>,
+    // only needed by `bootstrap`
+    pub comptoken_program_keypair: Option<PathBuf>,
+    pub mint_keypair: Option<PathBuf>,
+    pub transfer_hook_program_keypair: Option<PathBuf>,
+    pub comptoken_so: Option<PathBuf>,
+    pub transfer_hook_so: Option<PathBuf>,
+}
+
+fn default_commitment() -> String {
+    "confirmed".to_string()
+}
+
+impl Profile {
+    fn builtin(name: &str) -> Option<Self> {
+        let json_rpc_url = match name {
+            "localnet" => "http://localhost:8899",
+            "devnet" => "https://api.devnet.solana.com",
+            "testnet" => "https://api.testnet.solana.com",
+            "mainnet-beta" => "https://api.mainnet-beta.solana.com",
+            _ => return None,
+        };
+        Some(Profile {
+            json_rpc_url: json_rpc_url.to_string(),
+            keypair: None,
+            commitment: default_commitment(),
+            comptoken_program_id: None,
+            transfer_hook_program_id: None,
+            mint: None,
+            comptoken_program_keypair: None,
+            mint_keypair: None,
+            transfer_hook_program_keypair: None,
+            comptoken_so: None,
+            transfer_hook_so: None,
+        })
+    }
+
+    pub fn load(config_path: Option<&PathBuf>, name: &str) -> Result<Self> {
+        let path = match config_path {
+            Some(path) => path.clone(),
+            None => home_dir()?.join(".config/compto/config.toml"),
+        };
+        let config: ConfigFile = match std::fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents).with_context(|| format!("invalid config {}", path.display()))?,
+            // a missing default config is fine, the builtin cluster profiles still work
+            Err(_) if config_path.is_none() => ConfigFile::default(),
+            Err(err) => return Err(err).with_context(|| format!("failed to read {}", path.display())),
+        };
+        config
+            .profiles
+            .get(name)
+            .cloned()
+            .or_else(|| Profile::builtin(name))
+            .ok_or_else(|| anyhow!("unknown profile `{name}`"))
+    }
+
+    pub fn commitment(&self) -> Result<CommitmentConfig> {
+        CommitmentConfig::from_str(&self.commitment).map_err(|_| anyhow!("invalid commitment `{}`", self.commitment))
+    }
+
+    pub fn payer(&self) -> Result<Keypair> {
+        let path = match &self.keypair {
+            Some(path) => expand_tilde(path)?,
+            None => home_dir()?.join(".config/solana/id.json"),
+        };
+        read_keypair(&path)
+    }
+
+    pub fn comptoken_program_id(&self) -> Result<Pubkey> {
+        parse_pubkey("comptoken_program_id", &self.comptoken_program_id)
+    }
+
+    pub fn transfer_hook_program_id(&self) -> Result<Pubkey> {
+        parse_pubkey("transfer_hook_program_id", &self.transfer_hook_program_id)
+    }
+
+    pub fn mint(&self) -> Result<Pubkey> {
+        match (&self.mint, &self.mint_keypair) {
+            (None, Some(_)) => Ok(self.mint_keypair()?.pubkey()),
+            (mint, _) => parse_pubkey("mint", mint),
+        }
+    }
+
+    /// the new instance's mint, which signs for its own creation
+    pub fn mint_keypair(&self) -> Result<Keypair> {
+        read_keypair(&expand_tilde(required("mint_keypair", &self.mint_keypair)?)?)
+    }
+
+    pub fn program_keypairs_and_binaries(&self) -> Result<[(PathBuf, PathBuf); 2]> {
+        Ok([
+            (
+                expand_tilde(required("comptoken_program_keypair", &self.comptoken_program_keypair)?)?,
+                expand_tilde(required("comptoken_so", &self.comptoken_so)?)?,
+            ),
+            (
+                expand_tilde(required("transfer_hook_program_keypair", &self.transfer_hook_program_keypair)?)?,
+                expand_tilde(required("transfer_hook_so", &self.transfer_hook_so)?)?,
+            ),
+        ])
+    }
+}
+
+fn required<'a, T>(name: &str, value: &'a Option<T>) -> Result<&'a T> {
+    value.as_ref().ok_or_else(|| anyhow!("profile is missing `{name}`"))
+}
+
+fn parse_pubkey(name: &str, value: &Option<String>) -> Result<Pubkey> {
+    let value = required(name, value)?;
+    Pubkey::from_str(value).map_err(|_| anyhow!("`{name}` is not a valid pubkey: {value}"))
+}
+
+fn read_keypair(path: &Path) -> Result<Keypair> {
+    read_keypair_file(path).map_err(|err| anyhow!("failed to read keypair {}: {err}", path.display()))
+}
+
+fn home_dir() -> Result<PathBuf> {
+    std::env::var_os("HOME").map(PathBuf::from).ok_or_else(|| anyhow!("$HOME is not set"))
+}
+
+fn expand_tilde(path: &Path) -> Result<PathBuf> {
+    match path.strip_prefix("~") {
+        Ok(rest) => Ok(home_dir()?.join(rest)),
+        Err(_) => Ok(path.to_path_buf()),
+    }
+}
@@ -0,0 +1,357 @@
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+    sysvar::slot_hashes,
+};
+use spl_token_2022::instruction::transfer_checked;
+
+use comptoken::query::QuerySelector;
+
+use crate::config::Profile;
+
+// ensure these remain consistent with the match in comptoken.rs
+pub mod comptoken_instruction {
+    pub const INITIALIZE_COMPTOKEN_PROGRAM: u8 = 2;
+    pub const CREATE_USER_DATA_ACCOUNT: u8 = 3;
+    pub const DAILY_DISTRIBUTION_EVENT: u8 = 4;
+    pub const GET_OWED_COMPTOKENS: u8 = 6;
+    pub const GET_DISTRIBUTION_STATUS: u8 = 7;
+    pub const UPDATE_COMPTOKEN_METADATA: u8 = 8;
+    pub const CREATE_USER_IDENTITY: u8 = 9;
+    pub const SET_PROOF_SCHEME: u8 = 10;
+    pub const AUDIT: u8 = 11;
+    pub const PREVIEW_OWED_COMPTOKENS: u8 = 12;
+    pub const QUERY_GLOBAL_DATA: u8 = 13;
+}
+
+/// The token metadata fields the admin can update
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum MetadataField {
+    Name,
+    Symbol,
+    Uri,
+}
+
+/// The hash functions proofs can be mined with, ensure this remains consistent with proof_scheme.rs
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum ProofScheme {
+    Sha256,
+    Keccak256,
+    Blake3,
+}
+
+/// The parts of the global data that can be queried
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum QuerySelection {
+    /// the settings the program was initialized with
+    Parameters,
+    /// the high water mark, yesterday's supply, and the current interest and APY
+    Distribution,
+    /// every interest rate, most recent first
+    InterestHistory,
+    /// mining and distribution stats for recent days, most recent first
+    StatsHistory,
+    /// when the blockhashes were announced and became valid
+    BlockhashTimes,
+}
+
+impl From<QuerySelection> for QuerySelector {
+    fn from(selection: QuerySelection) -> Self {
+        match selection {
+            QuerySelection::Parameters => QuerySelector::Parameters,
+            QuerySelection::Distribution => QuerySelector::Distribution,
+            QuerySelection::InterestHistory => QuerySelector::InterestHistory,
+            QuerySelection::StatsHistory => QuerySelector::StatsHistory,
+            QuerySelection::BlockhashTimes => QuerySelector::BlockhashTimes,
+        }
+    }
+}
+
+/// The settings the comptoken program is initialized with
+#[derive(Debug, Clone, clap::Args)]
+pub struct InitializeSettings {
+    /// comptokens paid to whoever runs the daily distribution
+    #[arg(long, default_value_t = 0)]
+    pub distribution_bounty: u64,
+    /// seconds proofs against the previous valid blockhash are still accepted after it changes
+    #[arg(long, default_value_t = 300)]
+    pub blockhash_grace_period: i64,
+    /// unix time until which unversioned (v1) proofs are still accepted
+    #[arg(long, default_value_t = 0)]
+    pub v1_proof_deadline: i64,
+    /// seconds in a comptoken day; shorten it on test networks to exercise distributions quickly
+    #[arg(long, default_value_t = 86_400)]
+    pub day_length: i64,
+    /// seconds after a multiple of the day length that each day starts
+    #[arg(long, default_value_t = 0)]
+    pub day_offset: i64,
+    /// the token metadata name
+    #[arg(long, default_value = "Comptoken")]
+    pub name: String,
+    /// the token metadata symbol
+    #[arg(long, default_value = "CPT")]
+    pub symbol: String,
+    /// the token metadata uri
+    #[arg(long, default_value = "")]
+    pub uri: String,
+}
+
+// MAGIC NUMBER: ensure this remains consistent with constants.rs
+pub const MINT_DECIMALS: u8 = 0;
+
+/// Every address the cli needs, derived from the program ids and mint in a profile
+#[derive(Debug, Clone)]
+pub struct Addresses {
+    pub comptoken_program: Pubkey,
+    pub transfer_hook_program: Pubkey,
+    pub mint: Pubkey,
+    pub global_data: Pubkey,
+    pub interest_bank: Pubkey,
+    pub ubi_bank: Pubkey,
+    pub extra_account_metas: Pubkey,
+}
+
+impl Addresses {
+    pub fn from_profile(profile: &Profile) -> anyhow::Result<Self> {
+        let comptoken_program = profile.comptoken_program_id()?;
+        let transfer_hook_program = profile.transfer_hook_program_id()?;
+        let mint = profile.mint()?;
+        let pda = |seeds: &[&[u8]], program| Pubkey::find_program_address(seeds, program).0;
+        Ok(Addresses {
+            comptoken_program,
+            transfer_hook_program,
+            mint,
+            global_data: pda(&[b"Global Data", mint.as_ref()], &comptoken_program),
+            interest_bank: pda(&[b"Interest Bank", mint.as_ref()], &comptoken_program),
+            ubi_bank: pda(&[b"UBI Bank", mint.as_ref()], &comptoken_program),
+            extra_account_metas: pda(&[b"extra-account-metas", mint.as_ref()], &transfer_hook_program),
+        })
+    }
+
+    pub fn user_data(&self, user_comptoken_wallet: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[user_comptoken_wallet.as_ref()], &self.comptoken_program).0
+    }
+
+    pub fn user_identity(&self, owner: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"User Identity", self.mint.as_ref(), owner.as_ref()], &self.comptoken_program).0
+    }
+
+    // the transfer hook requires a "user data" account for the banks, but they don't actually exist
+    fn bank_data(&self, bank: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[bank.as_ref()], &self.comptoken_program).0
+    }
+
+    /// the accounts the transfer hook needs for a transfer from `source` to `destination`
+    fn transfer_hook_accounts(&self, source: &Pubkey, destination: &Pubkey) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(self.extra_account_metas, false),
+            AccountMeta::new_readonly(self.transfer_hook_program, false),
+            AccountMeta::new_readonly(self.comptoken_program, false),
+            // the comptoken program settles both wallets when the transfer hook runs
+            AccountMeta::new(self.user_data(source), false),
+            AccountMeta::new(self.user_data(destination), false),
+            AccountMeta::new_readonly(self.global_data, false),
+        ]
+    }
+}
+
+// strings are encoded as a u32 length followed by their utf-8 bytes
+fn extend_string(data: &mut Vec<u8>, string: &str) {
+    data.extend((string.len() as u32).to_le_bytes());
+    data.extend(string.as_bytes());
+}
+
+pub fn initialize_comptoken_program(
+    addresses: &Addresses, payer: &Pubkey, settings: &InitializeSettings,
+) -> Instruction {
+    let mut data = vec![comptoken_instruction::INITIALIZE_COMPTOKEN_PROGRAM];
+    data.extend(settings.distribution_bounty.to_le_bytes());
+    data.extend(settings.blockhash_grace_period.to_le_bytes());
+    data.extend(settings.v1_proof_deadline.to_le_bytes());
+    data.extend(settings.day_length.to_le_bytes());
+    data.extend(settings.day_offset.to_le_bytes());
+    extend_string(&mut data, &settings.name);
+    extend_string(&mut data, &settings.symbol);
+    extend_string(&mut data, &settings.uri);
+    Instruction {
+        program_id: addresses.comptoken_program,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(addresses.global_data, false),
+            AccountMeta::new(addresses.interest_bank, false),
+            AccountMeta::new(addresses.ubi_bank, false),
+            AccountMeta::new(addresses.mint, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token_2022::ID, false),
+            AccountMeta::new_readonly(slot_hashes::ID, false),
+            AccountMeta::new_readonly(addresses.transfer_hook_program, false),
+            AccountMeta::new(addresses.extra_account_metas, false),
+        ],
+        data,
+    }
+}
+
+pub fn update_comptoken_metadata(
+    addresses: &Addresses, admin: &Pubkey, field: MetadataField, value: &str,
+) -> Instruction {
+    let mut data = vec![comptoken_instruction::UPDATE_COMPTOKEN_METADATA, field as u8];
+    extend_string(&mut data, value);
+    Instruction {
+        program_id: addresses.comptoken_program,
+        accounts: vec![
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(addresses.mint, false),
+            AccountMeta::new_readonly(addresses.global_data, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token_2022::ID, false),
+        ],
+        data,
+    }
+}
+
+pub fn set_proof_scheme(addresses: &Addresses, admin: &Pubkey, scheme: ProofScheme) -> Instruction {
+    Instruction {
+        program_id: addresses.comptoken_program,
+        accounts: vec![AccountMeta::new(*admin, true), AccountMeta::new(addresses.global_data, false)],
+        data: vec![comptoken_instruction::SET_PROOF_SCHEME, scheme as u8],
+    }
+}
+
+pub fn create_user_data_account(
+    addresses: &Addresses, payer: &Pubkey, user_comptoken_wallet: &Pubkey, proof_capacity: u64,
+) -> Instruction {
+    let mut data = vec![comptoken_instruction::CREATE_USER_DATA_ACCOUNT];
+    data.extend(proof_capacity.to_le_bytes());
+    Instruction {
+        program_id: addresses.comptoken_program,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(addresses.user_data(user_comptoken_wallet), false),
+            AccountMeta::new_readonly(*user_comptoken_wallet, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(addresses.user_identity(payer), false),
+            AccountMeta::new_readonly(addresses.global_data, false),
+        ],
+        data,
+    }
+}
+
+pub fn create_user_identity(addresses: &Addresses, owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: addresses.comptoken_program,
+        accounts: vec![
+            AccountMeta::new(*owner, true),
+            AccountMeta::new(addresses.user_identity(owner), false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(addresses.global_data, false),
+        ],
+        data: vec![comptoken_instruction::CREATE_USER_IDENTITY],
+    }
+}
+
+pub fn daily_distribution_event(addresses: &Addresses, caller_comptoken_wallet: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: addresses.comptoken_program,
+        accounts: vec![
+            AccountMeta::new(addresses.mint, false),
+            AccountMeta::new(addresses.global_data, false),
+            AccountMeta::new(addresses.interest_bank, false),
+            AccountMeta::new(addresses.ubi_bank, false),
+            AccountMeta::new_readonly(spl_token_2022::ID, false),
+            AccountMeta::new_readonly(slot_hashes::ID, false),
+            AccountMeta::new(*caller_comptoken_wallet, false),
+            AccountMeta::new(addresses.user_data(caller_comptoken_wallet), false),
+        ],
+        data: vec![comptoken_instruction::DAILY_DISTRIBUTION_EVENT],
+    }
+}
+
+pub fn get_distribution_status(addresses: &Addresses) -> Instruction {
+    Instruction {
+        program_id: addresses.comptoken_program,
+        accounts: vec![AccountMeta::new_readonly(addresses.global_data, false)],
+        data: vec![comptoken_instruction::GET_DISTRIBUTION_STATUS],
+    }
+}
+
+pub fn audit(addresses: &Addresses) -> Instruction {
+    Instruction {
+        program_id: addresses.comptoken_program,
+        accounts: vec![
+            AccountMeta::new_readonly(addresses.mint, false),
+            AccountMeta::new_readonly(addresses.global_data, false),
+            AccountMeta::new_readonly(addresses.interest_bank, false),
+            AccountMeta::new_readonly(addresses.ubi_bank, false),
+        ],
+        data: vec![comptoken_instruction::AUDIT],
+    }
+}
+
+pub fn query_global_data(addresses: &Addresses, selector: QuerySelector, page: u16) -> Instruction {
+    let mut data = vec![comptoken_instruction::QUERY_GLOBAL_DATA, selector as u8];
+    data.extend(page.to_le_bytes());
+    Instruction {
+        program_id: addresses.comptoken_program,
+        accounts: vec![AccountMeta::new_readonly(addresses.global_data, false)],
+        data,
+    }
+}
+
+/// `user_identity` is the identity the wallet's user data links to
+pub fn get_owed_comptokens(
+    addresses: &Addresses, user_comptoken_wallet: &Pubkey, user_identity: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: addresses.comptoken_program,
+        accounts: vec![
+            AccountMeta::new(addresses.user_data(user_comptoken_wallet), false),
+            AccountMeta::new(*user_comptoken_wallet, false),
+            AccountMeta::new_readonly(addresses.mint, false),
+            AccountMeta::new(addresses.global_data, false),
+            AccountMeta::new(addresses.interest_bank, false),
+            AccountMeta::new(addresses.ubi_bank, false),
+            AccountMeta::new_readonly(spl_token_2022::ID, false),
+            AccountMeta::new_readonly(addresses.extra_account_metas, false),
+            AccountMeta::new_readonly(addresses.transfer_hook_program, false),
+            AccountMeta::new_readonly(addresses.comptoken_program, false),
+            AccountMeta::new(addresses.bank_data(&addresses.interest_bank), false),
+            AccountMeta::new(addresses.bank_data(&addresses.ubi_bank), false),
+            AccountMeta::new(*user_identity, false),
+        ],
+        data: vec![comptoken_instruction::GET_OWED_COMPTOKENS],
+    }
+}
+
+/// `user_identity` is the identity the wallet's user data links to
+pub fn preview_owed_comptokens(
+    addresses: &Addresses, user_comptoken_wallet: &Pubkey, user_identity: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: addresses.comptoken_program,
+        accounts: vec![
+            AccountMeta::new_readonly(addresses.user_data(user_comptoken_wallet), false),
+            AccountMeta::new_readonly(*user_comptoken_wallet, false),
+            AccountMeta::new_readonly(addresses.global_data, false),
+            AccountMeta::new_readonly(*user_identity, false),
+        ],
+        data: vec![comptoken_instruction::PREVIEW_OWED_COMPTOKENS],
+    }
+}
+
+pub fn transfer(
+    addresses: &Addresses, source: &Pubkey, destination: &Pubkey, authority: &Pubkey, amount: u64,
+) -> anyhow::Result<Instruction> {
+    let mut instruction = transfer_checked(
+        &spl_token_2022::ID,
+        source,
+        &addresses.mint,
+        destination,
+        authority,
+        &[],
+        amount,
+        MINT_DECIMALS,
+    )?;
+    instruction.accounts.extend(addresses.transfer_hook_accounts(source, destination));
+    Ok(instruction)
+}
@@ -1,4 +1,3 @@
-mod generated;
 mod verify_accounts;
 
 use spl_tlv_account_resolution::{account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList};
@@ -6,31 +5,39 @@ use spl_token_2022::solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint,
     entrypoint::ProgramResult,
-    msg, pubkey,
+    instruction::{AccountMeta, Instruction},
+    msg,
+    program::invoke,
     pubkey::Pubkey,
     rent::Rent,
     sysvar::Sysvar,
 };
 use spl_transfer_hook_interface::instruction::{ExecuteInstruction, TransferHookInstruction};
 
-use comptoken_utils::{create_pda, user_data::UserData};
-
-use generated::{
-    COMPTOKEN_ID, COMPTO_INTEREST_BANK_ACCOUNT_PUBKEY, COMPTO_UBI_BANK_ACCOUNT_PUBKEY,
-    EXTRA_ACCOUNT_METAS_ACCOUNT_SEEDS,
+use comptoken_utils::{
+    config::{Config, EXTRA_ACCOUNT_METAS_SEED},
+    create_pda, verbose_msg,
 };
+
 use verify_accounts::{
-    verify_account_meta_storage_account, verify_comptoken_mint, verify_comptoken_program, verify_destination_account,
-    verify_mint_account, verify_mint_authority, verify_source_account, verify_source_authority_account,
-    verify_user_data_account, VerifiedAccountInfo,
+    verify_account_meta_storage_account, verify_comptoken_program, verify_destination_account,
+    verify_global_data_account, verify_mint_account, verify_mint_authority, verify_new_account_meta_storage_account,
+    verify_source_account, verify_source_authority_account, verify_user_data_account, VerifiedAccountInfo,
 };
 
+// MAGIC NUMBER: ensure this remains consistent with the dispatch in comptoken.rs
+const SETTLE_TRANSFER_INSTRUCTION: u8 = 14;
+
 entrypoint!(process_instruction);
 pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
-    msg!("{:?}", instruction_data);
+    verbose_msg!("instruction_data: {:?}", instruction_data);
     match TransferHookInstruction::unpack(instruction_data)? {
-        TransferHookInstruction::Execute { amount } => process_execute(program_id, accounts, amount),
+        TransferHookInstruction::Execute { amount } => {
+            msg!("Execute");
+            process_execute(program_id, accounts, amount)
+        }
         TransferHookInstruction::InitializeExtraAccountMetaList { extra_account_metas } => {
+            msg!("Initialize Extra Account Meta List");
             process_initialize_extra_account_meta_list(program_id, accounts, extra_account_metas)
         }
         TransferHookInstruction::UpdateExtraAccountMetaList { extra_account_metas: _ } => {
@@ -47,35 +54,77 @@ fn process_execute(program_id: &Pubkey, accounts: &[AccountInfo], _amount: u64)
     //      []: Source token account authority
     //      []: account meta storage account
     //      []: Comptoken Program
-    //      []: Source Data Account
-    //      []: Destination Data Account
+    //      [w]: Source Data Account
+    //      [w]: Destination Data Account
+    //      []: Comptoken Global Data (holds the instance's config)
 
     let account_info_iter = &mut accounts.iter();
-    let source_account = verify_source_account(next_account_info(account_info_iter)?);
+    let source_account = next_account_info(account_info_iter)?;
+    let mint_account = next_account_info(account_info_iter)?;
+    let destination_account = next_account_info(account_info_iter)?;
+    let source_account_authority = next_account_info(account_info_iter)?;
+    let account_meta_storage_account = next_account_info(account_info_iter)?;
+    let comptoken_program = next_account_info(account_info_iter)?;
+    let source_data_account = next_account_info(account_info_iter)?;
+    let destination_data_account = next_account_info(account_info_iter)?;
+    let global_data_account = next_account_info(account_info_iter)?;
+
     // required as part of the transferhook API to identify that comptokens are being transferred
-    let _comptoken_mint_account = verify_comptoken_mint(next_account_info(account_info_iter)?);
-    let destination_account = verify_destination_account(next_account_info(account_info_iter)?);
+    let mint_account = verify_mint_account(mint_account);
+    // the mint authority is the instance's global data, which identifies the rest of the instance
+    let global_data_account = verify_global_data_account(global_data_account, &mint_account);
+    let config: &Config = (&global_data_account).into();
+    let source_account = verify_source_account(source_account);
+    let destination_account = verify_destination_account(destination_account);
     // also required as part of the transferhook API but we don't use
-    let _source_account_authority = verify_source_authority_account(next_account_info(account_info_iter)?);
+    let _source_account_authority = verify_source_authority_account(source_account_authority);
     // used by transferhook to get the comptoken program and the PDAs before it gets here
     let _account_meta_storage_account =
-        verify_account_meta_storage_account(next_account_info(account_info_iter)?, program_id, false);
+        verify_account_meta_storage_account(account_meta_storage_account, config, program_id, false);
     // used by transferhook to generate the PDAs before it gets here
-    let _comptoken_program = verify_comptoken_program(next_account_info(account_info_iter)?);
-    let source_data_account = verify_user_data_account(next_account_info(account_info_iter)?, &source_account);
+    let comptoken_program = verify_comptoken_program(comptoken_program, &global_data_account);
+    let source_data_account =
+        verify_user_data_account(source_data_account, &source_account, config, comptoken_program.key);
     let destination_data_account =
-        verify_user_data_account(next_account_info(account_info_iter)?, &destination_account);
-
-    // Account must either be a bank account or have no unpaid interest or UBI amounts to do a transfer
-    if !is_bank(source_account.key) {
-        let source_user_data: &UserData = (&source_data_account).into();
-        assert!(source_user_data.is_current());
-        if !is_bank(destination_account.key) {
-            let destination_user_data: &UserData = (&destination_data_account).into();
-            assert!(destination_user_data.is_current());
-        }
+        verify_user_data_account(destination_data_account, &destination_account, config, comptoken_program.key);
+
+    // transfers out of the banks are made by the comptoken program, which settles the destination itself (and can't be
+    // reentered). any other transfer is settled by the comptoken program, which also rejects it unless both wallets
+    // have no unpaid interest or UBI amounts
+    if config.is_bank(source_account.key) {
+        return Ok(());
     }
-    Ok(())
+    settle_transfer(
+        &comptoken_program,
+        [
+            &source_account,
+            &destination_account,
+            &source_data_account,
+            &destination_data_account,
+            &global_data_account,
+        ],
+    )
+}
+
+/// `accounts` are the source and destination wallets, their user data, and the global data
+fn settle_transfer<'a>(
+    comptoken_program: &VerifiedAccountInfo<'a>, accounts: [&VerifiedAccountInfo<'a>; 5],
+) -> ProgramResult {
+    let [source, destination, source_data, destination_data, global_data] = accounts;
+    let instruction = Instruction {
+        program_id: *comptoken_program.key,
+        accounts: vec![
+            AccountMeta::new_readonly(*source.key, false),
+            AccountMeta::new_readonly(*destination.key, false),
+            AccountMeta::new(*source_data.key, false),
+            AccountMeta::new(*destination_data.key, false),
+            AccountMeta::new_readonly(*global_data.key, false),
+        ],
+        data: vec![SETTLE_TRANSFER_INSTRUCTION],
+    };
+    let account_infos = [source, destination, source_data, destination_data, global_data, comptoken_program]
+        .map(|account| account.0.clone());
+    invoke(&instruction, &account_infos)
 }
 
 fn process_initialize_extra_account_meta_list(
@@ -96,9 +145,11 @@ fn process_initialize_extra_account_meta_list(
     let payer_account = next_account_info(account_info_iter)?;
 
     let mint_account = verify_mint_account(mint_account);
-    let account_meta_storage_account =
-        verify_account_meta_storage_account(account_meta_storage_account, program_id, true);
-    let _mint_authority = verify_mint_authority(mint_authority, &mint_account, true, false);
+    let (account_meta_storage_account, bump) =
+        verify_new_account_meta_storage_account(account_meta_storage_account, &mint_account, program_id);
+    // the mint authority is the comptoken instance's global data, so it also identifies the comptoken program
+    let mint_authority = verify_mint_authority(mint_authority, &mint_account, true, false);
+    let comptoken_id = *mint_authority.owner;
     let payer_account = VerifiedAccountInfo::verify_account_signer_or_writable(payer_account, true, true);
 
     const SENDER_ACCOUNT_INDEX: u8 = 0;
@@ -110,28 +161,30 @@ fn process_initialize_extra_account_meta_list(
 
     let account_metas = vec![
         // index: 5
-        ExtraAccountMeta::new_with_pubkey(&COMPTOKEN_ID, false, false)?,
-        // index: 6
+        ExtraAccountMeta::new_with_pubkey(&comptoken_id, false, false)?,
+        // index: 6 (writable, so the comptoken program can settle the transfer)
         ExtraAccountMeta::new_external_pda_with_seeds(
             COMPTOKEN_PROGRAM_INDEX,
             &[Seed::AccountKey { index: SENDER_ACCOUNT_INDEX }],
             false,
-            false,
+            true,
         )?,
-        // index: 7
+        // index: 7 (writable, so the comptoken program can settle the transfer)
         ExtraAccountMeta::new_external_pda_with_seeds(
             COMPTOKEN_PROGRAM_INDEX,
             &[Seed::AccountKey { index: RECEIVER_ACCOUNT_INDEX }],
             false,
-            false,
+            true,
         )?,
+        // index: 8
+        ExtraAccountMeta::new_with_pubkey(mint_authority.key, false, false)?,
     ];
 
     let account_size = ExtraAccountMetaList::size_of(account_metas.len())? as u64;
 
     let lamports = Rent::get()?.minimum_balance(account_size as usize);
 
-    let signer_seeds: &[&[&[u8]]] = &[EXTRA_ACCOUNT_METAS_ACCOUNT_SEEDS];
+    let signer_seeds: &[&[&[u8]]] = &[&[EXTRA_ACCOUNT_METAS_SEED, mint_account.key.as_ref(), &[bump]]];
 
     create_pda(&payer_account, &account_meta_storage_account, lamports, account_size, program_id, signer_seeds)?;
 
@@ -142,7 +195,3 @@ fn process_initialize_extra_account_meta_list(
 
     Ok(())
 }
-
-fn is_bank(address: &Pubkey) -> bool {
-    *address == COMPTO_INTEREST_BANK_ACCOUNT_PUBKEY || *address == COMPTO_UBI_BANK_ACCOUNT_PUBKEY
-}
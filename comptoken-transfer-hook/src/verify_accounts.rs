@@ -5,16 +5,24 @@ use spl_token_2022::{
 };
 
 pub use comptoken_utils::verify_accounts::VerifiedAccountInfo;
+use comptoken_utils::{
+    config::{Config, EXTRA_ACCOUNT_METAS_SEED},
+    user_data::UserData,
+};
 
-use crate::generated::{COMPTOKEN_ID, EXTRA_ACCOUNT_METAS_ACCOUNT_SEEDS, MINT_ADDRESS};
+pub fn verify_new_account_meta_storage_account<'a>(
+    account: &AccountInfo<'a>, mint: &VerifiedAccountInfo, program_id: &Pubkey,
+) -> (VerifiedAccountInfo<'a>, u8) {
+    VerifiedAccountInfo::verify_pda(account, program_id, &[EXTRA_ACCOUNT_METAS_SEED, mint.key.as_ref()], false, true)
+}
 
 pub fn verify_account_meta_storage_account<'a>(
-    account: &AccountInfo<'a>, program_id: &Pubkey, needs_writable: bool,
+    account: &AccountInfo<'a>, config: &Config, program_id: &Pubkey, needs_writable: bool,
 ) -> VerifiedAccountInfo<'a> {
     VerifiedAccountInfo::verify_pda_with_bump(
         account,
         program_id,
-        EXTRA_ACCOUNT_METAS_ACCOUNT_SEEDS,
+        &config.extra_account_metas_seeds(),
         false,
         needs_writable,
     )
@@ -25,10 +33,6 @@ pub fn verify_mint_account<'a>(account: &AccountInfo<'a>) -> VerifiedAccountInfo
     VerifiedAccountInfo::verify_account_signer_or_writable(account, false, false)
 }
 
-pub fn verify_comptoken_mint<'a>(account: &AccountInfo<'a>) -> VerifiedAccountInfo<'a> {
-    VerifiedAccountInfo::verify_specific_address(account, &MINT_ADDRESS, false, false)
-}
-
 pub fn verify_mint_authority<'a>(
     account: &AccountInfo<'a>, mint: &VerifiedAccountInfo, needs_signer: bool, needs_writable: bool,
 ) -> VerifiedAccountInfo<'a> {
@@ -38,6 +42,16 @@ pub fn verify_mint_authority<'a>(
     VerifiedAccountInfo::verify_account_signer_or_writable(account, needs_signer, needs_writable)
 }
 
+pub fn verify_global_data_account<'a>(
+    account: &AccountInfo<'a>, mint: &VerifiedAccountInfo,
+) -> VerifiedAccountInfo<'a> {
+    // the global data is the mint authority, and its config must agree that this is its mint
+    let global_data_account = verify_mint_authority(account, mint, false, false);
+    let config: &Config = (&global_data_account).into();
+    assert_eq!(config.mint, *mint.key, "global data belongs to this mint");
+    global_data_account
+}
+
 pub fn verify_source_account<'a>(account: &AccountInfo<'a>) -> VerifiedAccountInfo<'a> {
     VerifiedAccountInfo::verify_account_signer_or_writable(account, false, false)
 }
@@ -50,12 +64,41 @@ pub fn verify_source_authority_account<'a>(account: &AccountInfo<'a>) -> Verifie
     VerifiedAccountInfo::verify_account_signer_or_writable(account, false, false)
 }
 
-pub fn verify_comptoken_program<'a>(account: &AccountInfo<'a>) -> VerifiedAccountInfo<'a> {
-    VerifiedAccountInfo::verify_specific_address(account, &COMPTOKEN_ID, false, false)
+pub fn verify_comptoken_program<'a>(
+    account: &AccountInfo<'a>, global_data: &VerifiedAccountInfo,
+) -> VerifiedAccountInfo<'a> {
+    // the comptoken program is whichever program owns the instance's global data
+    VerifiedAccountInfo::verify_specific_address(account, global_data.owner, false, false)
 }
 
 pub fn verify_user_data_account<'a>(
-    account: &AccountInfo<'a>, user_account: &VerifiedAccountInfo<'a>,
+    account: &AccountInfo<'a>, user_account: &VerifiedAccountInfo<'a>, config: &Config, comptoken_id: &Pubkey,
 ) -> VerifiedAccountInfo<'a> {
-    VerifiedAccountInfo::verify_pda(account, &COMPTOKEN_ID, &[user_account.key.as_ref()], false, false).0
+    // writable so the comptoken program can settle the transfer
+    // the banks' "user data" accounts don't actually exist, so their bumps are stored in the config instead
+    if *user_account.key == config.interest_bank {
+        return VerifiedAccountInfo::verify_pda_with_bump(
+            account,
+            comptoken_id,
+            &config.interest_bank_data_seeds(),
+            false,
+            true,
+        );
+    }
+    if *user_account.key == config.ubi_bank {
+        return VerifiedAccountInfo::verify_pda_with_bump(
+            account,
+            comptoken_id,
+            &config.ubi_bank_data_seeds(),
+            false,
+            true,
+        );
+    }
+    assert_eq!(account.owner, comptoken_id, "user data is owned by the comptoken program");
+    let bump = {
+        let data = account.try_borrow_data().expect("account data is not borrowed");
+        let user_data: &UserData = data.as_ref().try_into().expect("valid user data");
+        user_data.bump
+    };
+    VerifiedAccountInfo::verify_pda_with_bump(account, comptoken_id, &[user_account.key.as_ref(), &[bump]], false, true)
 }
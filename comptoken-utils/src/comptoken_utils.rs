@@ -1,6 +1,10 @@
+pub mod config;
 pub mod user_data;
+pub mod user_identity;
 pub mod verify_accounts;
 
+use std::cell::Cell;
+
 use spl_token_2022::solana_program::{
     clock::Clock, entrypoint::ProgramResult, instruction::Instruction, program::invoke_signed, pubkey::Pubkey,
     system_instruction, sysvar::Sysvar,
@@ -10,6 +14,16 @@ use verify_accounts::VerifiedAccountInfo;
 
 pub const SEC_PER_DAY: i64 = 86_400;
 
+/// `msg!` that is only compiled in when the calling program enables its `verbose-logs` feature,
+/// since formatting logs is expensive in compute units
+#[macro_export]
+macro_rules! verbose_msg {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "verbose-logs")]
+        ::spl_token_2022::solana_program::msg!($($arg)+);
+    };
+}
+
 pub fn create_pda<'a>(
     payer: &VerifiedAccountInfo<'a>, new_account: &VerifiedAccountInfo<'a>, lamports: u64, space: u64, owner: &Pubkey,
     signers_seeds: &[&[&[u8]]],
@@ -27,10 +41,98 @@ pub fn invoke_signed_verified(
     invoke_signed(instruction, &account_refs, signers_seeds)
 }
 
-pub fn get_current_time() -> i64 {
-    Clock::get().unwrap().unix_timestamp
+/// Where time dependent logic gets the current unix time, so it can be run against a `MockClock` in host tests
+pub trait TimeSource {
+    fn now(&self) -> i64;
+
+    /// the start of the current day
+    fn today(&self, days: &Days) -> i64 {
+        days.start_of(self.now())
+    }
+}
+
+/// The clock sysvar, which is only available on chain
+pub struct SysvarClock;
+
+impl TimeSource for SysvarClock {
+    fn now(&self) -> i64 {
+        Clock::get().unwrap().unix_timestamp
+    }
+}
+
+/// A clock that only moves when told to, for driving time dependent logic through arbitrary timelines in tests
+#[derive(Debug, Default)]
+pub struct MockClock(Cell<i64>);
+
+impl MockClock {
+    pub fn new(time: i64) -> Self {
+        MockClock(Cell::new(time))
+    }
+
+    pub fn set(&self, time: i64) {
+        self.0.set(time);
+    }
+
+    pub fn advance(&self, seconds: i64) {
+        self.0.set(self.0.get() + seconds);
+    }
+}
+
+impl TimeSource for MockClock {
+    fn now(&self) -> i64 {
+        self.0.get()
+    }
+}
+
+/// How long a comptoken "day" is and what its start is aligned to. Interest, UBI, blockhash rotation and the daily
+/// distribution all happen once per day, so test deployments shorten them to exercise those without waiting.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Days {
+    // seconds
+    pub length: i64,
+    // seconds after each multiple of `length` since the unix epoch that a day starts
+    pub offset: i64,
+}
+
+impl Days {
+    /// days starting at midnight UTC+0
+    pub const UTC: Days = Days { length: SEC_PER_DAY, offset: 0 };
+
+    pub fn validate(&self) {
+        assert!(self.length > 0, "days must have a positive length");
+        assert!((0..self.length).contains(&self.offset), "day offset must be less than a day");
+    }
+
+    /// the start of the day `time` is in
+    pub fn start_of(&self, time: i64) -> i64 {
+        time - (time - self.offset).rem_euclid(self.length)
+    }
+
+    /// the whole days from `start` to `end`
+    pub fn between(&self, start: i64, end: i64) -> i64 {
+        (end - start) / self.length
+    }
 }
 
-pub fn normalize_time(time: i64) -> i64 {
-    time - time % SEC_PER_DAY // midnight UTC+0
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_days() {
+        assert_eq!(Days::UTC.start_of(3 * SEC_PER_DAY + 5), 3 * SEC_PER_DAY);
+        assert_eq!(Days::UTC.between(SEC_PER_DAY, 3 * SEC_PER_DAY + 5), 2);
+
+        // 10 minute days starting 2 minutes past every 10 minutes
+        let days = Days { length: 600, offset: 120 };
+        assert_eq!(days.start_of(600_120), 600_120);
+        assert_eq!(days.start_of(600_119), 599_520);
+        assert_eq!(days.start_of(600_719), 600_120);
+        assert_eq!(days.between(599_520, 600_720), 2);
+
+        let clock = MockClock::new(600_500);
+        assert_eq!(clock.today(&days), 600_120);
+        assert_eq!(clock.today(&Days::UTC), Days::UTC.start_of(600_500));
+    }
 }
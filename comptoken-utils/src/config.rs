@@ -0,0 +1,111 @@
+use std::slice;
+
+use spl_token_2022::solana_program::pubkey::Pubkey;
+
+use crate::{Days, TimeSource, VerifiedAccountInfo};
+
+pub const GLOBAL_DATA_SEED: &[u8] = b"Global Data";
+pub const INTEREST_BANK_SEED: &[u8] = b"Interest Bank";
+pub const UBI_BANK_SEED: &[u8] = b"UBI Bank";
+pub const EXTRA_ACCOUNT_METAS_SEED: &[u8] = b"extra-account-metas";
+
+/// The settings and addresses a comptoken instance is initialized with. Every instance is keyed by its mint, and this
+/// is the start of the instance's global data so the transfer hook can read it as well.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+// CHANGES TO THE SIZE OF THIS STRUCT NEED TO BE REFLECTED IN accounts.js
+pub struct Config {
+    // comptokens paid to whoever calls the daily distribution, taken out of that day's distribution
+    pub distribution_bounty: u64,
+    // seconds after the valid blockhash changes during which proofs against the previous one are still accepted
+    pub blockhash_grace_period: i64,
+    // may update the comptoken metadata
+    pub admin: Pubkey,
+    // unix time until which unversioned (v1) proofs are still accepted alongside v2 proofs
+    pub v1_proof_deadline: i64,
+    // how long a day is, which interest, UBI, blockhash rotation and the daily distribution are all based on
+    pub days: Days,
+    // the accounts making up this instance, fixed when it is initialized
+    pub mint: Pubkey,
+    pub transfer_hook_program: Pubkey,
+    pub interest_bank: Pubkey,
+    pub ubi_bank: Pubkey,
+    pub bumps: Bumps,
+    // padding: [u8; 2],
+}
+
+/// The bumps of the instance's PDAs, stored so they can be verified without searching for them
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Bumps {
+    pub global_data: u8,
+    pub interest_bank: u8,
+    pub ubi_bank: u8,
+    // the banks' "user data" accounts don't actually exist, but the transfer hook still needs their addresses
+    pub interest_bank_data: u8,
+    pub ubi_bank_data: u8,
+    // owned by the transfer hook program
+    pub extra_account_metas: u8,
+}
+
+pub const CONFIG_SIZE: usize = std::mem::size_of::<Config>();
+
+impl Config {
+    pub fn validate(&self) {
+        self.days.validate();
+        assert!(
+            (0..self.days.length).contains(&self.blockhash_grace_period),
+            "blockhash grace period must be less than a day"
+        );
+    }
+
+    pub fn accepts_v1_proofs(&self, clock: &impl TimeSource) -> bool {
+        clock.now() <= self.v1_proof_deadline
+    }
+
+    pub fn is_bank(&self, address: &Pubkey) -> bool {
+        *address == self.interest_bank || *address == self.ubi_bank
+    }
+
+    pub fn global_data_seeds(&self) -> [&[u8]; 3] {
+        [GLOBAL_DATA_SEED, self.mint.as_ref(), slice::from_ref(&self.bumps.global_data)]
+    }
+
+    pub fn interest_bank_seeds(&self) -> [&[u8]; 3] {
+        [INTEREST_BANK_SEED, self.mint.as_ref(), slice::from_ref(&self.bumps.interest_bank)]
+    }
+
+    pub fn ubi_bank_seeds(&self) -> [&[u8]; 3] {
+        [UBI_BANK_SEED, self.mint.as_ref(), slice::from_ref(&self.bumps.ubi_bank)]
+    }
+
+    pub fn interest_bank_data_seeds(&self) -> [&[u8]; 2] {
+        [self.interest_bank.as_ref(), slice::from_ref(&self.bumps.interest_bank_data)]
+    }
+
+    pub fn ubi_bank_data_seeds(&self) -> [&[u8]; 2] {
+        [self.ubi_bank.as_ref(), slice::from_ref(&self.bumps.ubi_bank_data)]
+    }
+
+    pub fn extra_account_metas_seeds(&self) -> [&[u8]; 3] {
+        [
+            EXTRA_ACCOUNT_METAS_SEED,
+            self.mint.as_ref(),
+            slice::from_ref(&self.bumps.extra_account_metas),
+        ]
+    }
+}
+
+impl From<&[u8]> for &Config {
+    fn from(data: &[u8]) -> Self {
+        // the config is only the start of the global data
+        assert!(data.len() >= CONFIG_SIZE, "account is too small to hold a config");
+        unsafe { &*(data.as_ptr() as *const Config) }
+    }
+}
+
+impl<'a> From<&VerifiedAccountInfo<'a>> for &'a Config {
+    fn from(account: &VerifiedAccountInfo) -> Self {
+        account.data.borrow().as_ref().into()
+    }
+}
@@ -1,17 +1,30 @@
-use spl_token_2022::solana_program::{hash::Hash, hash::HASH_BYTES, program_error::ProgramError};
+use spl_token_2022::solana_program::{hash::Hash, hash::HASH_BYTES, program_error::ProgramError, pubkey::Pubkey};
 
-use crate::VerifiedAccountInfo;
+use crate::{Days, TimeSource, VerifiedAccountInfo};
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Default)]
 // CHANGES TO THE SIZE OF THIS STRUCT NEED TO BE REFLECTED IN test_client.js
 pub struct UserDataBase<T: ?Sized> {
     // capacity is stored in the fat pointer
     pub last_interest_payout_date: i64,
-    pub is_verified_human: bool,
+    // the wallet's balance when interest was last settled; interest for later days is only paid on what was held
+    // throughout, so deposits and mined comptokens can't collect interest for days before they arrived
+    pub last_settled_balance: u64,
+    // interest settled but not yet paid out of the interest bank
+    pub accrued_interest: u64,
+    // the owner level identity, which holds the verification status and UBI eligibility
+    pub user_identity: Pubkey,
+    // the user data's own bump, stored so it can be verified without searching for it
+    pub bump: u8,
     // padding: [u8; 7],
     length: usize,
     recent_blockhash: Hash,
+    // proofs for the previous blockhash are kept while it is still accepted, so duplicates are caught during the
+    // grace period after the valid blockhash changes
+    previous_length: usize,
+    previous_blockhash: Hash,
+    // the previous blockhash's proofs are stored first, followed by the recent blockhash's; each segment is sorted
     proofs: T,
 }
 
@@ -19,34 +32,144 @@ pub const USER_DATA_MIN_SIZE: usize = std::mem::size_of::<UserDataBase<Hash>>();
 
 pub type UserData = UserDataBase<[Hash]>;
 
+/// the size of a user data account able to store `proof_capacity` proofs
+pub fn user_data_size(proof_capacity: usize) -> usize {
+    USER_DATA_MIN_SIZE + (proof_capacity.max(1) - 1) * HASH_BYTES
+}
+
+impl<T: ?Sized> UserDataBase<T> {
+    pub fn recent_blockhash(&self) -> &Hash {
+        &self.recent_blockhash
+    }
+
+    pub fn proof_count(&self) -> usize {
+        self.length
+    }
+
+    pub fn previous_blockhash(&self) -> &Hash {
+        &self.previous_blockhash
+    }
+
+    pub fn previous_proof_count(&self) -> usize {
+        self.previous_length
+    }
+
+    /// the interest that settling now and taking the accrued interest would pay out, without changing anything
+    pub fn owed_interest(
+        &self, balance: u64, last_distribution_day: i64, days: &Days, apply_n_interests: impl Fn(usize, u64) -> u64,
+    ) -> u64 {
+        let days_since_last_update = days.between(self.last_interest_payout_date, last_distribution_day).max(0);
+        let held_balance = self.last_settled_balance.min(balance);
+        self.accrued_interest + apply_n_interests(days_since_last_update as usize, held_balance) - held_balance
+    }
+}
+
 impl UserData {
-    pub fn insert(&mut self, new_proof: &Hash, new_blockhash: &Hash) {
+    /// `valid_blockhash` is the global valid blockhash, and `previous_valid_blockhash` the one before it if proofs
+    /// against it are still accepted. The recent proofs are always those against the valid blockhash and the previous
+    /// proofs those against the previous one, so a blockhash's proofs are kept for as long as it is accepted.
+    pub fn insert(
+        &mut self, new_proof: &Hash, new_blockhash: &Hash, valid_blockhash: &Hash,
+        previous_valid_blockhash: Option<&Hash>,
+    ) {
         // new_proof and new_blockhash have already been verified
-        if self.recent_blockhash != *new_blockhash {
-            self.recent_blockhash = *new_blockhash;
-            self.length = 0;
+        self.align_segments(valid_blockhash, previous_valid_blockhash);
+        if new_blockhash == valid_blockhash {
+            if self.previous_length + self.length == self.proofs.len() && self.previous_length > 0 {
+                // make room for the valid blockhash's proofs. the previous proofs can't be checked for duplicates once
+                // they are gone, so no more proofs against the previous blockhash are accepted from this wallet
+                self.forget_previous_proofs();
+            }
+            self.length = self.insert_sorted(self.previous_length, self.length, new_proof);
+        } else {
+            assert_eq!(
+                self.previous_blockhash, *new_blockhash,
+                "proofs against the previous blockhash are no longer accepted from this wallet"
+            );
+            self.previous_length = self.insert_sorted(0, self.previous_length, new_proof);
         }
-        assert!(!self.contains(new_proof), "proof should be new");
+    }
 
-        match self.proofs.get_mut(self.length) {
-            // If proofs is not full, write the new proof into the next slot
-            Some(proof) => *proof = *new_proof,
-            None => panic!("User Data Account not large enough, consider reallocing"),
+    /// ties the recent proofs to `valid_blockhash` and the previous proofs to `previous_valid_blockhash`, forgetting
+    /// the proofs of blockhashes that are no longer accepted
+    fn align_segments(&mut self, valid_blockhash: &Hash, previous_valid_blockhash: Option<&Hash>) {
+        if self.previous_blockhash == *valid_blockhash || Some(&self.recent_blockhash) == previous_valid_blockhash {
+            // the valid blockhash changed since the wallet's last proof, or the segments were stored the wrong way
+            // round by an older version of the program
+            self.proofs[..self.previous_length + self.length].rotate_left(self.previous_length);
+            std::mem::swap(&mut self.previous_length, &mut self.length);
+            std::mem::swap(&mut self.previous_blockhash, &mut self.recent_blockhash);
+        }
+        let is_new_valid_blockhash = self.recent_blockhash != *valid_blockhash;
+        if is_new_valid_blockhash {
+            // the recent proofs are stored last, so they are forgotten just by dropping them
+            self.recent_blockhash = *valid_blockhash;
+            self.length = 0;
+        }
+        if Some(&self.previous_blockhash) != previous_valid_blockhash {
+            self.forget_previous_proofs();
+            // a wallet whose previous proofs were forgotten to make room keeps them forgotten until the next blockhash
+            if is_new_valid_blockhash {
+                self.previous_blockhash = previous_valid_blockhash.copied().unwrap_or_default();
+            }
         }
-        self.length += 1;
     }
 
-    fn contains(&self, new_proof: &Hash) -> bool {
-        self.into_iter().any(|proof| proof == new_proof)
+    /// inserts `new_proof` into the sorted segment of `length` proofs starting at `start`, returning the new length
+    fn insert_sorted(&mut self, start: usize, length: usize, new_proof: &Hash) -> usize {
+        // each segment is kept sorted so duplicates can be found with a binary search instead of a scan
+        let index = match self.proofs[start..start + length].binary_search(new_proof) {
+            Ok(_) => panic!("proof should be new"),
+            Err(index) => start + index,
+        };
+        let end = self.previous_length + self.length;
+        assert!(end < self.proofs.len(), "User Data Account not large enough, consider reallocing");
+        // make room for the new proof, moving any later proofs (including later segments) up one slot
+        self.proofs.copy_within(index..end, index + 1);
+        self.proofs[index] = *new_proof;
+        length + 1
+    }
+
+    fn forget_previous_proofs(&mut self) {
+        self.proofs.copy_within(self.previous_length..self.previous_length + self.length, 0);
+        self.previous_length = 0;
+        self.previous_blockhash = Hash::default();
+    }
+
+    pub fn previous_proofs(&self) -> &[Hash] {
+        &self.proofs[..self.previous_length]
+    }
+
+    pub fn initialize(&mut self, user_identity: &Pubkey, bump: u8, days: &Days, clock: &impl TimeSource) {
+        self.last_interest_payout_date = clock.today(days);
+        self.last_settled_balance = 0;
+        self.accrued_interest = 0;
+        self.user_identity = *user_identity;
+        self.bump = bump;
     }
 
-    pub fn initialize(&mut self) {
-        self.last_interest_payout_date = crate::normalize_time(crate::get_current_time());
-        self.is_verified_human = false;
+    /// Accrues interest for every distribution day since the last settlement, then records `balance` as the settled
+    /// balance. Only the part of `balance` held since the last settlement earns interest for those days.
+    /// `apply_n_interests` applies the interest of the last `n` distributions to an amount.
+    pub fn settle(
+        &mut self, balance: u64, last_distribution_day: i64, days: &Days, apply_n_interests: impl Fn(usize, u64) -> u64,
+    ) {
+        self.accrued_interest = self.owed_interest(balance, last_distribution_day, days, apply_n_interests);
+        self.last_interest_payout_date = self.last_interest_payout_date.max(last_distribution_day);
+        self.last_settled_balance = balance;
     }
 
-    pub fn is_current(&self) -> bool {
-        self.last_interest_payout_date == crate::normalize_time(crate::get_current_time())
+    /// takes the accrued interest to pay it out, which the wallet then holds from today
+    pub fn take_accrued_interest(&mut self) -> u64 {
+        let interest = std::mem::take(&mut self.accrued_interest);
+        self.last_settled_balance += interest;
+        interest
+    }
+
+    /// whether nothing has accrued since the distribution at `last_distribution_day`, the same day settling counts up
+    /// to, so a settled wallet is current until the next distribution however late that runs
+    pub fn is_current(&self, last_distribution_day: i64) -> bool {
+        self.last_interest_payout_date >= last_distribution_day
     }
 }
 
@@ -55,7 +178,7 @@ impl TryFrom<&mut [u8]> for &mut UserData {
 
     fn try_from(data: &mut [u8]) -> Result<Self, Self::Error> {
         assert!(data.len() >= USER_DATA_MIN_SIZE);
-        assert!((data.len() - USER_DATA_MIN_SIZE) % HASH_BYTES == 0);
+        assert_eq!((data.len() - USER_DATA_MIN_SIZE) % HASH_BYTES, 0);
 
         let capacity = ((data.len() - USER_DATA_MIN_SIZE) / HASH_BYTES) + 1;
         // Two step process to dynamically create ProofStorage from the account data array of bytes
@@ -70,7 +193,7 @@ impl TryFrom<&mut [u8]> for &mut UserData {
         // This is how the rust docs say to do it... :/
         // https://doc.rust-lang.org/std/mem/fn.transmute.html
         let result = unsafe { &mut *(data_hashes as *mut _ as *mut UserData) };
-        assert!(result.length <= result.proofs.len());
+        assert!(result.previous_length + result.length <= result.proofs.len());
         Ok(result)
     }
 }
@@ -80,7 +203,7 @@ impl TryFrom<&[u8]> for &UserData {
 
     fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
         assert!(data.len() >= USER_DATA_MIN_SIZE);
-        assert!((data.len() - USER_DATA_MIN_SIZE) % HASH_BYTES == 0);
+        assert_eq!((data.len() - USER_DATA_MIN_SIZE) % HASH_BYTES, 0);
 
         let capacity = ((data.len() - USER_DATA_MIN_SIZE) / HASH_BYTES) + 1;
         // Two step process to dynamically create ProofStorage from the account data array of bytes
@@ -95,7 +218,7 @@ impl TryFrom<&[u8]> for &UserData {
         // This is how the rust docs say to do it... :/
         // https://doc.rust-lang.org/std/mem/fn.transmute.html
         let result = unsafe { &*(data_hashes as *const _ as *const UserData) };
-        assert!(result.length <= result.proofs.len());
+        assert!(result.previous_length + result.length <= result.proofs.len());
         Ok(result)
     }
 }
@@ -113,7 +236,7 @@ impl<'a> From<&VerifiedAccountInfo<'a>> for &'a UserData {
 }
 
 pub struct HashIter<'a> {
-    iter: std::iter::Take<std::slice::Iter<'a, Hash>>,
+    iter: std::slice::Iter<'a, Hash>,
 }
 
 impl<'a> Iterator for HashIter<'a> {
@@ -125,7 +248,7 @@ impl<'a> Iterator for HashIter<'a> {
 }
 
 pub struct MutHashIter<'a> {
-    iter: std::iter::Take<std::slice::IterMut<'a, Hash>>,
+    iter: std::slice::IterMut<'a, Hash>,
 }
 
 impl<'a> Iterator for MutHashIter<'a> {
@@ -141,7 +264,9 @@ impl<'a> IntoIterator for &'a UserData {
     type IntoIter = HashIter<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
-        HashIter { iter: self.proofs.iter().take(self.length) }
+        HashIter {
+            iter: self.proofs[self.previous_length..self.previous_length + self.length].iter(),
+        }
     }
 }
 
@@ -150,7 +275,9 @@ impl<'a> IntoIterator for &'a mut UserData {
     type IntoIter = MutHashIter<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
-        MutHashIter { iter: self.proofs.iter_mut().take(self.length) }
+        MutHashIter {
+            iter: self.proofs[self.previous_length..self.previous_length + self.length].iter_mut(),
+        }
     }
 }
 
@@ -202,14 +329,14 @@ mod test {
     ///
     /// data must be large enough to hold a ProofStorage of length proofs.len()
     unsafe fn write_data(data: &mut [u8], length: usize, blockhash: &Hash, proofs: &[Hash]) {
-        let len_ptr = data.as_mut_ptr().offset(16) as *mut usize;
+        let len_ptr = data.as_mut_ptr().offset(64) as *mut usize;
         *len_ptr = length;
 
-        let blockhash_ptr = data.as_mut_ptr().offset(24) as *mut Hash;
+        let blockhash_ptr = data.as_mut_ptr().offset(72) as *mut Hash;
         *blockhash_ptr = *blockhash;
 
         for (i, proof) in proofs.iter().enumerate() {
-            let proof_ptr = data.as_mut_ptr().add(56 + i * HASH_BYTES) as *mut Hash;
+            let proof_ptr = data.as_mut_ptr().add(144 + i * HASH_BYTES) as *mut Hash;
             *proof_ptr = *proof;
         }
     }
@@ -228,7 +355,7 @@ mod test {
         let user_data: &mut UserData = input.data.try_into().expect("panicked already if failed");
 
         for pow in input.new_proofs {
-            user_data.insert(&pow.proof, &pow.blockhash);
+            user_data.insert(&pow.proof, &pow.blockhash, &pow.blockhash, None);
         }
 
         let user_data: &UserData = user_data;
@@ -324,4 +451,155 @@ mod test {
             output: None,
         })
     }
+
+    #[test]
+    fn test_insert_previous_blockhash_during_grace_period() {
+        let mut data = [0_u8; USER_DATA_MIN_SIZE + 2 * HASH_BYTES];
+        unsafe { write_data(&mut data, 1, &POSSIBLE_BLOCKHASHES[0], &[POSSIBLE_PROOFS[0]]) }
+        let user_data: &mut UserData = data.as_mut_slice().try_into().expect("valid user data");
+
+        user_data.insert(
+            &POSSIBLE_PROOFS[1],
+            &POSSIBLE_BLOCKHASHES[1],
+            &POSSIBLE_BLOCKHASHES[1],
+            Some(&POSSIBLE_BLOCKHASHES[0]),
+        );
+        assert_eq!(user_data.previous_blockhash, POSSIBLE_BLOCKHASHES[0]);
+        assert_eq!(user_data.previous_proofs(), &[POSSIBLE_PROOFS[0]]);
+
+        let new_proof = Hash::new_from_array([7; HASH_BYTES]);
+        user_data.insert(
+            &new_proof,
+            &POSSIBLE_BLOCKHASHES[0],
+            &POSSIBLE_BLOCKHASHES[1],
+            Some(&POSSIBLE_BLOCKHASHES[0]),
+        );
+        // proofs are kept sorted
+        assert_eq!(user_data.previous_proofs(), &[new_proof, POSSIBLE_PROOFS[0]]);
+        assert_eq!(user_data.into_iter().collect::<Vec<_>>(), vec![&POSSIBLE_PROOFS[1]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "proof should be new")]
+    fn test_insert_previous_blockhash_duplicate() {
+        let mut data = [0_u8; USER_DATA_MIN_SIZE + 2 * HASH_BYTES];
+        unsafe { write_data(&mut data, 1, &POSSIBLE_BLOCKHASHES[0], &[POSSIBLE_PROOFS[0]]) }
+        let user_data: &mut UserData = data.as_mut_slice().try_into().expect("valid user data");
+
+        user_data.insert(
+            &POSSIBLE_PROOFS[1],
+            &POSSIBLE_BLOCKHASHES[1],
+            &POSSIBLE_BLOCKHASHES[1],
+            Some(&POSSIBLE_BLOCKHASHES[0]),
+        );
+        user_data.insert(
+            &POSSIBLE_PROOFS[0],
+            &POSSIBLE_BLOCKHASHES[0],
+            &POSSIBLE_BLOCKHASHES[1],
+            Some(&POSSIBLE_BLOCKHASHES[0]),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "proof should be new")]
+    fn test_previous_proofs_kept_while_accepted() {
+        // blockhashes of three consecutive days
+        let [b, c, d] = [1, 2, 3].map(|byte| Hash::new_from_array([byte; HASH_BYTES]));
+        let [proof_b, proof_c, proof_d] = [4, 5, 6].map(|byte| Hash::new_from_array([byte; HASH_BYTES]));
+        let mut data = [0_u8; USER_DATA_MIN_SIZE + 2 * HASH_BYTES];
+        let user_data: &mut UserData = data.as_mut_slice().try_into().expect("valid user data");
+
+        // on day c, while b is still in its grace period, a proof for c arrives before one for b
+        user_data.insert(&proof_c, &c, &c, Some(&b));
+        user_data.insert(&proof_b, &b, &c, Some(&b));
+        assert_eq!((user_data.recent_blockhash, user_data.previous_blockhash), (c, b));
+
+        // on day d, c is in its grace period, so its proofs must still be caught as duplicates
+        user_data.insert(&proof_d, &d, &d, Some(&c));
+        assert_eq!((user_data.recent_blockhash, user_data.previous_blockhash), (d, c));
+        assert_eq!(user_data.previous_proofs(), &[proof_c]);
+        user_data.insert(&proof_c, &c, &d, Some(&c));
+    }
+
+    #[test]
+    #[should_panic(expected = "no longer accepted from this wallet")]
+    fn test_insert_full_account_new_day_during_grace() {
+        let [b, c] = [1, 2].map(|byte| Hash::new_from_array([byte; HASH_BYTES]));
+        let mut data = [0_u8; USER_DATA_MIN_SIZE];
+        let user_data: &mut UserData = data.as_mut_slice().try_into().expect("valid user data");
+        user_data.insert(&POSSIBLE_PROOFS[0], &b, &b, None);
+
+        // the account is full, so the previous proofs make room for the valid blockhash's
+        user_data.insert(&POSSIBLE_PROOFS[1], &c, &c, Some(&b));
+        assert_eq!(user_data.into_iter().collect::<Vec<_>>(), vec![&POSSIBLE_PROOFS[1]]);
+        assert!(user_data.previous_proofs().is_empty());
+
+        // without them, proofs against b could be replayed, so they are no longer accepted from this wallet
+        user_data.insert(&POSSIBLE_PROOFS[0], &b, &c, Some(&b));
+    }
+
+    #[test]
+    fn test_insert_keeps_proofs_sorted() {
+        let mut data = [0_u8; USER_DATA_MIN_SIZE + 3 * HASH_BYTES];
+        unsafe { write_data(&mut data, 0, &POSSIBLE_BLOCKHASHES[0], &[]) }
+        let user_data: &mut UserData = data.as_mut_slice().try_into().expect("valid user data");
+
+        for byte in [5, 1, 9, 3] {
+            let proof = Hash::new_from_array([byte; HASH_BYTES]);
+            user_data.insert(&proof, &POSSIBLE_BLOCKHASHES[0], &POSSIBLE_BLOCKHASHES[0], None);
+        }
+        let proofs: Vec<_> = user_data.into_iter().map(|proof| proof.to_bytes()[0]).collect();
+        assert_eq!(proofs, [1, 3, 5, 9]);
+    }
+
+    #[test]
+    fn test_settle_only_pays_interest_on_held_balance() {
+        let mut data = [0_u8; USER_DATA_MIN_SIZE];
+        let user_data: &mut UserData = data.as_mut_slice().try_into().expect("valid user data");
+        user_data.last_settled_balance = 100;
+        // 10% interest a day
+        let apply_n_interests = |n: usize, amount: u64| (0..n).fold(amount, |amount, _| amount + amount / 10);
+
+        // a deposit made after the last settlement earns nothing for the missed days
+        assert_eq!(user_data.owed_interest(1_000, 2 * crate::SEC_PER_DAY, &Days::UTC, apply_n_interests), 21);
+        user_data.settle(1_000, 2 * crate::SEC_PER_DAY, &Days::UTC, apply_n_interests);
+        assert_eq!(user_data.accrued_interest, 21);
+        assert_eq!(user_data.last_interest_payout_date, 2 * crate::SEC_PER_DAY);
+        assert_eq!(user_data.last_settled_balance, 1_000);
+
+        // settling again on the same day accrues nothing
+        user_data.settle(1_000, 2 * crate::SEC_PER_DAY, &Days::UTC, apply_n_interests);
+        assert_eq!(user_data.accrued_interest, 21);
+
+        // a withdrawal only earns on what is left
+        user_data.settle(500, 3 * crate::SEC_PER_DAY, &Days::UTC, apply_n_interests);
+        assert_eq!(user_data.accrued_interest, 71);
+
+        assert_eq!(user_data.take_accrued_interest(), 71);
+        assert_eq!(user_data.accrued_interest, 0);
+        assert_eq!(user_data.last_settled_balance, 571);
+    }
+
+    #[test]
+    fn test_is_current() {
+        let mut data = [0_u8; USER_DATA_MIN_SIZE];
+        let user_data: &mut UserData = data.as_mut_slice().try_into().expect("valid user data");
+        let day = |n: i64| n * crate::SEC_PER_DAY;
+        let apply_n_interests = |_: usize, amount: u64| amount;
+
+        // created before today's distribution has run
+        let clock = crate::MockClock::new(day(5) + 1);
+        user_data.initialize(&Pubkey::new_unique(), 255, &Days::UTC, &clock);
+        assert!(user_data.is_current(day(4)));
+        assert!(user_data.is_current(day(5)));
+
+        // behind once a later distribution runs, whatever the time
+        assert!(!user_data.is_current(day(6)));
+
+        // and current again once settled, even while the distribution is still catching up on missed days
+        clock.set(day(9) + 1);
+        user_data.settle(0, day(6), &Days::UTC, apply_n_interests);
+        assert!(user_data.is_current(day(6)));
+        assert!(!user_data.is_current(day(7)));
+    }
 }
@@ -0,0 +1,87 @@
+use spl_token_2022::solana_program::pubkey::Pubkey;
+
+use crate::{Days, TimeSource, VerifiedAccountInfo};
+
+#[repr(C)]
+#[derive(Debug)]
+// CHANGES TO THE SIZE OF THIS STRUCT NEED TO BE REFLECTED IN accounts.js
+pub struct UserIdentity {
+    // the owner of every comptoken wallet whose user data links to this identity
+    pub owner: Pubkey,
+    // the last distribution day UBI was paid for, to any of the owner's comptoken wallets
+    pub last_ubi_payout_date: i64,
+    pub is_verified_human: bool,
+    // the identity's own bump, stored so it can be verified without searching for it
+    pub bump: u8,
+    // padding: [u8; 6],
+}
+
+pub const USER_IDENTITY_SIZE: usize = std::mem::size_of::<UserIdentity>();
+
+impl UserIdentity {
+    pub fn initialize(&mut self, owner: &Pubkey, bump: u8, days: &Days, clock: &impl TimeSource) {
+        self.owner = *owner;
+        self.last_ubi_payout_date = clock.today(days);
+        self.is_verified_human = false;
+        self.bump = bump;
+    }
+
+    /// whether UBI is owed for the distribution at `last_distribution_day`
+    pub fn is_ubi_owed(&self, last_distribution_day: i64) -> bool {
+        self.is_verified_human && self.last_ubi_payout_date < last_distribution_day
+    }
+
+    /// whether UBI is owed for the distribution at `last_distribution_day`, which is then marked as paid
+    pub fn claim_ubi(&mut self, last_distribution_day: i64) -> bool {
+        if !self.is_ubi_owed(last_distribution_day) {
+            return false;
+        }
+        self.last_ubi_payout_date = last_distribution_day;
+        true
+    }
+}
+
+impl From<&mut [u8]> for &mut UserIdentity {
+    fn from(data: &mut [u8]) -> Self {
+        assert_eq!(data.len(), USER_IDENTITY_SIZE);
+        unsafe { &mut *(data.as_mut_ptr() as *mut UserIdentity) }
+    }
+}
+
+impl From<&[u8]> for &UserIdentity {
+    fn from(data: &[u8]) -> Self {
+        assert_eq!(data.len(), USER_IDENTITY_SIZE);
+        unsafe { &*(data.as_ptr() as *const UserIdentity) }
+    }
+}
+
+impl<'a> From<&VerifiedAccountInfo<'a>> for &'a mut UserIdentity {
+    fn from(account: &VerifiedAccountInfo) -> Self {
+        account.data.borrow_mut().as_mut().into()
+    }
+}
+
+impl<'a> From<&VerifiedAccountInfo<'a>> for &'a UserIdentity {
+    fn from(account: &VerifiedAccountInfo) -> Self {
+        account.data.borrow().as_ref().into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_claim_ubi_once_per_day() {
+        let mut data = [0_u8; USER_IDENTITY_SIZE];
+        let identity: &mut UserIdentity = data.as_mut_slice().into();
+        identity.last_ubi_payout_date = 0;
+
+        assert!(!identity.claim_ubi(crate::SEC_PER_DAY), "only verified humans get UBI");
+        identity.is_verified_human = true;
+        assert!(identity.claim_ubi(crate::SEC_PER_DAY));
+        assert!(!identity.claim_ubi(crate::SEC_PER_DAY), "UBI is claimed once per day");
+        assert!(identity.claim_ubi(2 * crate::SEC_PER_DAY));
+        assert_eq!(identity.last_ubi_payout_date, 2 * crate::SEC_PER_DAY);
+    }
+}
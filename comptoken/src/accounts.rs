@@ -0,0 +1,735 @@
+//! Host side decoders for every comptoken account type.
+//!
+//! These parse raw account data (e.g. from an rpc `getAccountInfo`) into owned structs, so explorers, wallets, and
+//! tests don't need to keep their own copies of the `#[repr(C)]` layouts in sync.
+//! Enable the `serde` feature to serialize them, and the `no-entrypoint` feature when depending on this crate.
+
+use std::fmt::{self, Display};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use spl_token_2022::solana_program::{
+    hash::{Hash, HASH_BYTES},
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+use comptoken_utils::{
+    user_data::UserDataBase,
+    user_identity::{UserIdentity, USER_IDENTITY_SIZE},
+    Days,
+};
+
+use crate::{
+    global_data::{self, daily_distribution_data::apply_interests, GlobalData},
+    proof_scheme::ProofSchemeKind,
+};
+
+pub const GLOBAL_DATA_SIZE: usize = std::mem::size_of::<GlobalData>();
+
+// the proofs start right after the fixed size fields
+type UserDataHeader = UserDataBase<[Hash; 0]>;
+const USER_DATA_HEADER_SIZE: usize = std::mem::size_of::<UserDataHeader>();
+
+// value is solanas transfer hook execute instruction discriminator
+// https://github.com/solana-labs/solana-program-library/blob/token-2022-v3.0/token/js/src/extensions/transferHook/instructions.ts#L168
+const EXECUTE_INSTRUCTION_DISCRIMINATOR: [u8; 8] = [105, 37, 101, 197, 75, 251, 102, 26];
+const EXTRA_ACCOUNT_META_SIZE: usize = 35;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountDecodeError {
+    InvalidSize { account: &'static str, size: usize },
+    InvalidUserDataLength { length: usize, capacity: usize },
+    InvalidDiscriminator { discriminator: [u8; 8] },
+    InvalidField { account: &'static str, field: &'static str, value: u8 },
+}
+
+impl Display for AccountDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountDecodeError::InvalidSize { account, size } => write!(f, "{size} bytes is not a valid {account}"),
+            AccountDecodeError::InvalidUserDataLength { length, capacity } => {
+                write!(f, "user data length {length} exceeds its capacity {capacity}")
+            }
+            AccountDecodeError::InvalidDiscriminator { discriminator } => {
+                write!(f, "{discriminator:?} is not the transfer hook execute discriminator")
+            }
+            AccountDecodeError::InvalidField { account, field, value } => {
+                write!(f, "{value} is not a valid {field} for a {account}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AccountDecodeError {}
+
+// `std::mem::offset_of!` is newer than the compiler `cargo build-sbf` uses
+macro_rules! offset_of {
+    ($type:ty, $($field:ident).+) => {{
+        let value = std::mem::MaybeUninit::<$type>::uninit();
+        let base = value.as_ptr();
+        // SAFETY: only the field's address is taken, nothing is read
+        unsafe { std::ptr::addr_of!((*base).$($field).+) as usize - base as usize }
+    }};
+}
+
+/// Copies a `#[repr(C)]` `T` out of `data`, which account data from an rpc isn't necessarily aligned for. `validate`
+/// sees the copied bytes first and must reject or fix up every field that not all bit patterns are valid for.
+fn read_validated<T>(data: &[u8], validate: impl FnOnce(&mut [u8]) -> Result<()>) -> Result<T> {
+    let mut bytes = data[..std::mem::size_of::<T>()].to_vec();
+    validate(&mut bytes)?;
+    // SAFETY: `bytes` is the size of a `T`, and `validate` has left a valid value in each of its fields
+    Ok(unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const T) })
+}
+
+type Result<T> = std::result::Result<T, AccountDecodeError>;
+
+pub use comptoken_utils::user_data::user_data_size;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Config {
+    pub distribution_bounty: u64,
+    pub blockhash_grace_period: i64,
+    #[cfg_attr(feature = "serde", serde(with = "as_string"))]
+    pub admin: Pubkey,
+    pub v1_proof_deadline: i64,
+    pub day_length: i64,
+    pub day_offset: i64,
+    #[cfg_attr(feature = "serde", serde(with = "as_string"))]
+    pub mint: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "as_string"))]
+    pub transfer_hook_program: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "as_string"))]
+    pub interest_bank: Pubkey,
+    #[cfg_attr(feature = "serde", serde(with = "as_string"))]
+    pub ubi_bank: Pubkey,
+}
+
+impl Config {
+    pub fn days(&self) -> Days {
+        Days { length: self.day_length, offset: self.day_offset }
+    }
+}
+
+impl From<&global_data::config::Config> for Config {
+    fn from(value: &global_data::config::Config) -> Self {
+        Config {
+            distribution_bounty: value.distribution_bounty,
+            blockhash_grace_period: value.blockhash_grace_period,
+            admin: value.admin,
+            v1_proof_deadline: value.v1_proof_deadline,
+            day_length: value.days.length,
+            day_offset: value.days.offset,
+            mint: value.mint,
+            transfer_hook_program: value.transfer_hook_program,
+            interest_bank: value.interest_bank,
+            ubi_bank: value.ubi_bank,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ValidBlockhashes {
+    #[cfg_attr(feature = "serde", serde(with = "as_string"))]
+    pub announced_blockhash: Hash,
+    pub announced_blockhash_time: i64,
+    pub announced_blockhash_slot: u64,
+    #[cfg_attr(feature = "serde", serde(with = "as_string"))]
+    pub valid_blockhash: Hash,
+    pub valid_blockhash_time: i64,
+    #[cfg_attr(feature = "serde", serde(with = "as_string"))]
+    pub previous_valid_blockhash: Hash,
+    pub previous_valid_blockhash_time: i64,
+    pub valid_proof_scheme: ProofSchemeKind,
+    pub previous_proof_scheme: ProofSchemeKind,
+    pub next_proof_scheme: ProofSchemeKind,
+}
+
+impl From<&global_data::valid_blockhashes::ValidBlockhashes> for ValidBlockhashes {
+    fn from(value: &global_data::valid_blockhashes::ValidBlockhashes) -> Self {
+        ValidBlockhashes {
+            announced_blockhash: value.announced_blockhash,
+            announced_blockhash_time: value.announced_blockhash_time,
+            announced_blockhash_slot: value.announced_blockhash_slot,
+            valid_blockhash: value.valid_blockhash,
+            valid_blockhash_time: value.valid_blockhash_time,
+            previous_valid_blockhash: value.previous_valid_blockhash,
+            previous_valid_blockhash_time: value.previous_valid_blockhash_time,
+            valid_proof_scheme: value.valid_proof_scheme,
+            previous_proof_scheme: value.previous_proof_scheme,
+            next_proof_scheme: value.next_proof_scheme,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct DailyDistributionData {
+    pub yesterday_supply: u64,
+    pub high_water_mark: u64,
+    pub last_daily_distribution_time: i64,
+    pub mined_since_last_distribution: u64,
+    /// most recent first
+    pub historic_interests: Vec<f64>,
+}
+
+impl DailyDistributionData {
+    pub fn apply_n_interests(&self, n: usize, initial_money: u64) -> u64 {
+        apply_interests(self.historic_interests.iter().copied().take(n), initial_money)
+    }
+}
+
+impl From<&global_data::daily_distribution_data::DailyDistributionData> for DailyDistributionData {
+    fn from(value: &global_data::daily_distribution_data::DailyDistributionData) -> Self {
+        DailyDistributionData {
+            yesterday_supply: value.yesterday_supply,
+            high_water_mark: value.high_water_mark,
+            last_daily_distribution_time: value.last_daily_distribution_time,
+            mined_since_last_distribution: value.mined_since_last_distribution,
+            historic_interests: value.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Ledger {
+    pub total_mined: u64,
+    pub total_interest_distributed: u64,
+    pub total_ubi_distributed: u64,
+    pub total_bounty_distributed: u64,
+    pub total_interest_claimed: u64,
+    pub total_ubi_claimed: u64,
+}
+
+impl From<&global_data::ledger::Ledger> for Ledger {
+    fn from(value: &global_data::ledger::Ledger) -> Self {
+        Ledger {
+            total_mined: value.total_mined,
+            total_interest_distributed: value.total_interest_distributed,
+            total_ubi_distributed: value.total_ubi_distributed,
+            total_bounty_distributed: value.total_bounty_distributed,
+            total_interest_claimed: value.total_interest_claimed,
+            total_ubi_claimed: value.total_ubi_claimed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct DailyStats {
+    pub day: i64,
+    pub proofs_accepted: u64,
+    pub unique_miners: u64,
+    pub tokens_mined: u64,
+    pub high_water_mark: u64,
+    pub supply: u64,
+    pub interest_distributed: u64,
+    pub ubi_distributed: u64,
+    #[cfg_attr(feature = "serde", serde(with = "as_string"))]
+    pub valid_blockhash: Hash,
+}
+
+impl From<&global_data::daily_stats::DailyStats> for DailyStats {
+    fn from(value: &global_data::daily_stats::DailyStats) -> Self {
+        DailyStats {
+            day: value.day,
+            proofs_accepted: value.proofs_accepted,
+            unique_miners: value.unique_miners,
+            tokens_mined: value.tokens_mined,
+            high_water_mark: value.high_water_mark,
+            supply: value.supply,
+            interest_distributed: value.interest_distributed,
+            ubi_distributed: value.ubi_distributed,
+            valid_blockhash: value.valid_blockhash,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct DailyStatsHistory {
+    pub proofs_since_last_distribution: u64,
+    pub miners_since_last_distribution: u64,
+    /// most recent first
+    pub historic_stats: Vec<DailyStats>,
+}
+
+impl From<&global_data::daily_stats::DailyStatsHistory> for DailyStatsHistory {
+    fn from(value: &global_data::daily_stats::DailyStatsHistory) -> Self {
+        DailyStatsHistory {
+            proofs_since_last_distribution: value.proofs_since_last_distribution,
+            miners_since_last_distribution: value.miners_since_last_distribution,
+            historic_stats: value.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct GlobalDataAccount {
+    pub config: Config,
+    pub valid_blockhashes: ValidBlockhashes,
+    pub daily_distribution_data: DailyDistributionData,
+    pub ledger: Ledger,
+    pub daily_stats: DailyStatsHistory,
+}
+
+impl GlobalDataAccount {
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() < GLOBAL_DATA_SIZE {
+            return Err(AccountDecodeError::InvalidSize { account: "global data account", size: data.len() });
+        }
+        let global_data: GlobalData = read_validated(data, |bytes| {
+            for (field, offset) in [
+                ("valid proof scheme", offset_of!(GlobalData, valid_blockhashes.valid_proof_scheme)),
+                ("previous proof scheme", offset_of!(GlobalData, valid_blockhashes.previous_proof_scheme)),
+                ("next proof scheme", offset_of!(GlobalData, valid_blockhashes.next_proof_scheme)),
+            ] {
+                ProofSchemeKind::try_from(bytes[offset]).map_err(|value| AccountDecodeError::InvalidField {
+                    account: "global data account",
+                    field,
+                    value,
+                })?;
+            }
+            Ok(())
+        })?;
+        Ok(GlobalDataAccount {
+            config: (&global_data.config).into(),
+            valid_blockhashes: (&global_data.valid_blockhashes).into(),
+            daily_distribution_data: (&global_data.daily_distribution_data).into(),
+            ledger: (&global_data.ledger).into(),
+            daily_stats: (&global_data.daily_stats).into(),
+        })
+    }
+}
+
+impl Display for GlobalDataAccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let valid_blockhashes = &self.valid_blockhashes;
+        let daily_distribution_data = &self.daily_distribution_data;
+        writeln!(
+            f,
+            "Announced Blockhash: {} (at {}, from slot {})",
+            valid_blockhashes.announced_blockhash,
+            valid_blockhashes.announced_blockhash_time,
+            valid_blockhashes.announced_blockhash_slot
+        )?;
+        writeln!(
+            f,
+            "Valid Blockhash: {} (at {})",
+            valid_blockhashes.valid_blockhash, valid_blockhashes.valid_blockhash_time
+        )?;
+        writeln!(
+            f,
+            "Previous Valid Blockhash: {} (at {})",
+            valid_blockhashes.previous_valid_blockhash, valid_blockhashes.previous_valid_blockhash_time
+        )?;
+        writeln!(
+            f,
+            "Proof Schemes: {:?} (previous {:?}, next {:?})",
+            valid_blockhashes.valid_proof_scheme,
+            valid_blockhashes.previous_proof_scheme,
+            valid_blockhashes.next_proof_scheme
+        )?;
+        writeln!(f, "Yesterday's Supply: {}", daily_distribution_data.yesterday_supply)?;
+        writeln!(f, "High Water Mark: {}", daily_distribution_data.high_water_mark)?;
+        writeln!(f, "Last Daily Distribution: {}", daily_distribution_data.last_daily_distribution_time)?;
+        writeln!(f, "Mined Since Last Distribution: {}", daily_distribution_data.mined_since_last_distribution)?;
+        writeln!(
+            f,
+            "Proofs Since Last Distribution: {} (by {} miners)",
+            self.daily_stats.proofs_since_last_distribution, self.daily_stats.miners_since_last_distribution
+        )?;
+        for stats in self.daily_stats.historic_stats.iter().take(7) {
+            writeln!(
+                f,
+                "Day {}: {} proofs by {} miners, {} mined, {} interest, {} UBI, supply {}",
+                stats.day,
+                stats.proofs_accepted,
+                stats.unique_miners,
+                stats.tokens_mined,
+                stats.interest_distributed,
+                stats.ubi_distributed,
+                stats.supply
+            )?;
+        }
+        writeln!(
+            f,
+            "Total Issued: {} mined, {} interest, {} UBI, {} bounties",
+            self.ledger.total_mined,
+            self.ledger.total_interest_distributed,
+            self.ledger.total_ubi_distributed,
+            self.ledger.total_bounty_distributed
+        )?;
+        writeln!(
+            f,
+            "Total Claimed: {} interest, {} UBI",
+            self.ledger.total_interest_claimed, self.ledger.total_ubi_claimed
+        )?;
+        writeln!(f, "Distribution Bounty: {}", self.config.distribution_bounty)?;
+        writeln!(f, "Blockhash Grace Period: {}", self.config.blockhash_grace_period)?;
+        writeln!(f, "Admin: {}", self.config.admin)?;
+        writeln!(f, "V1 Proof Deadline: {}", self.config.v1_proof_deadline)?;
+        writeln!(f, "Day Length: {} (offset {})", self.config.day_length, self.config.day_offset)?;
+        writeln!(f, "Mint: {}", self.config.mint)?;
+        writeln!(f, "Transfer Hook Program: {}", self.config.transfer_hook_program)?;
+        writeln!(f, "Interest Bank: {}", self.config.interest_bank)?;
+        writeln!(f, "UBI Bank: {}", self.config.ubi_bank)?;
+        write!(f, "Recent Interest Rates:")?;
+        for interest in daily_distribution_data.historic_interests.iter().take(7) {
+            write!(f, " {interest}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct UserDataAccount {
+    pub last_interest_payout_date: i64,
+    pub last_settled_balance: u64,
+    pub accrued_interest: u64,
+    #[cfg_attr(feature = "serde", serde(with = "as_string"))]
+    pub user_identity: Pubkey,
+    pub bump: u8,
+    #[cfg_attr(feature = "serde", serde(with = "as_string"))]
+    pub recent_blockhash: Hash,
+    #[cfg_attr(feature = "serde", serde(with = "as_strings"))]
+    pub proofs: Vec<Hash>,
+    #[cfg_attr(feature = "serde", serde(with = "as_string"))]
+    pub previous_blockhash: Hash,
+    #[cfg_attr(feature = "serde", serde(with = "as_strings"))]
+    pub previous_proofs: Vec<Hash>,
+    pub capacity: usize,
+}
+
+impl UserDataAccount {
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let capacity = data.len().saturating_sub(USER_DATA_HEADER_SIZE) / HASH_BYTES;
+        if capacity == 0 || data.len() != user_data_size(capacity) {
+            return Err(AccountDecodeError::InvalidSize { account: "user data account", size: data.len() });
+        }
+        // every bit pattern is valid for each of the header's fields
+        let header: UserDataHeader = read_validated(data, |_| Ok(()))?;
+        let previous_length = header.previous_proof_count();
+        let length = header.proof_count();
+        if previous_length + length > capacity {
+            return Err(AccountDecodeError::InvalidUserDataLength { length: previous_length + length, capacity });
+        }
+        // the previous blockhash's proofs are stored before the recent blockhash's
+        let mut stored_proofs = data[USER_DATA_HEADER_SIZE..].chunks_exact(HASH_BYTES).map(Hash::new);
+        let previous_proofs = stored_proofs.by_ref().take(previous_length).collect();
+        let proofs = stored_proofs.take(length).collect();
+
+        Ok(UserDataAccount {
+            last_interest_payout_date: header.last_interest_payout_date,
+            last_settled_balance: header.last_settled_balance,
+            accrued_interest: header.accrued_interest,
+            user_identity: header.user_identity,
+            bump: header.bump,
+            recent_blockhash: *header.recent_blockhash(),
+            proofs,
+            previous_blockhash: *header.previous_blockhash(),
+            previous_proofs,
+            capacity,
+        })
+    }
+
+    /// the interest `get_owed_comptokens` would pay out to this user data's wallet, which now holds `balance`
+    pub fn owed_interest(&self, global_data: &GlobalDataAccount, balance: u64) -> u64 {
+        // the same calculation `get_owed_comptokens` settles with
+        let mut user_data = UserDataHeader::default();
+        user_data.last_interest_payout_date = self.last_interest_payout_date;
+        user_data.last_settled_balance = self.last_settled_balance;
+        user_data.accrued_interest = self.accrued_interest;
+        let daily_distribution_data = &global_data.daily_distribution_data;
+        user_data.owed_interest(
+            balance,
+            daily_distribution_data.last_daily_distribution_time,
+            &global_data.config.days(),
+            |n, amount| daily_distribution_data.apply_n_interests(n, amount),
+        )
+    }
+}
+
+impl Display for UserDataAccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Last Interest Payout Date: {}", self.last_interest_payout_date)?;
+        writeln!(f, "Last Settled Balance: {}", self.last_settled_balance)?;
+        writeln!(f, "Accrued Interest: {}", self.accrued_interest)?;
+        writeln!(f, "User Identity: {}", self.user_identity)?;
+        writeln!(f, "Recent Blockhash: {}", self.recent_blockhash)?;
+        writeln!(f, "Previous Blockhash: {}", self.previous_blockhash)?;
+        write!(f, "Proofs: {} (+{} previous)/{}", self.proofs.len(), self.previous_proofs.len(), self.capacity)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct UserIdentityAccount {
+    #[cfg_attr(feature = "serde", serde(with = "as_string"))]
+    pub owner: Pubkey,
+    pub last_ubi_payout_date: i64,
+    pub is_verified_human: bool,
+    pub bump: u8,
+}
+
+impl UserIdentityAccount {
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() != USER_IDENTITY_SIZE {
+            return Err(AccountDecodeError::InvalidSize { account: "user identity account", size: data.len() });
+        }
+        let identity: UserIdentity = read_validated(data, |bytes| {
+            // the program only ever writes 0 or 1, but any other byte would not be a valid bool
+            let is_verified_human = &mut bytes[offset_of!(UserIdentity, is_verified_human)];
+            *is_verified_human = (*is_verified_human != 0) as u8;
+            Ok(())
+        })?;
+        Ok(UserIdentityAccount {
+            owner: identity.owner,
+            last_ubi_payout_date: identity.last_ubi_payout_date,
+            is_verified_human: identity.is_verified_human,
+            bump: identity.bump,
+        })
+    }
+}
+
+impl Display for UserIdentityAccount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Owner: {}", self.owner)?;
+        writeln!(f, "Last UBI Payout Date: {}", self.last_ubi_payout_date)?;
+        write!(f, "Verified Human: {}", self.is_verified_human)
+    }
+}
+
+// effectively the same as spl_tlv_account_resolution::account::ExtraAccountMeta
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ExtraAccountMeta {
+    /// 0 for a literal address, 1 for a pda of the transfer hook program,
+    /// and `1 << 7 | index` for a pda of the program at account `index`
+    pub discriminator: u8,
+    /// either the literal address or the packed seeds of the pda
+    pub address_config: [u8; PUBKEY_BYTES],
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl ExtraAccountMeta {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        ExtraAccountMeta {
+            discriminator: bytes[0],
+            address_config: bytes[1..1 + PUBKEY_BYTES].try_into().unwrap(),
+            is_signer: bytes[1 + PUBKEY_BYTES] != 0,
+            is_writable: bytes[2 + PUBKEY_BYTES] != 0,
+        }
+    }
+
+    /// the address of this account, if it is a literal rather than a pda
+    pub fn address(&self) -> Option<Pubkey> {
+        (self.discriminator == 0).then(|| Pubkey::new_from_array(self.address_config))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ExtraAccountMetaAccount {
+    pub extra_account_metas: Vec<ExtraAccountMeta>,
+}
+
+impl ExtraAccountMetaAccount {
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        //  TLV layout:
+        //      [u8; 8]: execute instruction discriminator
+        //      u32: length of the value
+        //      u32: number of extra account metas
+        //      [ExtraAccountMeta]
+        let invalid_size = AccountDecodeError::InvalidSize { account: "extra account metas account", size: data.len() };
+        if data.len() < 16 {
+            return Err(invalid_size);
+        }
+        let discriminator: [u8; 8] = data[0..8].try_into().unwrap();
+        if discriminator != EXECUTE_INSTRUCTION_DISCRIMINATOR {
+            return Err(AccountDecodeError::InvalidDiscriminator { discriminator });
+        }
+        let length = u32::from_le_bytes(data[8..12].try_into().unwrap()) as usize;
+        let count = u32::from_le_bytes(data[12..16].try_into().unwrap()) as usize;
+        if length != 4 + count * EXTRA_ACCOUNT_META_SIZE || data.len() < 12 + length {
+            return Err(invalid_size);
+        }
+        let extra_account_metas = data[16..12 + length]
+            .chunks_exact(EXTRA_ACCOUNT_META_SIZE)
+            .map(ExtraAccountMeta::from_bytes)
+            .collect();
+        Ok(ExtraAccountMetaAccount { extra_account_metas })
+    }
+}
+
+#[cfg(feature = "serde")]
+pub(crate) mod as_string {
+    use std::fmt::Display;
+
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(value: &impl Display, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod as_strings {
+    use std::fmt::Display;
+
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer, T: Display>(values: &[T], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(values.iter().map(ToString::to_string))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use comptoken_utils::SEC_PER_DAY;
+
+    fn global_data_bytes(interests: &[f64]) -> Vec<u8> {
+        let mut data = vec![0_u8; GLOBAL_DATA_SIZE];
+        let global_data: &mut GlobalData = data.as_mut_slice().into();
+        global_data.config.distribution_bounty = 10;
+        global_data.config.days = Days::UTC;
+        global_data.valid_blockhashes.valid_blockhash = Hash::new_from_array([1; HASH_BYTES]);
+        global_data.valid_blockhashes.valid_blockhash_time = SEC_PER_DAY;
+        global_data.valid_blockhashes.next_proof_scheme = ProofSchemeKind::Blake3;
+        global_data.daily_distribution_data.yesterday_supply = 1_000;
+        global_data.daily_distribution_data.last_daily_distribution_time = 2 * SEC_PER_DAY;
+        global_data.record_mining(4);
+        global_data.record_proof(true);
+        // stored oldest first, starting from `oldest_interest`
+        global_data.daily_distribution_data.historic_interests[..interests.len()].copy_from_slice(interests);
+        global_data.daily_distribution_data.oldest_interest = interests.len();
+        data
+    }
+
+    #[test]
+    fn test_global_data_from_bytes() {
+        let data = global_data_bytes(&[0.5, 0.25]);
+        // offset by one byte so the data is definitely unaligned
+        let mut unaligned = vec![0_u8];
+        unaligned.extend(&data);
+        let global_data = GlobalDataAccount::from_bytes(&unaligned[1..]).unwrap();
+
+        assert_eq!(global_data.config.distribution_bounty, 10);
+        assert_eq!(global_data.valid_blockhashes.valid_blockhash, Hash::new_from_array([1; HASH_BYTES]));
+        assert_eq!(global_data.valid_blockhashes.valid_proof_scheme, ProofSchemeKind::Sha256);
+        assert_eq!(global_data.valid_blockhashes.next_proof_scheme, ProofSchemeKind::Blake3);
+        assert_eq!(global_data.daily_distribution_data.yesterday_supply, 1_000);
+        assert_eq!(global_data.daily_distribution_data.mined_since_last_distribution, 4);
+        assert_eq!(global_data.ledger.total_mined, 4);
+        assert_eq!(global_data.daily_stats.proofs_since_last_distribution, 1);
+        assert_eq!(global_data.daily_stats.miners_since_last_distribution, 1);
+        assert!(global_data.daily_stats.historic_stats.is_empty(), "no day has ended yet");
+        assert_eq!(global_data.daily_distribution_data.historic_interests[..3], [0.25, 0.5, 0.]);
+        assert_eq!(global_data.daily_distribution_data.historic_interests.len(), 365);
+
+        assert!(GlobalDataAccount::from_bytes(&data[1..]).is_err());
+        let mut invalid_scheme = data.clone();
+        invalid_scheme[offset_of!(GlobalData, valid_blockhashes.next_proof_scheme)] = 3;
+        assert_eq!(
+            GlobalDataAccount::from_bytes(&invalid_scheme),
+            Err(AccountDecodeError::InvalidField {
+                account: "global data account",
+                field: "next proof scheme",
+                value: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_user_data_from_bytes() {
+        let mut data = vec![0_u8; user_data_size(2)];
+        data[0..8].copy_from_slice(&SEC_PER_DAY.to_le_bytes());
+        data[8..16].copy_from_slice(&100_u64.to_le_bytes());
+        data[16..24].copy_from_slice(&7_u64.to_le_bytes());
+        data[24..56].copy_from_slice(&[1; PUBKEY_BYTES]);
+        data[56] = 254;
+        data[64..72].copy_from_slice(&1_usize.to_le_bytes());
+        data[72..104].copy_from_slice(&[2; HASH_BYTES]);
+        data[104..112].copy_from_slice(&1_usize.to_le_bytes());
+        data[112..144].copy_from_slice(&[4; HASH_BYTES]);
+        data[144..176].copy_from_slice(&[5; HASH_BYTES]);
+        data[176..208].copy_from_slice(&[3; HASH_BYTES]);
+        let user_data = UserDataAccount::from_bytes(&data).unwrap();
+
+        assert_eq!(user_data.last_interest_payout_date, SEC_PER_DAY);
+        assert_eq!(user_data.last_settled_balance, 100);
+        assert_eq!(user_data.accrued_interest, 7);
+        assert_eq!(user_data.user_identity, Pubkey::new_from_array([1; PUBKEY_BYTES]));
+        assert_eq!(user_data.bump, 254);
+        assert_eq!(user_data.recent_blockhash, Hash::new_from_array([2; HASH_BYTES]));
+        assert_eq!(user_data.proofs, [Hash::new_from_array([3; HASH_BYTES])]);
+        assert_eq!(user_data.previous_blockhash, Hash::new_from_array([4; HASH_BYTES]));
+        assert_eq!(user_data.previous_proofs, [Hash::new_from_array([5; HASH_BYTES])]);
+        assert_eq!(user_data.capacity, 2);
+
+        let global_data = GlobalDataAccount::from_bytes(&global_data_bytes(&[0.5, 0.25])).unwrap();
+        assert_eq!(user_data.owed_interest(&global_data, 1_000), 32, "deposits since the last settlement don't earn");
+        assert_eq!(user_data.owed_interest(&global_data, 40), 17);
+        let mut unsettled = user_data.clone();
+        unsettled.last_interest_payout_date = 0;
+        assert_eq!(unsettled.owed_interest(&global_data, 100), 95);
+        unsettled.last_interest_payout_date = 2 * SEC_PER_DAY;
+        assert_eq!(unsettled.owed_interest(&global_data, 100), 7, "only distributed days earn interest");
+
+        data[64..72].copy_from_slice(&2_usize.to_le_bytes());
+        assert_eq!(
+            UserDataAccount::from_bytes(&data),
+            Err(AccountDecodeError::InvalidUserDataLength { length: 3, capacity: 2 })
+        );
+        assert!(UserDataAccount::from_bytes(&data[1..]).is_err());
+    }
+
+    #[test]
+    fn test_user_identity_from_bytes() {
+        let mut data = vec![0_u8; USER_IDENTITY_SIZE];
+        data[0..32].copy_from_slice(&[1; PUBKEY_BYTES]);
+        data[32..40].copy_from_slice(&SEC_PER_DAY.to_le_bytes());
+        data[40] = 1;
+        data[41] = 253;
+        let identity = UserIdentityAccount::from_bytes(&data).unwrap();
+
+        assert_eq!(identity.owner, Pubkey::new_from_array([1; PUBKEY_BYTES]));
+        assert_eq!(identity.last_ubi_payout_date, SEC_PER_DAY);
+        assert!(identity.is_verified_human);
+        assert_eq!(identity.bump, 253);
+        data[40] = 2;
+        assert!(UserIdentityAccount::from_bytes(&data).unwrap().is_verified_human, "any nonzero byte is verified");
+        assert!(UserIdentityAccount::from_bytes(&data[1..]).is_err());
+    }
+
+    #[test]
+    fn test_extra_account_metas_from_bytes() {
+        let mut data = Vec::from(EXECUTE_INSTRUCTION_DISCRIMINATOR);
+        data.extend((4 + 2 * EXTRA_ACCOUNT_META_SIZE as u32).to_le_bytes());
+        data.extend(2_u32.to_le_bytes());
+        data.push(0);
+        data.extend([4; PUBKEY_BYTES]);
+        data.extend([0, 0]);
+        data.push(1 << 7 | 5);
+        data.extend([3, 0].into_iter().chain([0; PUBKEY_BYTES - 2]));
+        data.extend([0, 1]);
+        let extra_account_metas = ExtraAccountMetaAccount::from_bytes(&data).unwrap().extra_account_metas;
+
+        assert_eq!(extra_account_metas.len(), 2);
+        assert_eq!(extra_account_metas[0].address(), Some(Pubkey::new_from_array([4; PUBKEY_BYTES])));
+        assert_eq!(extra_account_metas[1].address(), None);
+        assert!(extra_account_metas[1].is_writable);
+
+        data[0] = 0;
+        assert!(matches!(
+            ExtraAccountMetaAccount::from_bytes(&data),
+            Err(AccountDecodeError::InvalidDiscriminator { .. })
+        ));
+    }
+}
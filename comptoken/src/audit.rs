@@ -0,0 +1,201 @@
+use std::fmt::{self, Display};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use comptoken_utils::TimeSource;
+
+use crate::global_data::GlobalData;
+
+/// The invariants checked by the `audit` instruction, and the values they were checked against.
+///
+/// The bank checks compare each bank's balance with what the ledger says was distributed to it less what was claimed
+/// from it. Only the program moves comptokens in and out of the banks and it records each move in the ledger, so these
+/// checks can only fail if a bank or the ledger was changed some other way. They don't show that the banks can pay
+/// every wallet what it is owed: interest is only worked out for a wallet when it settles, and the total owed across
+/// all wallets isn't tracked, so claims exceeding what was distributed go unnoticed until a claim fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct AuditReport {
+    pub supply: u64,
+    pub total_issued: u64,
+    pub interest_bank_balance: u64,
+    pub interest_liabilities: u64,
+    pub ubi_bank_balance: u64,
+    pub ubi_liabilities: u64,
+    pub is_blockhash_stale: bool,
+    pub is_distribution_overdue: bool,
+    // the ledger records more interest or UBI claimed than distributed, in which case that liability is reported as 0
+    pub is_ledger_inconsistent: bool,
+}
+
+// bits of the failed checks byte, ensure these remain consistent with audit.js
+pub const SUPPLY_MISMATCH: u8 = 1 << 0;
+pub const INTEREST_BANK_INSOLVENT: u8 = 1 << 1;
+pub const UBI_BANK_INSOLVENT: u8 = 1 << 2;
+pub const BLOCKHASH_STALE: u8 = 1 << 3;
+pub const DISTRIBUTION_OVERDUE: u8 = 1 << 4;
+pub const LEDGER_INCONSISTENT: u8 = 1 << 5;
+
+impl AuditReport {
+    //  return data:
+    //      u8: the failed checks, a bitmask of the constants above
+    //      u64: the mint's supply
+    //      u64: the total issued according to the ledger
+    //      u64: the interest bank's balance
+    //      u64: the interest distributed to the interest bank and not yet claimed
+    //      u64: the UBI bank's balance
+    //      u64: the UBI distributed to the UBI bank and not yet claimed
+    pub const SIZE: usize = 1 + 6 * 8;
+
+    pub fn new(
+        global_data: &GlobalData, supply: u64, interest_bank_balance: u64, ubi_bank_balance: u64,
+        clock: &impl TimeSource,
+    ) -> Self {
+        let valid_blockhashes = &global_data.valid_blockhashes;
+        let days = &global_data.config.days;
+        let interest_liabilities = global_data.ledger.interest_liabilities();
+        let ubi_liabilities = global_data.ledger.ubi_liabilities();
+        AuditReport {
+            supply,
+            total_issued: global_data.ledger.total_issued(),
+            interest_bank_balance,
+            interest_liabilities: interest_liabilities.unwrap_or_default(),
+            ubi_bank_balance,
+            ubi_liabilities: ubi_liabilities.unwrap_or_default(),
+            is_blockhash_stale: valid_blockhashes.is_valid_blockhash_stale(days, clock)
+                || valid_blockhashes.is_announced_blockhash_stale(days, clock),
+            is_distribution_overdue: global_data.daily_distribution_data.is_distribution_overdue(days, clock.now()),
+            is_ledger_inconsistent: interest_liabilities.is_none() || ubi_liabilities.is_none(),
+        }
+    }
+
+    pub fn failed_checks(&self) -> u8 {
+        let mut failed_checks = 0;
+        if self.supply != self.total_issued {
+            failed_checks |= SUPPLY_MISMATCH;
+        }
+        if self.interest_bank_balance < self.interest_liabilities {
+            failed_checks |= INTEREST_BANK_INSOLVENT;
+        }
+        if self.ubi_bank_balance < self.ubi_liabilities {
+            failed_checks |= UBI_BANK_INSOLVENT;
+        }
+        if self.is_blockhash_stale {
+            failed_checks |= BLOCKHASH_STALE;
+        }
+        if self.is_distribution_overdue {
+            failed_checks |= DISTRIBUTION_OVERDUE;
+        }
+        if self.is_ledger_inconsistent {
+            failed_checks |= LEDGER_INCONSISTENT;
+        }
+        failed_checks
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failed_checks() == 0
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(Self::SIZE);
+        data.push(self.failed_checks());
+        for value in [
+            self.supply,
+            self.total_issued,
+            self.interest_bank_balance,
+            self.interest_liabilities,
+            self.ubi_bank_balance,
+            self.ubi_liabilities,
+        ] {
+            data.extend(value.to_le_bytes());
+        }
+        data
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::SIZE {
+            return None;
+        }
+        let failed_checks = data[0];
+        let value = |i: usize| u64::from_le_bytes(data[1 + 8 * i..9 + 8 * i].try_into().unwrap());
+        Some(AuditReport {
+            supply: value(0),
+            total_issued: value(1),
+            interest_bank_balance: value(2),
+            interest_liabilities: value(3),
+            ubi_bank_balance: value(4),
+            ubi_liabilities: value(5),
+            is_blockhash_stale: failed_checks & BLOCKHASH_STALE != 0,
+            is_distribution_overdue: failed_checks & DISTRIBUTION_OVERDUE != 0,
+            is_ledger_inconsistent: failed_checks & LEDGER_INCONSISTENT != 0,
+        })
+    }
+}
+
+impl Display for AuditReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let check = |failed: u8| if self.failed_checks() & failed == 0 { "pass" } else { "FAIL" };
+        writeln!(f, "Supply: {} ({} issued) {}", self.supply, self.total_issued, check(SUPPLY_MISMATCH))?;
+        writeln!(
+            f,
+            "Interest Bank: {} ({} distributed and unclaimed) {}",
+            self.interest_bank_balance,
+            self.interest_liabilities,
+            check(INTEREST_BANK_INSOLVENT)
+        )?;
+        writeln!(
+            f,
+            "UBI Bank: {} ({} distributed and unclaimed) {}",
+            self.ubi_bank_balance,
+            self.ubi_liabilities,
+            check(UBI_BANK_INSOLVENT)
+        )?;
+        writeln!(f, "Blockhash Fresh: {}", check(BLOCKHASH_STALE))?;
+        writeln!(f, "Distribution On Time: {}", check(DISTRIBUTION_OVERDUE))?;
+        write!(f, "Ledger Consistent: {}", check(LEDGER_INCONSISTENT))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use comptoken_utils::{Days, MockClock};
+
+    use super::*;
+
+    #[test]
+    fn test_report_round_trip() {
+        let report = AuditReport {
+            supply: 100,
+            total_issued: 90,
+            interest_bank_balance: 5,
+            interest_liabilities: 5,
+            ubi_bank_balance: 4,
+            ubi_liabilities: 5,
+            is_blockhash_stale: false,
+            is_distribution_overdue: true,
+            is_ledger_inconsistent: false,
+        };
+        assert_eq!(report.failed_checks(), SUPPLY_MISMATCH | UBI_BANK_INSOLVENT | DISTRIBUTION_OVERDUE);
+        assert!(!report.passed());
+
+        let data = report.to_bytes();
+        assert_eq!(data.len(), AuditReport::SIZE);
+        assert_eq!(AuditReport::from_bytes(&data), Some(report));
+    }
+
+    #[test]
+    fn test_claims_exceeding_distributions_fail() {
+        let mut data = vec![0_u8; std::mem::size_of::<GlobalData>()];
+        let global_data: &mut GlobalData = data.as_mut_slice().into();
+        global_data.config.days = Days::UTC;
+        global_data.ledger.total_ubi_distributed = 3;
+        global_data.ledger.total_ubi_claimed = 4;
+        let clock = MockClock::new(0);
+
+        let report = AuditReport::new(global_data, 0, 0, 0, &clock);
+        assert_eq!(report.ubi_liabilities, 0);
+        assert!(report.is_ledger_inconsistent);
+        assert_eq!(report.failed_checks() & (LEDGER_INCONSISTENT | UBI_BANK_INSOLVENT), LEDGER_INCONSISTENT);
+    }
+}
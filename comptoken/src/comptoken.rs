@@ -1,60 +1,71 @@
+pub mod accounts;
+pub mod audit;
 mod comptoken_proof;
 mod constants;
 mod global_data;
+pub mod proof_scheme;
+pub mod query;
+#[cfg(feature = "testmode")]
+mod testmode;
 mod verify_accounts;
 
 extern crate bs58;
 
 use spl_token_2022::{
-    extension::StateWithExtensions,
-    instruction::mint_to,
+    extension::{metadata_pointer, transfer_hook, BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+    instruction::{initialize_mint2, mint_to},
     onchain,
     solana_program::{
         account_info::{next_account_info, AccountInfo},
-        entrypoint,
-        hash::HASH_BYTES,
+        instruction::AccountMeta,
         msg,
         program::set_return_data,
         program_error::ProgramError,
         pubkey::Pubkey,
+        rent::Rent,
+        system_instruction,
+        sysvar::Sysvar,
     },
     state::{Account, Mint},
 };
+use spl_token_metadata_interface::{
+    instruction::{initialize as initialize_metadata, update_field},
+    state::{Field, TokenMetadata},
+};
+use spl_transfer_hook_interface::instruction::initialize_extra_account_meta_list;
 
 use comptoken_utils::{
-    create_pda, get_current_time, invoke_signed_verified, normalize_time,
-    user_data::{UserData, USER_DATA_MIN_SIZE},
-    SEC_PER_DAY,
+    config::{Bumps, Config},
+    create_pda, invoke_signed_verified,
+    user_data::{user_data_size, UserData},
+    user_identity::{UserIdentity, USER_IDENTITY_SIZE},
+    verbose_msg, Days, SysvarClock, TimeSource,
 };
 
+use audit::AuditReport;
 use comptoken_proof::ComptokenProof;
 use constants::*;
 use global_data::{daily_distribution_data::DailyDistributionValues, GlobalData};
+use proof_scheme::ProofSchemeKind;
+use query::QuerySelector;
 use verify_accounts::*;
 
 // declare and export the program's entrypoint
-entrypoint!(process_instruction);
+#[cfg(not(feature = "no-entrypoint"))]
+spl_token_2022::solana_program::entrypoint!(process_instruction);
 
 type ProgramResult = Result<(), ProgramError>;
 
 const GLOBAL_DATA_ACCOUNT_SPACE: u64 = std::mem::size_of::<GlobalData>() as u64;
 
-mod generated;
-use generated::{
-    COMPTOKEN_MINT_ADDRESS, COMPTO_GLOBAL_DATA_ACCOUNT_SEEDS, COMPTO_INTEREST_BANK_ACCOUNT_SEEDS,
-    COMPTO_UBI_BANK_ACCOUNT_SEEDS,
-};
-
-const INTEREST_BANK_SPACE: u64 = 256; // TODO get actual size
-const UBI_BANK_SPACE: u64 = 256; // TODO get actual size
-
 // program entrypoint's implementation
 pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
-    msg!("instruction_data: {:?}", instruction_data);
+    verbose_msg!("instruction_data: {:?}", instruction_data);
     match instruction_data[0] {
+        #[cfg(feature = "testmode")]
         0 => {
             msg!("Test Mint");
-            test_mint(program_id, accounts, &instruction_data[1..])
+            testmode::test_mint(program_id, accounts, &instruction_data[1..])
         }
         1 => {
             msg!("Mint New Comptokens");
@@ -80,6 +91,58 @@ pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], instru
             msg!("Get Owed Comptokens");
             get_owed_comptokens(program_id, accounts, &instruction_data[1..])
         }
+        7 => {
+            msg!("Get Distribution Status");
+            get_distribution_status(program_id, accounts, &instruction_data[1..])
+        }
+        8 => {
+            msg!("Update Comptoken Metadata");
+            update_comptoken_metadata(program_id, accounts, &instruction_data[1..])
+        }
+        9 => {
+            msg!("Create User Identity");
+            create_user_identity(program_id, accounts, &instruction_data[1..])
+        }
+        10 => {
+            msg!("Set Proof Scheme");
+            set_proof_scheme(program_id, accounts, &instruction_data[1..])
+        }
+        11 => {
+            msg!("Audit");
+            audit(program_id, accounts, &instruction_data[1..])
+        }
+        12 => {
+            msg!("Preview Owed Comptokens");
+            preview_owed_comptokens(program_id, accounts, &instruction_data[1..])
+        }
+        13 => {
+            msg!("Query Global Data");
+            query_global_data(program_id, accounts, &instruction_data[1..])
+        }
+        14 => {
+            msg!("Settle Transfer");
+            settle_transfer(program_id, accounts, &instruction_data[1..])
+        }
+        #[cfg(feature = "testmode")]
+        200 => {
+            msg!("Test Set Last Interest Payout Date");
+            testmode::set_last_interest_payout_date(program_id, accounts, &instruction_data[1..])
+        }
+        #[cfg(feature = "testmode")]
+        201 => {
+            msg!("Test Set Verified Human");
+            testmode::set_verified_human(program_id, accounts, &instruction_data[1..])
+        }
+        #[cfg(feature = "testmode")]
+        202 => {
+            msg!("Test Inject Historic Interests");
+            testmode::inject_historic_interests(program_id, accounts, &instruction_data[1..])
+        }
+        #[cfg(feature = "testmode")]
+        203 => {
+            msg!("Test Force Blockhash Rotation");
+            testmode::force_blockhash_rotation(program_id, accounts, &instruction_data[1..])
+        }
         _ => {
             msg!("Invalid Instruction");
             Err(ProgramError::InvalidInstructionData)
@@ -87,47 +150,24 @@ pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], instru
     }
 }
 
-pub fn test_mint(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
-    //  accounts order:
-    //      Comptoken Mint account
-    //      Testuser Comptoken Wallet
-    //      Global Data (also Mint Authority)
-    //      Solana Token 2022
-
-    msg!("instruction_data: {:?}", instruction_data);
-
-    let account_info_iter = &mut accounts.iter();
-    let comptoken_mint_account = next_account_info(account_info_iter)?;
-    msg!("Comptoken Mint Key: {:?}", comptoken_mint_account.key);
-    let user_comptoken_wallet_account = next_account_info(account_info_iter)?;
-    msg!("User Comptoken Wallet Key: {:?}", user_comptoken_wallet_account.key);
-    let global_data_account = next_account_info(account_info_iter)?;
-    msg!("Global Data Key: {:?}", global_data_account.key);
-    let _solana_token_account = next_account_info(account_info_iter)?;
-    msg!("Solana Token Key: {:?}", _solana_token_account.key);
-
-    let comptoken_mint_account = verify_comptoken_mint(comptoken_mint_account, true);
-    let user_comptoken_wallet_account =
-        verify_user_comptoken_wallet_account(user_comptoken_wallet_account, false, true);
-    let global_data_account = verify_global_data_account(global_data_account, program_id, false);
-
-    let amount = 2;
-
-    mint(
-        &global_data_account,
-        &user_comptoken_wallet_account,
-        amount,
-        &[&comptoken_mint_account, &user_comptoken_wallet_account, &global_data_account],
-    )
-}
-
 pub fn mint_comptokens(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
     //  accounts order:
     //      Comptoken Mint (writable)
     //      User Comptoken Wallet (writable)
-    //      Global Data (also Mint Authority)
+    //      Global Data (also Mint Authority) (writable)
     //      User Data (writable)
     //      Solana Token 2022
+    //      optionally, to pay out owed interest and UBI first so the wallet stays current:
+    //          Comptoken Interest Bank (writable)
+    //          Comptoken UBI Bank (writable)
+    //          Extra Account Metas Account
+    //          Transfer Hook Program
+    //          Comptoken Program
+    //          Interest Bank Data PDA (writable) (doesn't actually exist)
+    //          UBI Bank Data PDA (writable) (doesn't actually exist)
+    //          User's Identity (writable) (the one the user data links to)
+    //  instruction data:
+    //      the proof, either a v1 or a v2 layout (see comptoken_proof.rs)
 
     let account_info_iter = &mut accounts.iter();
     let _comptoken_mint_account = next_account_info(account_info_iter)?;
@@ -136,30 +176,68 @@ pub fn mint_comptokens(program_id: &Pubkey, accounts: &[AccountInfo], instructio
     let user_data_account = next_account_info(account_info_iter)?;
     let _solana_token_account = next_account_info(account_info_iter)?;
 
-    let comptoken_mint_account = verify_comptoken_mint(_comptoken_mint_account, true);
-    let global_data_account = verify_global_data_account(global_data_account, program_id, false);
+    let global_data_account = verify_global_data_account(global_data_account, program_id, true);
     let global_data: &mut GlobalData = (&global_data_account).into();
+    let config = global_data.config;
+    let comptoken_mint_account = verify_comptoken_mint(_comptoken_mint_account, &config, true);
     let user_comptoken_wallet_account =
-        verify_user_comptoken_wallet_account(user_comptoken_wallet_account, false, true);
+        verify_user_comptoken_wallet_account(user_comptoken_wallet_account, &config, false, true);
     let proof = ComptokenProof::verify_submitted_proof(
         &user_comptoken_wallet_account,
         instruction_data,
-        &global_data.valid_blockhashes,
+        global_data,
+        program_id,
+        &SysvarClock,
     );
-    let (user_data_account, _) =
+    let user_data_account =
         verify_user_data_account(user_data_account, &user_comptoken_wallet_account, program_id, true);
+    let settlement_accounts = match account_info_iter.as_slice() {
+        [] => None,
+        [unpaid_interest_bank, unpaid_ubi_bank, extra_account_metas_account, transfer_hook_program, compto_program, interest_data_pda, ubi_data_pda, user_identity_account] => {
+            Some(SettlementAccounts::verify(
+                [
+                    unpaid_interest_bank,
+                    unpaid_ubi_bank,
+                    extra_account_metas_account,
+                    transfer_hook_program,
+                    compto_program,
+                    interest_data_pda,
+                    ubi_data_pda,
+                    user_identity_account,
+                ],
+                &user_data_account,
+                &config,
+                program_id,
+            ))
+        }
+        _ => return Err(ProgramError::NotEnoughAccountKeys),
+    };
 
-    msg!("data/accounts verified");
+    verbose_msg!("data/accounts verified");
     let amount = 2;
     // now save the hash to the account, returning an error if the hash already exists
-    store_hash(proof, &user_data_account);
-    msg!("stored the proof");
-    mint(
-        &global_data_account,
-        &user_comptoken_wallet_account,
-        amount,
-        &[&comptoken_mint_account, &user_comptoken_wallet_account, &global_data_account],
-    )?;
+    let is_new_miner = store_hash(proof, &user_data_account, global_data, &SysvarClock);
+    verbose_msg!("stored the proof");
+    global_data.record_proof(is_new_miner);
+    // the balance is about to change, so settle the interest on the old balance first
+    match &settlement_accounts {
+        Some(settlement_accounts) => pay_owed_comptokens(
+            &user_data_account,
+            &user_comptoken_wallet_account,
+            &comptoken_mint_account,
+            &global_data_account,
+            settlement_accounts,
+        )?,
+        None => {
+            let user_data: &mut UserData = (&user_data_account).into();
+            settle_interest(user_data, &user_comptoken_wallet_account, global_data);
+        }
+    }
+    // mined comptokens earn interest from today
+    let user_data: &mut UserData = (&user_data_account).into();
+    user_data.last_settled_balance += amount;
+    global_data.record_mining(amount);
+    mint(&comptoken_mint_account, &global_data_account, &user_comptoken_wallet_account, amount, &config)?;
 
     Ok(())
 }
@@ -168,16 +246,25 @@ pub fn initialize_comptoken_program(
     program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8],
 ) -> ProgramResult {
     //  accounts order:
-    //      Payer (probably COMPTO's account)
+    //      Payer (probably COMPTO's account, becomes the admin)
     //      Global Data Account (also mint authority)
     //      Comptoken Interest Bank
     //      Comptoken UBI Bank
-    //      Comptoken Mint
+    //      Comptoken Mint (signer, writable) (a new keypair, which keys the rest of the instance)
     //      Solana Program
     //      Solana Token 2022 Program
     //      Solana SlotHashes Sysvar
-
-    msg!("instruction_data: {:?}", instruction_data);
+    //      Comptoken Transfer Hook Program
+    //      Transfer Hook Extra Account Metas (writable)
+    //  instruction data:
+    //      u64: the bounty paid to the daily distribution caller
+    //      i64: the blockhash grace period in seconds
+    //      i64: the unix time until which v1 proofs are accepted
+    //      i64: the length of a day in seconds
+    //      i64: the offset in seconds of the start of each day from a multiple of its length
+    //      string: the comptoken name
+    //      string: the comptoken symbol
+    //      string: the comptoken metadata uri
 
     let account_info_iter = &mut accounts.iter();
     let payer_account = next_account_info(account_info_iter)?;
@@ -185,59 +272,127 @@ pub fn initialize_comptoken_program(
     let unpaid_interest_bank = next_account_info(account_info_iter)?;
     let unpaid_ubi_bank = next_account_info(account_info_iter)?;
     let comptoken_mint = next_account_info(account_info_iter)?;
-    let _solana_program = next_account_info(account_info_iter)?;
+    let solana_program = next_account_info(account_info_iter)?;
     let _token_2022_program = next_account_info(account_info_iter)?;
     let slot_hashes_account = next_account_info(account_info_iter)?;
+    let transfer_hook_program = next_account_info(account_info_iter)?;
+    let extra_account_metas_account = next_account_info(account_info_iter)?;
 
     let payer_account = verify_payer_account(payer_account);
-    let global_data_account = verify_global_data_account(global_data_account, program_id, true);
-    let unpaid_interest_bank = verify_interest_bank_account(unpaid_interest_bank, program_id, true);
-    let unpaid_ubi_bank = verify_ubi_bank_account(unpaid_ubi_bank, program_id, true);
-    let comptoken_mint = verify_comptoken_mint(comptoken_mint, false);
+    let comptoken_mint = verify_new_comptoken_mint(comptoken_mint);
+    let (global_data_account, global_data_bump) =
+        verify_new_global_data_account(global_data_account, &comptoken_mint, program_id);
+    let (unpaid_interest_bank, interest_bank_bump) =
+        verify_new_interest_bank_account(unpaid_interest_bank, &comptoken_mint, program_id);
+    let (unpaid_ubi_bank, ubi_bank_bump) = verify_new_ubi_bank_account(unpaid_ubi_bank, &comptoken_mint, program_id);
+    let solana_program = verify_system_program(solana_program);
     let slot_hashes_account = verify_slothashes_account(slot_hashes_account);
-
-    let first_8_bytes: [u8; 8] = instruction_data[0..8].try_into().unwrap();
-    let lamports_global_data = u64::from_le_bytes(first_8_bytes);
-    let lamports_interest_bank = u64::from_le_bytes(instruction_data[8..16].try_into().unwrap());
-    let lamports_ubi_bank = u64::from_le_bytes(instruction_data[16..24].try_into().unwrap());
-    msg!("Lamports global data: {:?}", lamports_global_data);
-    msg!("Lamports interest bank: {:?}", lamports_interest_bank);
-    msg!("Lamports ubi bank: {:?}", lamports_ubi_bank);
-
+    let transfer_hook_program = verify_new_transfer_hook_program(transfer_hook_program);
+    let (extra_account_metas_account, extra_account_metas_bump) =
+        verify_new_validation_account(extra_account_metas_account, &transfer_hook_program, &comptoken_mint);
+    // the transfer hook requires a "user data" account for the banks, but they don't actually exist
+    let (_, interest_bank_data_bump) = Pubkey::find_program_address(&[unpaid_interest_bank.key.as_ref()], program_id);
+    let (_, ubi_bank_data_bump) = Pubkey::find_program_address(&[unpaid_ubi_bank.key.as_ref()], program_id);
+
+    let distribution_bounty = u64::from_le_bytes(instruction_data[0..8].try_into().unwrap());
+    let blockhash_grace_period = i64::from_le_bytes(instruction_data[8..16].try_into().unwrap());
+    let v1_proof_deadline = i64::from_le_bytes(instruction_data[16..24].try_into().unwrap());
+    let days = Days {
+        length: i64::from_le_bytes(instruction_data[24..32].try_into().unwrap()),
+        offset: i64::from_le_bytes(instruction_data[32..40].try_into().unwrap()),
+    };
+    let (name, rest) = split_string(&instruction_data[40..]);
+    let (symbol, rest) = split_string(rest);
+    let (uri, _) = split_string(rest);
+    verbose_msg!("Distribution bounty: {:?}", distribution_bounty);
+    verbose_msg!("Blockhash grace period: {:?}", blockhash_grace_period);
+    verbose_msg!("V1 proof deadline: {:?}", v1_proof_deadline);
+    verbose_msg!("Days: {:?}", days);
+    verbose_msg!("Metadata: {:?} {:?} {:?}", name, symbol, uri);
+
+    let config = Config {
+        distribution_bounty,
+        blockhash_grace_period,
+        admin: *payer_account.key,
+        v1_proof_deadline,
+        days,
+        mint: *comptoken_mint.key,
+        transfer_hook_program: *transfer_hook_program.key,
+        interest_bank: *unpaid_interest_bank.key,
+        ubi_bank: *unpaid_ubi_bank.key,
+        bumps: Bumps {
+            global_data: global_data_bump,
+            interest_bank: interest_bank_bump,
+            ubi_bank: ubi_bank_bump,
+            interest_bank_data: interest_bank_data_bump,
+            ubi_bank_data: ubi_bank_data_bump,
+            extra_account_metas: extra_account_metas_bump,
+        },
+    };
+    verbose_msg!("{:?}", config);
+
+    let rent = Rent::get()?;
     create_pda(
         &payer_account,
         &global_data_account,
-        lamports_global_data,
+        rent.minimum_balance(GLOBAL_DATA_ACCOUNT_SPACE as usize),
         GLOBAL_DATA_ACCOUNT_SPACE,
         program_id,
-        &[COMPTO_GLOBAL_DATA_ACCOUNT_SEEDS],
+        &[&config.global_data_seeds()],
     )?;
-    msg!("created global data account");
+    verbose_msg!("created global data account");
+    let metadata = TokenMetadata { name, symbol, uri, ..Default::default() };
+    create_comptoken_mint(&payer_account, &comptoken_mint, &global_data_account, metadata, &config)?;
+    verbose_msg!("created comptoken mint");
+    let bank_space = comptoken_account_space(&comptoken_mint)?;
     create_pda(
         &payer_account,
         &unpaid_interest_bank,
-        lamports_interest_bank,
-        INTEREST_BANK_SPACE,
+        rent.minimum_balance(bank_space),
+        bank_space as u64,
         &spl_token_2022::ID,
-        &[COMPTO_INTEREST_BANK_ACCOUNT_SEEDS],
+        &[&config.interest_bank_seeds()],
     )?;
-    msg!("created interest bank account");
+    verbose_msg!("created interest bank account");
     init_comptoken_account(&unpaid_interest_bank, &global_data_account, &[], &comptoken_mint)?;
-    msg!("initialized interest bank account");
+    verbose_msg!("initialized interest bank account");
     create_pda(
         &payer_account,
         &unpaid_ubi_bank,
-        lamports_interest_bank,
-        UBI_BANK_SPACE,
+        rent.minimum_balance(bank_space),
+        bank_space as u64,
         &spl_token_2022::ID,
-        &[COMPTO_UBI_BANK_ACCOUNT_SEEDS],
+        &[&config.ubi_bank_seeds()],
     )?;
-    msg!("created ubi bank account");
+    verbose_msg!("created ubi bank account");
     init_comptoken_account(&unpaid_ubi_bank, &global_data_account, &[], &comptoken_mint)?;
-    msg!("initialized ubi bank account");
+    verbose_msg!("initialized ubi bank account");
+
+    // the transfer hook requires the mint authority's signature, which is the global data account
+    let mut instruction = initialize_extra_account_meta_list(
+        transfer_hook_program.key,
+        extra_account_metas_account.key,
+        comptoken_mint.key,
+        global_data_account.key,
+        &[],
+    );
+    instruction.accounts.push(AccountMeta::new(*payer_account.key, true));
+    invoke_signed_verified(
+        &instruction,
+        &[
+            &extra_account_metas_account,
+            &comptoken_mint,
+            &global_data_account,
+            &solana_program,
+            &payer_account,
+            &transfer_hook_program,
+        ],
+        &[&config.global_data_seeds()],
+    )?;
+    verbose_msg!("initialized transfer hook extra account metas");
 
     let global_data: &mut GlobalData = (&global_data_account).into();
-    global_data.initialize(&slot_hashes_account);
+    global_data.initialize(config, &slot_hashes_account, &SysvarClock);
 
     Ok(())
 }
@@ -246,10 +401,14 @@ pub fn create_user_data_account(
     program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8],
 ) -> ProgramResult {
     //  Account Order
-    //      User's Solana Wallet (signer)
+    //      User's Solana Wallet (signer, owner of the comptoken wallet)
     //      User's Data (writable)
     //      User's Comptoken Wallet
     //      Solana Program
+    //      User's Identity (already created)
+    //      Comptoken Global Data
+    //  instruction data:
+    //      u64: the number of proofs the account can store
 
     let account_info_iter = &mut accounts.iter();
 
@@ -257,19 +416,25 @@ pub fn create_user_data_account(
     let user_data_account = next_account_info(account_info_iter)?;
     let user_comptoken_wallet_account = next_account_info(account_info_iter)?;
     let _solana_program = next_account_info(account_info_iter)?;
+    let user_identity_account = next_account_info(account_info_iter)?;
+    let global_data_account = next_account_info(account_info_iter)?;
 
+    let proof_capacity = u64::from_le_bytes(instruction_data[0..8].try_into().expect("correct size")) as usize;
+    assert!(proof_capacity > 0, "user data must be able to store at least one proof");
     // find space and minimum rent required for account
-    let rent_lamports = u64::from_le_bytes(instruction_data[0..8].try_into().expect("correct size"));
-    let space = usize::from_le_bytes(instruction_data[8..16].try_into().expect("correct size"));
-    msg!("space: {}", space);
-    assert!(space >= USER_DATA_MIN_SIZE);
-    assert!((space - USER_DATA_MIN_SIZE) % HASH_BYTES == 0);
+    let space = user_data_size(proof_capacity);
+    let rent_lamports = Rent::get()?.minimum_balance(space);
+    verbose_msg!("space: {}", space);
 
-    let payer_account = verify_payer_account(payer_account);
+    let global_data_account = verify_global_data_account(global_data_account, program_id, false);
+    let config = read_config(&global_data_account);
     let user_comptoken_wallet_account =
-        verify_user_comptoken_wallet_account(user_comptoken_wallet_account, false, false);
+        verify_user_comptoken_wallet_account(user_comptoken_wallet_account, &config, false, false);
+    let payer_account = verify_user_comptoken_wallet_owner(payer_account, &user_comptoken_wallet_account);
     let (user_data_account, bump) =
-        verify_user_data_account(user_data_account, &user_comptoken_wallet_account, program_id, true);
+        verify_new_user_data_account(user_data_account, &user_comptoken_wallet_account, program_id);
+    let user_identity_account =
+        verify_user_identity_account(user_identity_account, payer_account.key, &config, program_id, false);
 
     create_pda(
         &payer_account,
@@ -282,7 +447,42 @@ pub fn create_user_data_account(
 
     // initialize data account
     let user_data: &mut UserData = (&user_data_account).into();
-    user_data.initialize();
+    user_data.initialize(user_identity_account.key, bump, &config.days, &SysvarClock);
+
+    Ok(())
+}
+
+pub fn create_user_identity(program_id: &Pubkey, accounts: &[AccountInfo], _instruction_data: &[u8]) -> ProgramResult {
+    //  Account Order
+    //      User's Solana Wallet (signer, owner of the user's comptoken wallets)
+    //      User's Identity (writable)
+    //      Solana Program
+    //      Comptoken Global Data (identities are per instance)
+
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_account = next_account_info(account_info_iter)?;
+    let user_identity_account = next_account_info(account_info_iter)?;
+    let _solana_program = next_account_info(account_info_iter)?;
+    let global_data_account = next_account_info(account_info_iter)?;
+
+    let payer_account = verify_payer_account(payer_account);
+    let global_data_account = verify_global_data_account(global_data_account, program_id, false);
+    let config = read_config(&global_data_account);
+    let (user_identity_account, bump) =
+        verify_new_user_identity_account(user_identity_account, payer_account.key, &config, program_id);
+
+    create_pda(
+        &payer_account,
+        &user_identity_account,
+        Rent::get()?.minimum_balance(USER_IDENTITY_SIZE),
+        USER_IDENTITY_SIZE as u64,
+        program_id,
+        &[&[b"User Identity", config.mint.as_ref(), payer_account.key.as_ref(), &[bump]]],
+    )?;
+
+    let user_identity: &mut UserIdentity = (&user_identity_account).into();
+    user_identity.initialize(payer_account.key, bump, &config.days, &SysvarClock);
 
     Ok(())
 }
@@ -297,6 +497,8 @@ pub fn daily_distribution_event(
     //      Comptoken UBI Bank
     //      Solana Token Program
     //      Solana SlotHashes Sysvar
+    //      Caller's Comptoken Wallet (receives the distribution bounty)
+    //      Caller's User Data (writable)
 
     let account_info_iter = &mut accounts.iter();
     let comptoken_mint_account = next_account_info(account_info_iter)?;
@@ -305,15 +507,23 @@ pub fn daily_distribution_event(
     let unpaid_ubi_bank = next_account_info(account_info_iter)?;
     let _solana_token_account = next_account_info(account_info_iter)?;
     let slot_hashes_account = next_account_info(account_info_iter)?;
+    let caller_comptoken_wallet_account = next_account_info(account_info_iter)?;
+    let caller_user_data_account = next_account_info(account_info_iter)?;
 
-    let comptoken_mint_account = verify_comptoken_mint(comptoken_mint_account, false);
     let global_data_account = verify_global_data_account(global_data_account, program_id, true);
-    let unpaid_interest_bank = verify_interest_bank_account(unpaid_interest_bank, program_id, true);
-    let unpaid_ubi_bank = verify_ubi_bank_account(unpaid_ubi_bank, program_id, true);
+    let config = read_config(&global_data_account);
+    let comptoken_mint_account = verify_comptoken_mint(comptoken_mint_account, &config, false);
+    let unpaid_interest_bank = verify_interest_bank_account(unpaid_interest_bank, &config, true);
+    let unpaid_ubi_bank = verify_ubi_bank_account(unpaid_ubi_bank, &config, true);
     let slot_hashes_account = verify_slothashes_account(slot_hashes_account);
+    let caller_comptoken_wallet_account =
+        verify_user_comptoken_wallet_account(caller_comptoken_wallet_account, &config, false, true);
+    let caller_user_data_account =
+        verify_user_data_account(caller_user_data_account, &caller_comptoken_wallet_account, program_id, true);
 
     let interest_daily_distribution;
     let ubi_daily_distribution;
+    let bounty_daily_distribution;
     // scope to prevent reborrowing issues
     {
         let mut global_data_account_data = global_data_account.try_borrow_mut_data().unwrap();
@@ -321,30 +531,36 @@ pub fn daily_distribution_event(
         let mint_data = comptoken_mint_account.try_borrow_data().unwrap();
         let comptoken_mint = StateWithExtensions::<Mint>::unpack(mint_data.as_ref()).unwrap();
 
-        let current_time = get_current_time();
         assert!(
-            current_time > global_data.daily_distribution_data.last_daily_distribution_time + SEC_PER_DAY,
+            global_data.daily_distribution_data.is_distribution_due(&config.days, SysvarClock.now()),
             "daily distribution already called today"
         );
 
         DailyDistributionValues {
             interest_distributed: interest_daily_distribution,
             ubi_distributed: ubi_daily_distribution,
-        } = global_data.daily_distribution_event(comptoken_mint.base, &slot_hashes_account);
+            bounty_distributed: bounty_daily_distribution,
+        } = global_data.daily_distribution_event(comptoken_mint.base, &slot_hashes_account, &SysvarClock);
     }
     // mint to banks
-    mint(
-        &global_data_account,
-        &unpaid_interest_bank,
-        interest_daily_distribution,
-        &[&comptoken_mint_account, &global_data_account, &unpaid_interest_bank],
-    )?;
-    mint(
-        &global_data_account,
-        &unpaid_ubi_bank,
-        ubi_daily_distribution,
-        &[&comptoken_mint_account, &global_data_account, &unpaid_ubi_bank],
-    )?;
+    mint(&comptoken_mint_account, &global_data_account, &unpaid_interest_bank, interest_daily_distribution, &config)?;
+    mint(&comptoken_mint_account, &global_data_account, &unpaid_ubi_bank, ubi_daily_distribution, &config)?;
+    // reward the caller for keeping the distribution running
+    if bounty_daily_distribution > 0 {
+        let global_data: &mut GlobalData = (&global_data_account).into();
+        let user_data: &mut UserData = (&caller_user_data_account).into();
+        // the balance is about to change, so settle the interest on the old balance first
+        settle_interest(user_data, &caller_comptoken_wallet_account, global_data);
+        // the bounty earns interest from today
+        user_data.last_settled_balance += bounty_daily_distribution;
+        mint(
+            &comptoken_mint_account,
+            &global_data_account,
+            &caller_comptoken_wallet_account,
+            bounty_daily_distribution,
+            &config,
+        )?;
+    }
 
     Ok(())
 }
@@ -364,10 +580,14 @@ pub fn get_valid_blockhashes(program_id: &Pubkey, accounts: &[AccountInfo], _ins
     let global_data: &mut GlobalData = (&global_data_account).into();
     let valid_blockhashes = &mut global_data.valid_blockhashes;
 
-    valid_blockhashes.update(&slot_hashes_account);
+    valid_blockhashes.update(&slot_hashes_account, &global_data.config.days, &SysvarClock);
 
     let mut data = Vec::from(global_data.valid_blockhashes.valid_blockhash.to_bytes());
+    // the next blockhash, or the valid one again in the few slots before those a new announcement commits to exist
     data.extend(global_data.valid_blockhashes.announced_blockhash.to_bytes());
+    // the scheme to mine the valid blockhash with, and the one the announced blockhash will use
+    data.push(global_data.valid_blockhashes.valid_proof_scheme as u8);
+    data.push(global_data.valid_blockhashes.next_proof_scheme as u8);
     set_return_data(&data);
     Ok(())
 }
@@ -377,15 +597,16 @@ pub fn get_owed_comptokens(program_id: &Pubkey, accounts: &[AccountInfo], _instr
     //      [w] User's Data
     //      [w] User's Comptoken Wallet
     //      [] Comptoken Mint
-    //      [] Comptoken Global Data (also mint authority)
+    //      [w] Comptoken Global Data (also mint authority)
     //      [w] Comptoken Interest Bank
     //      [w] Comptoken UBI Bank
     //      [] Solana Token 2022 Program
     //      [] Extra Account Metas Account
     //      [] Transfer Hook Program
     //      [] Comptoken Program
-    //      [] Interest Bank Data PDA (doesn't actually exist)
-    //      [] UBI Bank Data PDA (doesn't actually exist)
+    //      [w] Interest Bank Data PDA (doesn't actually exist)
+    //      [w] UBI Bank Data PDA (doesn't actually exist)
+    //      [w] User's Identity (the one the user data links to)
 
     let account_info_iter = &mut accounts.iter();
     let user_data_account = next_account_info(account_info_iter)?;
@@ -400,85 +621,303 @@ pub fn get_owed_comptokens(program_id: &Pubkey, accounts: &[AccountInfo], _instr
     let compto_program = next_account_info(account_info_iter)?;
     let interest_data_pda /* not a real account */ = next_account_info(account_info_iter)?;
     let ubi_data_pda /* not a real account */ = next_account_info(account_info_iter)?;
+    let user_identity_account = next_account_info(account_info_iter)?;
 
+    let global_data_account = verify_global_data_account(global_data_account, program_id, true);
+    let config = read_config(&global_data_account);
     let user_comptoken_wallet_account =
-        verify_user_comptoken_wallet_account(user_comptoken_wallet_account, false, true);
-    let (user_data_account, _) =
+        verify_user_comptoken_wallet_account(user_comptoken_wallet_account, &config, false, true);
+    let user_data_account =
         verify_user_data_account(user_data_account, &user_comptoken_wallet_account, program_id, true);
-    let comptoken_mint_account = verify_comptoken_mint(comptoken_mint_account, false);
-    let global_data_account = verify_global_data_account(global_data_account, program_id, false);
-    let unpaid_interest_bank = verify_interest_bank_account(unpaid_interest_bank, program_id, true);
-    let unpaid_ubi_bank = verify_ubi_bank_account(unpaid_ubi_bank, program_id, true);
-    let transfer_hook_program = verify_transfer_hook_program(transfer_hook_program);
-    let validation_account =
-        verify_validation_account(extra_account_metas_account, &comptoken_mint_account, &transfer_hook_program);
-    let compto_program = VerifiedAccountInfo::verify_specific_address(compto_program, program_id, false, false);
-    let interest_data_pda = VerifiedAccountInfo::verify_pda(
-        interest_data_pda,
+    let comptoken_mint_account = verify_comptoken_mint(comptoken_mint_account, &config, false);
+    let settlement_accounts = SettlementAccounts::verify(
+        [
+            unpaid_interest_bank,
+            unpaid_ubi_bank,
+            extra_account_metas_account,
+            transfer_hook_program,
+            compto_program,
+            interest_data_pda,
+            ubi_data_pda,
+            user_identity_account,
+        ],
+        &user_data_account,
+        &config,
         program_id,
-        &[unpaid_interest_bank.key.as_ref()],
-        false,
-        false,
+    );
+
+    pay_owed_comptokens(
+        &user_data_account,
+        &user_comptoken_wallet_account,
+        &comptoken_mint_account,
+        &global_data_account,
+        &settlement_accounts,
     )
-    .0;
-    let ubi_data_pda =
-        VerifiedAccountInfo::verify_pda(ubi_data_pda, program_id, &[unpaid_ubi_bank.key.as_ref()], false, false).0;
+}
 
-    let interest;
-    let is_verified_human;
-    {
-        let user_wallet_data = user_comptoken_wallet_account.try_borrow_data().unwrap();
-        let user_comptoken_wallet = StateWithExtensions::<Account>::unpack(user_wallet_data.as_ref()).unwrap();
-        let global_data: &mut GlobalData = (&global_data_account).into();
-        let user_data: &mut UserData = (&user_data_account).into();
+pub fn preview_owed_comptokens(
+    program_id: &Pubkey, accounts: &[AccountInfo], _instruction_data: &[u8],
+) -> ProgramResult {
+    //  accounts order:
+    //      [] User's Data
+    //      [] User's Comptoken Wallet
+    //      [] Comptoken Global Data
+    //      [] User's Identity (the one the user data links to)
+
+    let account_info_iter = &mut accounts.iter();
+    let user_data_account = next_account_info(account_info_iter)?;
+    let user_comptoken_wallet_account = next_account_info(account_info_iter)?;
+    let global_data_account = next_account_info(account_info_iter)?;
+    let user_identity_account = next_account_info(account_info_iter)?;
+
+    let global_data_account = verify_global_data_account(global_data_account, program_id, false);
+    let config = read_config(&global_data_account);
+    let user_comptoken_wallet_account =
+        verify_user_comptoken_wallet_account(user_comptoken_wallet_account, &config, false, false);
+    let user_data_account =
+        verify_user_data_account(user_data_account, &user_comptoken_wallet_account, program_id, false);
+    let user_identity_account = {
+        let user_data: &UserData = (&user_data_account).into();
+        VerifiedAccountInfo::verify_specific_address(user_identity_account, &user_data.user_identity, false, false)
+    };
+
+    let global_data: &mut GlobalData = (&global_data_account).into();
+    let user_data: &UserData = (&user_data_account).into();
+    let user_identity: &UserIdentity = (&user_identity_account).into();
+    let daily_distribution_data = &global_data.daily_distribution_data;
+    let last_distribution_day = daily_distribution_data.last_daily_distribution_time;
+
+    // computed exactly as get_owed_comptokens would pay them out
+    let interest = user_data.owed_interest(
+        wallet_balance(&user_comptoken_wallet_account),
+        last_distribution_day,
+        &config.days,
+        |days, amount| daily_distribution_data.apply_n_interests(days, amount),
+    );
+    let ubi = if user_identity.is_ubi_owed(last_distribution_day) { UBI_AMOUNT } else { 0 };
+
+    //  return data:
+    //      u64: the interest owed
+    //      u64: the UBI owed
+    let mut data = Vec::from(interest.to_le_bytes());
+    data.extend(ubi.to_le_bytes());
+    set_return_data(&data);
+    Ok(())
+}
+
+pub fn get_distribution_status(
+    program_id: &Pubkey, accounts: &[AccountInfo], _instruction_data: &[u8],
+) -> ProgramResult {
+    //  accounts order:
+    //      [] Comptoken Global Data
+
+    let account_info_iter = &mut accounts.iter();
+    let global_data_account = next_account_info(account_info_iter)?;
+
+    let global_data_account = verify_global_data_account(global_data_account, program_id, false);
 
-        // get days since last update
-        let current_day = normalize_time(get_current_time());
-        let days_since_last_update = (current_day - user_data.last_interest_payout_date) / SEC_PER_DAY;
+    let global_data: &mut GlobalData = (&global_data_account).into();
+    let daily_distribution_data = &global_data.daily_distribution_data;
+
+    //  return data:
+    //      u8: whether the daily distribution can be called now
+    //      i64: the time after which the next daily distribution can be called
+    //      u64: the bounty paid to the caller
+    let days = &global_data.config.days;
+    let mut data = vec![daily_distribution_data.is_distribution_due(days, SysvarClock.now()) as u8];
+    data.extend(daily_distribution_data.next_distribution_time(days).to_le_bytes());
+    data.extend(global_data.config.distribution_bounty.to_le_bytes());
+    set_return_data(&data);
+    Ok(())
+}
+
+pub fn update_comptoken_metadata(
+    program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8],
+) -> ProgramResult {
+    //  accounts order:
+    //      Admin (signer, writable)
+    //      Comptoken Mint (writable)
+    //      Global Data (also metadata update authority)
+    //      Solana Program
+    //      Solana Token 2022 Program
+    //  instruction data:
+    //      u8: the field to update; 0 is the name, 1 the symbol, and 2 the uri
+    //      string: the new value
 
-        msg!("total before interest: {}", user_comptoken_wallet.base.amount);
-        // get interest
-        interest = global_data
-            .daily_distribution_data
-            .apply_n_interests(days_since_last_update as usize, user_comptoken_wallet.base.amount)
-            - user_comptoken_wallet.base.amount;
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let comptoken_mint = next_account_info(account_info_iter)?;
+    let global_data_account = next_account_info(account_info_iter)?;
+    let solana_program = next_account_info(account_info_iter)?;
+    let _token_2022_program = next_account_info(account_info_iter)?;
 
-        msg!("Interest: {}", interest);
-        user_data.last_interest_payout_date = current_day;
-        is_verified_human = user_data.is_verified_human;
+    let global_data_account = verify_global_data_account(global_data_account, program_id, false);
+    let config = read_config(&global_data_account);
+    let admin_account = verify_admin_account(admin_account, &config.admin);
+    let comptoken_mint = verify_comptoken_mint(comptoken_mint, &config, true);
+    let solana_program = verify_system_program(solana_program);
+
+    let field = match instruction_data[0] {
+        0 => Field::Name,
+        1 => Field::Symbol,
+        2 => Field::Uri,
+        _ => panic!("invalid metadata field"),
+    };
+    let (value, _) = split_string(&instruction_data[1..]);
+    verbose_msg!("{:?}: {:?}", field, value);
+
+    // token 2022 resizes the mint for the new value, but the mint has to already hold the rent for it
+    let new_space = {
+        let mint_data = comptoken_mint.try_borrow_data()?;
+        let mint = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+        let mut metadata = mint.get_variable_len_extension::<TokenMetadata>()?;
+        let old_metadata_size = metadata.tlv_size_of()?;
+        metadata.update(field.clone(), value.clone());
+        mint_data.len() - old_metadata_size + metadata.tlv_size_of()?
+    };
+    let required_lamports = Rent::get()?.minimum_balance(new_space);
+    if comptoken_mint.lamports() < required_lamports {
+        let instruction = system_instruction::transfer(
+            admin_account.key,
+            comptoken_mint.key,
+            required_lamports - comptoken_mint.lamports(),
+        );
+        invoke_signed_verified(&instruction, &[&admin_account, &comptoken_mint, &solana_program], &[])?;
     }
 
-    transfer(
-        &unpaid_interest_bank,
-        &user_comptoken_wallet_account,
-        &comptoken_mint_account,
-        &global_data_account,
-        &[
-            &validation_account,
-            &transfer_hook_program,
-            &compto_program,
-            &user_data_account,
-            &interest_data_pda,
-        ],
-        interest,
-    )?;
+    let instruction = update_field(&spl_token_2022::ID, comptoken_mint.key, global_data_account.key, field, value);
+    invoke_signed_verified(&instruction, &[&comptoken_mint, &global_data_account], &[&config.global_data_seeds()])
+}
 
-    // get ubi if verified
-    if is_verified_human {
-        transfer(
-            &unpaid_ubi_bank,
-            &user_comptoken_wallet_account,
-            &comptoken_mint_account,
-            &global_data_account,
-            &[
-                &validation_account,
-                &transfer_hook_program,
-                &compto_program,
-                &user_data_account,
-                &ubi_data_pda,
-            ],
-            0, // TODO figure out correct amount
-        )?;
+pub fn set_proof_scheme(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    //  accounts order:
+    //      Admin (signer, writable)
+    //      Comptoken Global Data (writable)
+    //  instruction data:
+    //      u8: the scheme; 0 is sha256, 1 keccak256, and 2 blake3
+
+    let account_info_iter = &mut accounts.iter();
+    let admin_account = next_account_info(account_info_iter)?;
+    let global_data_account = next_account_info(account_info_iter)?;
+
+    let global_data_account = verify_global_data_account(global_data_account, program_id, true);
+    let global_data: &mut GlobalData = (&global_data_account).into();
+    let _admin_account = verify_admin_account(admin_account, &global_data.config.admin);
+
+    let scheme = ProofSchemeKind::try_from(instruction_data[0]).expect("valid proof scheme");
+    verbose_msg!("next proof scheme: {:?}", scheme);
+    // proofs against the current valid blockhash keep their scheme, so miners are never invalidated mid day
+    global_data.valid_blockhashes.next_proof_scheme = scheme;
+
+    Ok(())
+}
+
+pub fn audit(program_id: &Pubkey, accounts: &[AccountInfo], _instruction_data: &[u8]) -> ProgramResult {
+    //  accounts order:
+    //      [] Comptoken Mint
+    //      [] Comptoken Global Data
+    //      [] Comptoken Interest Bank
+    //      [] Comptoken UBI Bank
+
+    let account_info_iter = &mut accounts.iter();
+    let comptoken_mint_account = next_account_info(account_info_iter)?;
+    let global_data_account = next_account_info(account_info_iter)?;
+    let unpaid_interest_bank = next_account_info(account_info_iter)?;
+    let unpaid_ubi_bank = next_account_info(account_info_iter)?;
+
+    let global_data_account = verify_global_data_account(global_data_account, program_id, false);
+    let config = read_config(&global_data_account);
+    let comptoken_mint_account = verify_comptoken_mint(comptoken_mint_account, &config, false);
+    let unpaid_interest_bank = verify_interest_bank_account(unpaid_interest_bank, &config, false);
+    let unpaid_ubi_bank = verify_ubi_bank_account(unpaid_ubi_bank, &config, false);
+
+    let supply = {
+        let mint_data = comptoken_mint_account.try_borrow_data().unwrap();
+        StateWithExtensions::<Mint>::unpack(mint_data.as_ref()).unwrap().base.supply
+    };
+    let global_data: &mut GlobalData = (&global_data_account).into();
+
+    let report = AuditReport::new(
+        global_data,
+        supply,
+        wallet_balance(&unpaid_interest_bank),
+        wallet_balance(&unpaid_ubi_bank),
+        &SysvarClock,
+    );
+    verbose_msg!("{:?}", report);
+    // the layout is documented on AuditReport::SIZE
+    set_return_data(&report.to_bytes());
+    Ok(())
+}
+
+pub fn query_global_data(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    //  accounts order:
+    //      [] Comptoken Global Data
+    //  instruction data:
+    //      u8: the selector, see query::QuerySelector
+    //      u16: the page index
+    //  return data:
+    //      the page, see query.rs
+
+    let account_info_iter = &mut accounts.iter();
+    let global_data_account = next_account_info(account_info_iter)?;
+
+    let global_data_account = verify_global_data_account(global_data_account, program_id, false);
+    let global_data: &mut GlobalData = (&global_data_account).into();
+
+    // anyone can query, so malformed queries are rejected rather than panicking
+    let [selector, page @ ..] = instruction_data else {
+        return Err(ProgramError::InvalidInstructionData);
+    };
+    let selector = QuerySelector::try_from(*selector).map_err(|_| ProgramError::InvalidInstructionData)?;
+    let page = u16::from_le_bytes(page.try_into().map_err(|_| ProgramError::InvalidInstructionData)?);
+    verbose_msg!("selector: {:?}, page: {}", selector, page);
+    let page = query::page(global_data, selector, page).ok_or(ProgramError::InvalidInstructionData)?;
+    set_return_data(&page);
+    Ok(())
+}
+
+pub fn settle_transfer(program_id: &Pubkey, accounts: &[AccountInfo], _instruction_data: &[u8]) -> ProgramResult {
+    //  accounts order:
+    //      [] Source Comptoken Wallet
+    //      [] Destination Comptoken Wallet
+    //      [w] Source User Data (or the bank's data PDA, which doesn't actually exist)
+    //      [w] Destination User Data (or the bank's data PDA, which doesn't actually exist)
+    //      [] Comptoken Global Data
+    //  called by the transfer hook once token 2022 has moved the comptokens, so the comptokens a wallet receives
+    //  earn interest from the day they arrive
+
+    let account_info_iter = &mut accounts.iter();
+    let source_comptoken_wallet_account = next_account_info(account_info_iter)?;
+    let destination_comptoken_wallet_account = next_account_info(account_info_iter)?;
+    let source_user_data_account = next_account_info(account_info_iter)?;
+    let destination_user_data_account = next_account_info(account_info_iter)?;
+    let global_data_account = next_account_info(account_info_iter)?;
+
+    let global_data_account = verify_global_data_account(global_data_account, program_id, false);
+    let config = read_config(&global_data_account);
+    let global_data: &mut GlobalData = (&global_data_account).into();
+
+    for (comptoken_wallet_account, user_data_account, is_source) in [
+        (source_comptoken_wallet_account, source_user_data_account, true),
+        (destination_comptoken_wallet_account, destination_user_data_account, false),
+    ] {
+        let comptoken_wallet_account =
+            verify_user_comptoken_wallet_account(comptoken_wallet_account, &config, false, false);
+        // the banks don't earn interest
+        if config.is_bank(comptoken_wallet_account.key) {
+            continue;
+        }
+        let user_data_account =
+            verify_user_data_account(user_data_account, &comptoken_wallet_account, program_id, true);
+        let user_data: &mut UserData = (&user_data_account).into();
+        // a sender that hasn't claimed everything it is owed would lose the interest on what it sent. the receiver
+        // loses nothing, since only what it held before the transfer earns interest for the days it hasn't settled
+        let last_distribution_day = global_data.daily_distribution_data.last_daily_distribution_time;
+        if is_source {
+            assert!(user_data.is_current(last_distribution_day), "wallet must claim what it is owed first");
+        }
+        settle_interest(user_data, &comptoken_wallet_account, global_data);
     }
 
     Ok(())
@@ -488,26 +927,43 @@ pub fn realloc_user_data() {
     // TODO implement
 }
 
-fn mint(
-    mint_authority: &VerifiedAccountInfo, destination_wallet: &VerifiedAccountInfo, amount: u64,
-    accounts: &[&VerifiedAccountInfo],
+/// the instance's config, copied so its seeds can be used while the global data is borrowed
+fn read_config(global_data_account: &VerifiedAccountInfo) -> Config {
+    let config: &Config = global_data_account.into();
+    *config
+}
+
+fn mint<'a>(
+    comptoken_mint: &VerifiedAccountInfo<'a>, global_data: &VerifiedAccountInfo<'a>,
+    destination_wallet: &VerifiedAccountInfo<'a>, amount: u64, config: &Config,
 ) -> ProgramResult {
+    // the global data is the mint authority
     let instruction = mint_to(
         &spl_token_2022::id(),
-        &COMPTOKEN_MINT_ADDRESS,
+        comptoken_mint.key,
         destination_wallet.key,
-        mint_authority.key,
-        &[mint_authority.key],
+        global_data.key,
+        &[global_data.key],
         amount,
     )?;
-    invoke_signed_verified(&instruction, accounts, &[COMPTO_GLOBAL_DATA_ACCOUNT_SEEDS])
+    invoke_signed_verified(
+        &instruction,
+        &[comptoken_mint, destination_wallet, global_data],
+        &[&config.global_data_seeds()],
+    )
 }
 
 fn transfer<'a>(
     source: &VerifiedAccountInfo<'a>, destination: &VerifiedAccountInfo<'a>, mint: &VerifiedAccountInfo<'a>,
     global_data: &VerifiedAccountInfo<'a>, additional_accounts: &[&VerifiedAccountInfo<'a>], amount: u64,
+    config: &Config,
 ) -> ProgramResult {
-    let additional_accounts: Vec<_> = additional_accounts.iter().map(|account| account.0.clone()).collect();
+    // the transfer hook reads the config from the global data, so it is one of the hook's accounts as well
+    let additional_accounts: Vec<_> = additional_accounts
+        .iter()
+        .chain([&global_data])
+        .map(|account| account.0.clone())
+        .collect();
     onchain::invoke_transfer_checked(
         &spl_token_2022::ID,
         source.0.clone(),
@@ -517,7 +973,7 @@ fn transfer<'a>(
         &additional_accounts,
         amount,
         MINT_DECIMALS,
-        &[COMPTO_GLOBAL_DATA_ACCOUNT_SEEDS],
+        &[&config.global_data_seeds()],
     )
 }
 
@@ -525,16 +981,216 @@ fn init_comptoken_account<'a>(
     account: &VerifiedAccountInfo<'a>, owner: &VerifiedAccountInfo, signer_seeds: &[&[&[u8]]],
     mint: &VerifiedAccountInfo<'a>,
 ) -> ProgramResult {
-    let init_comptoken_account_instr = spl_token_2022::instruction::initialize_account3(
+    let init_comptoken_account_instr =
+        spl_token_2022::instruction::initialize_account3(&spl_token_2022::ID, account.key, mint.key, owner.key)?;
+    invoke_signed_verified(&init_comptoken_account_instr, &[account, mint], signer_seeds)
+}
+
+fn create_comptoken_mint<'a>(
+    payer: &VerifiedAccountInfo<'a>, mint: &VerifiedAccountInfo<'a>, global_data: &VerifiedAccountInfo<'a>,
+    metadata: TokenMetadata, config: &Config,
+) -> ProgramResult {
+    // the metadata is variable length, so token 2022 allocates space for it when it is initialized,
+    // but the mint must already hold the rent for that space
+    let space = ExtensionType::try_calculate_account_len::<Mint>(&[
+        ExtensionType::TransferHook,
+        ExtensionType::MetadataPointer,
+    ])?;
+    let lamports = Rent::get()?.minimum_balance(space + metadata.tlv_size_of()?);
+    // the mint is a keypair, so it signs for its own creation
+    create_pda(payer, mint, lamports, space as u64, &spl_token_2022::ID, &[])?;
+
+    let instruction = transfer_hook::instruction::initialize(
         &spl_token_2022::ID,
-        account.key,
-        &COMPTOKEN_MINT_ADDRESS,
-        owner.key,
+        mint.key,
+        Some(*global_data.key),
+        Some(config.transfer_hook_program),
     )?;
-    invoke_signed_verified(&init_comptoken_account_instr, &[account, mint], signer_seeds)
+    invoke_signed_verified(&instruction, &[mint], &[])?;
+    // the metadata lives in the mint itself
+    let instruction = metadata_pointer::instruction::initialize(
+        &spl_token_2022::ID,
+        mint.key,
+        Some(*global_data.key),
+        Some(*mint.key),
+    )?;
+    invoke_signed_verified(&instruction, &[mint], &[])?;
+    let instruction = initialize_mint2(&spl_token_2022::ID, mint.key, global_data.key, None, MINT_DECIMALS)?;
+    invoke_signed_verified(&instruction, &[mint], &[])?;
+    let instruction = initialize_metadata(
+        &spl_token_2022::ID,
+        mint.key,
+        global_data.key,
+        mint.key,
+        global_data.key,
+        metadata.name,
+        metadata.symbol,
+        metadata.uri,
+    );
+    invoke_signed_verified(&instruction, &[mint, global_data], &[&config.global_data_seeds()])
+}
+
+// strings are encoded as a u32 length followed by that many utf-8 bytes
+fn split_string(data: &[u8]) -> (String, &[u8]) {
+    let (length, rest) = data.split_at(4);
+    let length = u32::from_le_bytes(length.try_into().unwrap()) as usize;
+    let (string, rest) = rest.split_at(length);
+    (String::from_utf8(string.to_vec()).expect("valid utf-8"), rest)
 }
 
-fn store_hash(proof: ComptokenProof, data_account: &VerifiedAccountInfo) {
+/// the exact size of a comptoken account, including the extensions the mint requires its accounts to have
+fn comptoken_account_space(mint: &VerifiedAccountInfo) -> Result<usize, ProgramError> {
+    let mint_data = mint.try_borrow_data()?;
+    let mint_extensions = StateWithExtensions::<Mint>::unpack(&mint_data)?.get_extension_types()?;
+    let account_extensions = ExtensionType::get_required_init_account_extensions(&mint_extensions);
+    ExtensionType::try_calculate_account_len::<Account>(&account_extensions)
+}
+
+/// accrues the interest owed on the balance the wallet held since its last settlement
+fn settle_interest(user_data: &mut UserData, user_comptoken_wallet: &VerifiedAccountInfo, global_data: &GlobalData) {
+    let balance = wallet_balance(user_comptoken_wallet);
+    verbose_msg!("total before interest: {}", balance);
+    // only days that have been distributed have an interest, so days are counted up to the last distribution
+    let daily_distribution_data = &global_data.daily_distribution_data;
+    user_data.settle(
+        balance,
+        daily_distribution_data.last_daily_distribution_time,
+        &global_data.config.days,
+        |days, amount| daily_distribution_data.apply_n_interests(days, amount),
+    );
+}
+
+/// The accounts needed to pay owed interest and UBI out of the banks
+struct SettlementAccounts<'a> {
+    unpaid_interest_bank: VerifiedAccountInfo<'a>,
+    unpaid_ubi_bank: VerifiedAccountInfo<'a>,
+    validation_account: VerifiedAccountInfo<'a>,
+    transfer_hook_program: VerifiedAccountInfo<'a>,
+    compto_program: VerifiedAccountInfo<'a>,
+    interest_data_pda: VerifiedAccountInfo<'a>,
+    ubi_data_pda: VerifiedAccountInfo<'a>,
+    user_identity_account: VerifiedAccountInfo<'a>,
+}
+
+impl<'a> SettlementAccounts<'a> {
+    /// `accounts` are the interest bank, UBI bank, extra account metas, transfer hook program, comptoken program,
+    /// interest bank data PDA, UBI bank data PDA, and the identity `user_data_account` links to
+    fn verify(
+        accounts: [&AccountInfo<'a>; 8], user_data_account: &VerifiedAccountInfo, config: &Config, program_id: &Pubkey,
+    ) -> Self {
+        let [unpaid_interest_bank, unpaid_ubi_bank, extra_account_metas_account, transfer_hook_program, compto_program, interest_data_pda, ubi_data_pda, user_identity_account] =
+            accounts;
+        let unpaid_interest_bank = verify_interest_bank_account(unpaid_interest_bank, config, true);
+        let unpaid_ubi_bank = verify_ubi_bank_account(unpaid_ubi_bank, config, true);
+        let transfer_hook_program = verify_transfer_hook_program(transfer_hook_program, config);
+        let validation_account =
+            verify_validation_account(extra_account_metas_account, &transfer_hook_program, config, false);
+        let compto_program = VerifiedAccountInfo::verify_specific_address(compto_program, program_id, false, false);
+        let interest_data_pda = verify_bank_data_pda(interest_data_pda, &config.interest_bank_data_seeds(), program_id);
+        let ubi_data_pda = verify_bank_data_pda(ubi_data_pda, &config.ubi_bank_data_seeds(), program_id);
+        let user_identity_account = {
+            let user_data: &UserData = user_data_account.into();
+            VerifiedAccountInfo::verify_specific_address(user_identity_account, &user_data.user_identity, false, true)
+        };
+        SettlementAccounts {
+            unpaid_interest_bank,
+            unpaid_ubi_bank,
+            validation_account,
+            transfer_hook_program,
+            compto_program,
+            interest_data_pda,
+            ubi_data_pda,
+            user_identity_account,
+        }
+    }
+}
+
+/// settles the wallet's interest and pays it, and any UBI owed to its identity, out of the banks
+fn pay_owed_comptokens<'a>(
+    user_data_account: &VerifiedAccountInfo<'a>, user_comptoken_wallet_account: &VerifiedAccountInfo<'a>,
+    comptoken_mint_account: &VerifiedAccountInfo<'a>, global_data_account: &VerifiedAccountInfo<'a>,
+    settlement_accounts: &SettlementAccounts<'a>,
+) -> ProgramResult {
+    let interest;
+    let is_ubi_owed;
+    let ubi = UBI_AMOUNT;
+    let config = read_config(global_data_account);
+    {
+        let global_data: &mut GlobalData = global_data_account.into();
+        let user_data: &mut UserData = user_data_account.into();
+
+        settle_interest(user_data, user_comptoken_wallet_account, global_data);
+        interest = user_data.take_accrued_interest();
+        verbose_msg!("Interest: {}", interest);
+
+        // UBI belongs to the identity, so it is only paid once a day however many wallets the owner has
+        let last_distribution_day = global_data.daily_distribution_data.last_daily_distribution_time;
+        let user_identity: &mut UserIdentity = (&settlement_accounts.user_identity_account).into();
+        is_ubi_owed = user_identity.claim_ubi(last_distribution_day);
+        global_data.record_claim(interest, if is_ubi_owed { ubi } else { 0 });
+    }
+
+    transfer(
+        &settlement_accounts.unpaid_interest_bank,
+        user_comptoken_wallet_account,
+        comptoken_mint_account,
+        global_data_account,
+        &[
+            &settlement_accounts.validation_account,
+            &settlement_accounts.transfer_hook_program,
+            &settlement_accounts.compto_program,
+            user_data_account,
+            &settlement_accounts.interest_data_pda,
+        ],
+        interest,
+        &config,
+    )?;
+
+    // get ubi if verified
+    if is_ubi_owed {
+        transfer(
+            &settlement_accounts.unpaid_ubi_bank,
+            user_comptoken_wallet_account,
+            comptoken_mint_account,
+            global_data_account,
+            &[
+                &settlement_accounts.validation_account,
+                &settlement_accounts.transfer_hook_program,
+                &settlement_accounts.compto_program,
+                user_data_account,
+                &settlement_accounts.ubi_data_pda,
+            ],
+            ubi,
+            &config,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn wallet_balance(comptoken_wallet: &VerifiedAccountInfo) -> u64 {
+    let wallet_data = comptoken_wallet.try_borrow_data().unwrap();
+    StateWithExtensions::<Account>::unpack(wallet_data.as_ref()).unwrap().base.amount
+}
+
+/// returns whether this is the wallet's first proof against the proof's blockhash
+fn store_hash(
+    proof: ComptokenProof, data_account: &VerifiedAccountInfo, global_data: &GlobalData, clock: &impl TimeSource,
+) -> bool {
     let user_data: &mut UserData = data_account.into();
-    user_data.insert(&proof.hash, &proof.recent_block_hash)
+    let valid_blockhashes = &global_data.valid_blockhashes;
+    let previous_valid_blockhash = Some(&valid_blockhashes.previous_valid_blockhash)
+        .filter(|blockhash| global_data.is_blockhash_valid(blockhash, clock));
+    user_data.insert(
+        &proof.hash,
+        &proof.recent_block_hash,
+        &valid_blockhashes.valid_blockhash,
+        previous_valid_blockhash,
+    );
+    let proofs_against_blockhash = if *user_data.recent_blockhash() == proof.recent_block_hash {
+        user_data.proof_count()
+    } else {
+        user_data.previous_proof_count()
+    };
+    proofs_against_blockhash == 1
 }
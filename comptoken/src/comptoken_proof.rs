@@ -1,25 +1,41 @@
 use std::mem;
 
 use spl_token_2022::solana_program::{
-    hash::{Hash, Hasher, HASH_BYTES},
+    hash::{Hash, HASH_BYTES},
     pubkey::Pubkey,
 };
 
-use comptoken_utils::verify_accounts::VerifiedAccountInfo;
+use comptoken_utils::{verify_accounts::VerifiedAccountInfo, TimeSource};
 
-use crate::global_data::valid_blockhashes::ValidBlockhashes;
+use crate::{global_data::GlobalData, proof_scheme::ProofSchemeKind};
 
 // ensure this remains consistent with comptoken_proof.js
 const MIN_NUM_ZEROED_BITS: u32 = 3; // TODO: replace with permanent value
 
+// v2 proofs commit to this, the program, and the mint, so they aren't valid against any other instance
+const PROOF_DOMAIN: &[u8] = b"Comptoken Proof";
+
+pub const EXTRA_NONCE_BYTES: usize = 32;
 pub const VERIFY_DATA_SIZE: usize = HASH_BYTES + mem::size_of::<u64>() + HASH_BYTES;
+pub const VERIFY_DATA_V2_SIZE: usize = 1 + HASH_BYTES + mem::size_of::<u64>() + EXTRA_NONCE_BYTES + HASH_BYTES;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofVersion {
+    // the unversioned layout, only accepted until the configured deadline
+    V1 = 1,
+    V2 = 2,
+}
 
 // Ensure changes to this struct remain consistent with comptoken_proof.js
 #[derive(Debug)]
 pub struct ComptokenProof<'a> {
     pub pubkey: &'a Pubkey,
+    pub version: ProofVersion,
     pub recent_block_hash: Hash,
     pub nonce: u64,
+    // lets parallel miners partition the search space, always zero in v1
+    pub extra_nonce: [u8; EXTRA_NONCE_BYTES],
     pub hash: Hash,
 }
 
@@ -35,7 +51,46 @@ impl<'a> ComptokenProof<'a> {
         let nonce = u64::from_le_bytes(bytes[range_2].try_into().unwrap());
         let hash = Hash::new_from_array(bytes[range_3].try_into().unwrap());
 
-        ComptokenProof { pubkey: key, recent_block_hash, nonce, hash }
+        ComptokenProof {
+            pubkey: key,
+            version: ProofVersion::V1,
+            recent_block_hash,
+            nonce,
+            extra_nonce: [0; EXTRA_NONCE_BYTES],
+            hash,
+        }
+    }
+
+    pub fn from_v2_bytes(key: &'a Pubkey, bytes: &[u8; VERIFY_DATA_V2_SIZE]) -> Self {
+        // ensure this remains consistent with comptoken_proof.js
+        assert_eq!(bytes[0], ProofVersion::V2 as u8, "unknown proof version");
+        let range_1 = 1..1 + HASH_BYTES;
+        let range_2 = range_1.end..range_1.end + mem::size_of::<u64>();
+        let range_3 = range_2.end..range_2.end + EXTRA_NONCE_BYTES;
+        let range_4 = range_3.end..range_3.end + HASH_BYTES;
+
+        let recent_block_hash = Hash::new_from_array(bytes[range_1].try_into().unwrap());
+        let nonce = u64::from_le_bytes(bytes[range_2].try_into().unwrap());
+        let extra_nonce = bytes[range_3].try_into().unwrap();
+        let hash = Hash::new_from_array(bytes[range_4].try_into().unwrap());
+
+        ComptokenProof {
+            pubkey: key,
+            version: ProofVersion::V2,
+            recent_block_hash,
+            nonce,
+            extra_nonce,
+            hash,
+        }
+    }
+
+    /// parses either proof layout, which are told apart by their size
+    pub fn from_data(key: &'a Pubkey, data: &[u8]) -> Self {
+        match data.len() {
+            VERIFY_DATA_SIZE => ComptokenProof::from_bytes(key, data.try_into().unwrap()),
+            VERIFY_DATA_V2_SIZE => ComptokenProof::from_v2_bytes(key, data.try_into().unwrap()),
+            _ => panic!("invalid proof size"),
+        }
     }
 
     pub fn leading_zeroes(hash: &Hash) -> u32 {
@@ -55,30 +110,46 @@ impl<'a> ComptokenProof<'a> {
         leading_zeroes
     }
 
-    pub fn generate_hash(&self) -> Hash {
+    pub fn generate_hash(&self, scheme: ProofSchemeKind, program_id: &Pubkey, mint: &Pubkey) -> Hash {
         // ensure this remains consistent with comptoken_proof.js
-        let mut hasher = Hasher::default();
-        hasher.hash(&self.pubkey.to_bytes());
-        hasher.hash(&self.recent_block_hash.to_bytes());
-        hasher.hash(&self.nonce.to_le_bytes());
-        hasher.result()
+        match self.version {
+            ProofVersion::V1 => {
+                scheme.hashv(&[&self.pubkey.to_bytes(), &self.recent_block_hash.to_bytes(), &self.nonce.to_le_bytes()])
+            }
+            ProofVersion::V2 => scheme.hashv(&[
+                PROOF_DOMAIN,
+                &[self.version as u8],
+                &program_id.to_bytes(),
+                &mint.to_bytes(),
+                &self.pubkey.to_bytes(),
+                &self.recent_block_hash.to_bytes(),
+                &self.nonce.to_le_bytes(),
+                &self.extra_nonce,
+            ]),
+        }
     }
 
     pub fn verify_submitted_proof(
-        comptoken_wallet: &'a VerifiedAccountInfo, data: &[u8], valid_blockhashes: &ValidBlockhashes,
+        comptoken_wallet: &'a VerifiedAccountInfo, data: &[u8], global_data: &GlobalData, program_id: &Pubkey,
+        clock: &impl TimeSource,
     ) -> Self {
-        let proof = ComptokenProof::from_bytes(comptoken_wallet.key, data.try_into().expect("valid proof size"));
-        assert!(proof.verify_proof(valid_blockhashes), "invalid proof");
+        let proof = ComptokenProof::from_data(comptoken_wallet.key, data);
+        assert!(proof.verify_proof(global_data, program_id, clock), "invalid proof");
         proof
     }
 
-    fn verify_proof(&self, valid_blockhashes: &ValidBlockhashes) -> bool {
+    fn verify_proof(&self, global_data: &GlobalData, program_id: &Pubkey, clock: &impl TimeSource) -> bool {
+        if self.version == ProofVersion::V1 && !global_data.config.accepts_v1_proofs(clock) {
+            return false;
+        }
         let leading_zeros: bool = ComptokenProof::leading_zeroes(&self.hash) >= MIN_NUM_ZEROED_BITS;
-        let recent_blockhash: bool = self.recent_block_hash == valid_blockhashes.valid_blockhash;
-        let equal_hash: bool = self.generate_hash() == self.hash;
-        let valid_hash_is_fresh: bool = !valid_blockhashes.is_valid_blockhash_stale();
+        // also accepts the previous valid blockhash during its grace period, with the scheme it was valid with
+        let Some(scheme) = global_data.proof_scheme_for(&self.recent_block_hash, clock) else {
+            return false;
+        };
+        let equal_hash: bool = self.generate_hash(scheme, program_id, &global_data.config.mint) == self.hash;
         // hash duplicate check is part of inserting
-        leading_zeros && recent_blockhash && equal_hash && valid_hash_is_fresh
+        leading_zeros && equal_hash
     }
 }
 
@@ -86,12 +157,19 @@ impl<'a> ComptokenProof<'a> {
 mod test {
 
     use super::*;
-    use spl_token_2022::solana_program::pubkey::PUBKEY_BYTES;
+    use spl_token_2022::solana_program::{hash::Hasher, pubkey::PUBKEY_BYTES};
 
     const ZERO_PUBKEY: Pubkey = Pubkey::new_from_array([0; PUBKEY_BYTES]);
 
-    fn create_arbitrary_block(pubkey: &Pubkey, recent_block_hash: Hash, nonce: u64, hash: Hash) -> ComptokenProof {
-        ComptokenProof { pubkey, recent_block_hash, nonce, hash }
+    fn create_arbitrary_block(pubkey: &Pubkey, recent_block_hash: Hash, nonce: u64, hash: Hash) -> ComptokenProof<'_> {
+        ComptokenProof {
+            pubkey,
+            version: ProofVersion::V1,
+            recent_block_hash,
+            nonce,
+            extra_nonce: [0; EXTRA_NONCE_BYTES],
+            hash,
+        }
     }
 
     #[test]
@@ -137,4 +215,35 @@ mod test {
         assert_eq!(block_from_bytes.nonce, block_from_data.nonce, "nonces are different");
         assert_eq!(block_from_bytes.hash, block_from_data.hash, "hashes are different");
     }
+
+    #[test]
+    fn test_v2_domain_separation() {
+        let pubkey = Pubkey::new_from_array([2; PUBKEY_BYTES]);
+        let mut bytes = vec![ProofVersion::V2 as u8];
+        bytes.extend([1; HASH_BYTES]);
+        bytes.extend(3_u64.to_le_bytes());
+        bytes.extend([4; EXTRA_NONCE_BYTES]);
+        bytes.extend([5; HASH_BYTES]);
+
+        let proof = ComptokenProof::from_data(&pubkey, &bytes);
+        assert_eq!(proof.version, ProofVersion::V2);
+        assert_eq!(proof.nonce, 3);
+        assert_eq!(proof.extra_nonce, [4; EXTRA_NONCE_BYTES]);
+        assert_eq!(proof.hash, Hash::new_from_array([5; HASH_BYTES]));
+
+        let program_id = Pubkey::new_from_array([6; PUBKEY_BYTES]);
+        let other_program_id = Pubkey::new_from_array([7; PUBKEY_BYTES]);
+        let mint = Pubkey::new_from_array([8; PUBKEY_BYTES]);
+        let other_mint = Pubkey::new_from_array([9; PUBKEY_BYTES]);
+        assert_ne!(
+            proof.generate_hash(ProofSchemeKind::Sha256, &program_id, &mint),
+            proof.generate_hash(ProofSchemeKind::Sha256, &other_program_id, &mint),
+            "v2 proofs are only valid for one program"
+        );
+        assert_ne!(
+            proof.generate_hash(ProofSchemeKind::Sha256, &program_id, &mint),
+            proof.generate_hash(ProofSchemeKind::Sha256, &program_id, &other_mint),
+            "v2 proofs are only valid for one instance"
+        );
+    }
 }
@@ -12,8 +12,21 @@ pub const ADJUST_FACTOR: f64 = 0.3;
 // the USD supply increase per year (~7%), and quadrupling it to allow for periods of larger growth, then rounding to a nicer number.
 pub const END_GOAL_PERCENT_INCREASE: f64 = 0.00061;
 
+// TODO figure out correct amount
+// the comptokens paid to a verified human's identity for each daily distribution
+pub const UBI_AMOUNT: u64 = 0;
+
 // seconds between earliest possible announcement and switchover point, currently 5 mins
 pub const ANNOUNCEMENT_INTERVAL: i64 = 60 * 5;
 
+// the number of slot hashes, starting after the announcement, mixed into each announced blockhash. no single slot leader
+// or caller controls the result unless they produced every one of these slots
+pub const CHALLENGE_SLOT_HASH_COUNT: usize = 8;
+
+// the most missed days a single daily distribution will catch up on, so long gaps don't exceed the compute budget.
+// the remaining days are caught up on by the following calls
+// MAGIC NUMBER: ensure this remains consistent with dailyDistributionCatchUp.js
+pub const MAX_DISTRIBUTION_DAYS_PER_CALL: i64 = 30;
+
 // MAGIC NUMBER: ensure this remains consistent with full_deploy_test.py
 pub const MINT_DECIMALS: u8 = 0;
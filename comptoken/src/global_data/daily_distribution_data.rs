@@ -1,6 +1,8 @@
-use spl_token_2022::{solana_program::msg, state::Mint};
+use spl_token_2022::state::Mint;
 
-use crate::{constants::*, get_current_time, normalize_time};
+use comptoken_utils::{verbose_msg, Days, TimeSource};
+
+use crate::constants::*;
 
 const HISTORY_SIZE: usize = 365;
 
@@ -10,6 +12,8 @@ pub struct DailyDistributionData {
     pub yesterday_supply: u64,
     pub high_water_mark: u64,
     pub last_daily_distribution_time: i64,
+    // comptokens minted for proofs since the last distribution; other mints and burns don't affect it
+    pub mined_since_last_distribution: u64,
     pub oldest_interest: usize,
     pub historic_interests: [f64; HISTORY_SIZE],
 }
@@ -17,28 +21,69 @@ pub struct DailyDistributionData {
 impl DailyDistributionData {
     const HISTORY_SIZE: usize = HISTORY_SIZE;
 
-    pub(super) fn initialize(&mut self) {
-        self.last_daily_distribution_time = normalize_time(get_current_time());
+    pub(super) fn initialize(&mut self, days: &Days, clock: &impl TimeSource) {
+        self.last_daily_distribution_time = clock.today(days);
+    }
+
+    pub fn next_distribution_time(&self, days: &Days) -> i64 {
+        self.last_daily_distribution_time + days.length
+    }
+
+    pub fn is_distribution_due(&self, days: &Days, current_time: i64) -> bool {
+        current_time > self.next_distribution_time(days)
     }
 
-    pub(super) fn daily_distribution(&mut self, mint: Mint) -> DailyDistributionValues {
+    /// whether a whole distribution day has been missed, rather than the distribution just not having been called yet
+    pub fn is_distribution_overdue(&self, days: &Days, current_time: i64) -> bool {
+        days.start_of(current_time) - self.last_daily_distribution_time > days.length
+    }
+
+    pub(super) fn daily_distribution(
+        &mut self, mint: Mint, distribution_bounty: u64, days: &Days, clock: &impl TimeSource,
+    ) -> DailyDistributionValues {
+        // if days were missed, everything mined since the last distribution is attributed to the first missed day,
+        // and the rest are distributed as days without any mining so the interest history stays aligned with days
+        let days_missed = days.between(self.last_daily_distribution_time, clock.today(days));
+        let days_to_distribute = std::cmp::min(days_missed, MAX_DISTRIBUTION_DAYS_PER_CALL);
+        verbose_msg!("Distributing {} of {} missed days", days_to_distribute, days_missed);
+
+        let distribution_values =
+            self.distribute_day(mint.supply, self.mined_since_last_distribution, distribution_bounty, days);
+        self.mined_since_last_distribution = 0;
+        verbose_msg!("Interest: {}", self.newest_interest());
+        for _ in 1..days_to_distribute {
+            self.distribute_day(self.yesterday_supply, 0, 0, days);
+        }
+        distribution_values
+    }
+
+    fn distribute_day(
+        &mut self, supply: u64, daily_mining_total: u64, distribution_bounty: u64, days: &Days,
+    ) -> DailyDistributionValues {
         // calculate interest/high water mark
-        self.last_daily_distribution_time = normalize_time(get_current_time());
+        self.last_daily_distribution_time += days.length;
 
-        let daily_mining_total = mint.supply - self.yesterday_supply;
         let high_water_mark_increase = self.calculate_high_water_mark_increase(daily_mining_total);
         self.high_water_mark += high_water_mark_increase;
 
         let total_daily_distribution = high_water_mark_increase * COMPTOKEN_DISTRIBUTION_MULTIPLIER;
+        // the bounty can't be more than is being distributed
+        let bounty_distributed = std::cmp::min(distribution_bounty, total_daily_distribution);
         let distribution_values = DailyDistributionValues {
-            interest_distributed: total_daily_distribution / 2,
-            ubi_distributed: total_daily_distribution / 2,
+            interest_distributed: (total_daily_distribution - bounty_distributed) / 2,
+            ubi_distributed: (total_daily_distribution - bounty_distributed) / 2,
+            bounty_distributed,
+        };
+        self.yesterday_supply = supply
+            + distribution_values.interest_distributed
+            + distribution_values.ubi_distributed
+            + distribution_values.bounty_distributed;
+
+        // nothing can be distributed without a supply, so there is no interest
+        let interest = match self.yesterday_supply {
+            0 => 0.,
+            yesterday_supply => distribution_values.interest_distributed as f64 / yesterday_supply as f64,
         };
-        self.yesterday_supply =
-            mint.supply + distribution_values.interest_distributed + distribution_values.ubi_distributed;
-
-        let interest = distribution_values.interest_distributed as f64 / self.yesterday_supply as f64; // TODO: interest is NaN if yesterday supply is 0
-        msg!("Interest: {}", interest);
         self.insert(interest);
 
         distribution_values
@@ -70,12 +115,14 @@ impl DailyDistributionData {
     }
 
     pub fn apply_n_interests(&self, n: usize, initial_money: u64) -> u64 {
-        self.into_iter()
-            .take(n)
-            .fold(initial_money as f64, |money, interest| (money * (1. + interest)).round_ties_even()) as u64
+        apply_interests(self.into_iter().take(n), initial_money)
     }
 
-    fn insert(&mut self, interest: f64) {
+    pub fn newest_interest(&self) -> f64 {
+        self.historic_interests[(self.oldest_interest + Self::HISTORY_SIZE - 1) % Self::HISTORY_SIZE]
+    }
+
+    pub(crate) fn insert(&mut self, interest: f64) {
         self.historic_interests[self.oldest_interest] = interest;
         self.oldest_interest = (self.oldest_interest + 1) % Self::HISTORY_SIZE;
     }
@@ -111,14 +158,22 @@ impl IntoIterator for &DailyDistributionData {
     }
 }
 
+// interests are applied in the order given, which should be most recent first
+#[allow(unstable_name_collisions)]
+pub fn apply_interests(interests: impl Iterator<Item = f64>, initial_money: u64) -> u64 {
+    interests.fold(initial_money as f64, |money, interest| (money * (1. + interest)).round_ties_even()) as u64
+}
+
 pub struct DailyDistributionValues {
     pub interest_distributed: u64,
     pub ubi_distributed: u64,
+    pub bounty_distributed: u64,
 }
 
 // rust implements round_ties_even in version 1.77, which is more recent than
 // the version (1.75) solana uses. this is a reimplementation, however rust's
 // uses compiler intrinsics, so we can't just use their code
+#[allow(dead_code)] // unused on host toolchains where f64::round_ties_even exists
 pub trait RoundEven {
     fn round_ties_even(self) -> Self;
 }
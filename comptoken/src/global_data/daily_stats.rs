@@ -0,0 +1,89 @@
+use spl_token_2022::solana_program::hash::Hash;
+
+// the whole history has to fit in the 10KiB a program can allocate when it creates the global data
+const STATS_HISTORY_SIZE: usize = 64;
+
+/// What happened on one distribution day
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DailyStats {
+    // the start of the day
+    pub day: i64,
+    pub proofs_accepted: u64,
+    // wallets that submitted their first proof against a blockhash
+    pub unique_miners: u64,
+    pub tokens_mined: u64,
+    // after the day's distribution
+    pub high_water_mark: u64,
+    pub supply: u64,
+    pub interest_distributed: u64,
+    pub ubi_distributed: u64,
+    // the blockhash proofs were mined against, or the default hash if the day was missed entirely
+    pub valid_blockhash: Hash,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct DailyStatsHistory {
+    // counted until the next daily distribution records them
+    pub proofs_since_last_distribution: u64,
+    pub miners_since_last_distribution: u64,
+    pub oldest_stats: usize,
+    pub historic_stats: [DailyStats; STATS_HISTORY_SIZE],
+}
+
+impl DailyStatsHistory {
+    const HISTORY_SIZE: usize = STATS_HISTORY_SIZE;
+
+    pub(super) fn record_proof(&mut self, is_new_miner: bool) {
+        self.proofs_since_last_distribution += 1;
+        self.miners_since_last_distribution += is_new_miner as u64;
+    }
+
+    /// records the day that just ended, along with the proofs counted since the previous one
+    pub(super) fn end_day(&mut self, stats: DailyStats) {
+        self.historic_stats[self.oldest_stats] = DailyStats {
+            proofs_accepted: std::mem::take(&mut self.proofs_since_last_distribution),
+            unique_miners: std::mem::take(&mut self.miners_since_last_distribution),
+            ..stats
+        };
+        self.oldest_stats = (self.oldest_stats + 1) % Self::HISTORY_SIZE;
+    }
+
+    /// most recent first, skipping the days that haven't happened yet
+    pub fn iter(&self) -> impl Iterator<Item = &DailyStats> {
+        let (older, newer) = self.historic_stats.split_at(self.oldest_stats);
+        older.iter().rev().chain(newer.iter().rev()).filter(|stats| stats.day != 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_end_day_wraps_around() {
+        let mut history = DailyStatsHistory {
+            proofs_since_last_distribution: 0,
+            miners_since_last_distribution: 0,
+            oldest_stats: 0,
+            historic_stats: [DailyStats::default(); STATS_HISTORY_SIZE],
+        };
+        history.record_proof(true);
+        history.record_proof(false);
+        history.end_day(DailyStats { day: 1, tokens_mined: 4, ..Default::default() });
+        assert_eq!(history.iter().count(), 1);
+        let first = history.iter().next().unwrap();
+        assert_eq!((first.proofs_accepted, first.unique_miners, first.tokens_mined), (2, 1, 4));
+
+        // the counts restart for the next day
+        for day in 2..=STATS_HISTORY_SIZE as i64 + 1 {
+            history.end_day(DailyStats { day, ..Default::default() });
+        }
+        let days: Vec<i64> = history.iter().map(|stats| stats.day).collect();
+        assert_eq!(days.len(), STATS_HISTORY_SIZE);
+        assert_eq!(days[0], STATS_HISTORY_SIZE as i64 + 1);
+        assert_eq!(days[STATS_HISTORY_SIZE - 1], 2, "the oldest day was overwritten");
+        assert!(history.iter().all(|stats| stats.proofs_accepted == 0));
+    }
+}
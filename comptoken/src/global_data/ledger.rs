@@ -0,0 +1,47 @@
+use super::daily_distribution_data::DailyDistributionValues;
+
+/// Running totals of every comptoken the program has issued and paid out of the banks, kept for auditing
+#[repr(C)]
+#[derive(Debug)]
+pub struct Ledger {
+    pub total_mined: u64,
+    pub total_interest_distributed: u64,
+    pub total_ubi_distributed: u64,
+    pub total_bounty_distributed: u64,
+    pub total_interest_claimed: u64,
+    pub total_ubi_claimed: u64,
+}
+
+impl Ledger {
+    pub(super) fn record_mining(&mut self, amount: u64) {
+        self.total_mined += amount;
+    }
+
+    pub(super) fn record_distribution(&mut self, distribution_values: &DailyDistributionValues) {
+        self.total_interest_distributed += distribution_values.interest_distributed;
+        self.total_ubi_distributed += distribution_values.ubi_distributed;
+        self.total_bounty_distributed += distribution_values.bounty_distributed;
+    }
+
+    pub(super) fn record_claim(&mut self, interest: u64, ubi: u64) {
+        self.total_interest_claimed += interest;
+        self.total_ubi_claimed += ubi;
+    }
+
+    /// everything issued through mining and distributions
+    pub fn total_issued(&self) -> u64 {
+        self.total_mined + self.total_interest_distributed + self.total_ubi_distributed + self.total_bounty_distributed
+    }
+
+    /// interest distributed to the interest bank that hasn't been claimed yet, or `None` if the ledger records more
+    /// claimed than was ever distributed
+    pub fn interest_liabilities(&self) -> Option<u64> {
+        self.total_interest_distributed.checked_sub(self.total_interest_claimed)
+    }
+
+    /// UBI distributed to the UBI bank that hasn't been claimed yet, or `None` if the ledger records more claimed than
+    /// was ever distributed
+    pub fn ubi_liabilities(&self) -> Option<u64> {
+        self.total_ubi_distributed.checked_sub(self.total_ubi_claimed)
+    }
+}
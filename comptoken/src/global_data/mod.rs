@@ -1,31 +1,106 @@
 pub mod daily_distribution_data;
+pub mod daily_stats;
+pub mod ledger;
 pub mod valid_blockhashes;
 
-use spl_token_2022::state::Mint;
+pub use comptoken_utils::config;
 
-use crate::VerifiedAccountInfo;
+use spl_token_2022::{solana_program::hash::Hash, state::Mint};
+
+use crate::{constants::ANNOUNCEMENT_INTERVAL, proof_scheme::ProofSchemeKind, TimeSource, VerifiedAccountInfo};
+use config::Config;
 use daily_distribution_data::{DailyDistributionData, DailyDistributionValues};
+use daily_stats::{DailyStats, DailyStatsHistory};
+use ledger::Ledger;
 use valid_blockhashes::ValidBlockhashes;
 
 #[repr(C)]
 #[derive(Debug)]
 // MAGIC NUMBER: Changes to the size of this struct need to be reflected in test_client.js
 pub struct GlobalData {
+    pub config: Config,
     pub valid_blockhashes: ValidBlockhashes,
     pub daily_distribution_data: DailyDistributionData,
+    pub ledger: Ledger,
+    pub daily_stats: DailyStatsHistory,
 }
 
 impl GlobalData {
-    pub fn initialize(&mut self, slot_hash_account: &VerifiedAccountInfo) {
-        self.valid_blockhashes.initialize(slot_hash_account);
-        self.daily_distribution_data.initialize();
+    pub fn initialize(&mut self, config: Config, slot_hash_account: &VerifiedAccountInfo, clock: &impl TimeSource) {
+        config.validate();
+        // the next blockhash is announced this long before each day starts
+        assert!(config.days.length > ANNOUNCEMENT_INTERVAL, "days must be longer than the announcement interval");
+        self.config = config;
+        self.valid_blockhashes.initialize(slot_hash_account, &config.days, clock);
+        self.daily_distribution_data.initialize(&config.days, clock);
     }
 
     pub fn daily_distribution_event(
-        &mut self, mint: Mint, slot_hash_account: &VerifiedAccountInfo,
+        &mut self, mint: Mint, slot_hash_account: &VerifiedAccountInfo, clock: &impl TimeSource,
     ) -> DailyDistributionValues {
-        self.valid_blockhashes.update(slot_hash_account);
-        self.daily_distribution_data.daily_distribution(mint)
+        self.valid_blockhashes.update(slot_hash_account, &self.config.days, clock);
+        let first_day = self.daily_distribution_data.last_daily_distribution_time;
+        let tokens_mined = self.daily_distribution_data.mined_since_last_distribution;
+        let distribution_values = self.daily_distribution_data.daily_distribution(
+            mint,
+            self.config.distribution_bounty,
+            &self.config.days,
+            clock,
+        );
+        self.ledger.record_distribution(&distribution_values);
+        self.record_daily_stats(first_day, tokens_mined, &distribution_values);
+        distribution_values
+    }
+
+    fn record_daily_stats(&mut self, first_day: i64, tokens_mined: u64, distribution_values: &DailyDistributionValues) {
+        let days = &self.config.days;
+        let daily_distribution_data = &self.daily_distribution_data;
+        let stats = DailyStats {
+            day: first_day,
+            tokens_mined,
+            high_water_mark: daily_distribution_data.high_water_mark,
+            supply: daily_distribution_data.yesterday_supply,
+            interest_distributed: distribution_values.interest_distributed,
+            ubi_distributed: distribution_values.ubi_distributed,
+            valid_blockhash: self.valid_blockhashes.blockhash_valid_on(first_day).unwrap_or_default(),
+            ..Default::default()
+        };
+        self.daily_stats.end_day(stats);
+        // all the mining is attributed to the first day, so any other days distributed by the same call were missed
+        let days_distributed = days.between(first_day, daily_distribution_data.last_daily_distribution_time);
+        for n in 1..days_distributed {
+            self.daily_stats.end_day(DailyStats {
+                day: first_day + n * days.length,
+                high_water_mark: stats.high_water_mark,
+                supply: stats.supply,
+                ..Default::default()
+            });
+        }
+    }
+
+    /// records comptokens minted for proofs, which the next daily distribution is based on
+    pub fn record_mining(&mut self, amount: u64) {
+        self.daily_distribution_data.mined_since_last_distribution += amount;
+        self.ledger.record_mining(amount);
+    }
+
+    /// records a proof accepted by `mint_comptokens`, `is_new_miner` if it was the wallet's first against its blockhash
+    pub fn record_proof(&mut self, is_new_miner: bool) {
+        self.daily_stats.record_proof(is_new_miner);
+    }
+
+    /// records comptokens paid out of the banks by `get_owed_comptokens`
+    pub fn record_claim(&mut self, interest: u64, ubi: u64) {
+        self.ledger.record_claim(interest, ubi);
+    }
+
+    pub fn is_blockhash_valid(&self, blockhash: &Hash, clock: &impl TimeSource) -> bool {
+        self.proof_scheme_for(blockhash, clock).is_some()
+    }
+
+    pub fn proof_scheme_for(&self, blockhash: &Hash, clock: &impl TimeSource) -> Option<ProofSchemeKind> {
+        self.valid_blockhashes
+            .proof_scheme_for(blockhash, self.config.blockhash_grace_period, &self.config.days, clock)
     }
 }
 
@@ -50,3 +125,46 @@ impl From<&mut [u8]> for &mut GlobalData {
         unsafe { &mut *(value as *mut _ as *mut GlobalData) }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use comptoken_utils::Days;
+
+    use super::*;
+
+    #[test]
+    fn test_record_daily_stats_for_missed_days() {
+        let mut data = vec![0_u8; std::mem::size_of::<GlobalData>()];
+        let global_data: &mut GlobalData = data.as_mut_slice().into();
+        global_data.config.days = Days::UTC;
+        let first_day = 10 * Days::UTC.length;
+        let valid_blockhash = Hash::new_unique();
+        global_data.valid_blockhashes.valid_blockhash = valid_blockhash;
+        global_data.valid_blockhashes.valid_blockhash_time = first_day;
+        global_data.record_proof(true);
+        // a distribution that caught up on three days, the last two of which were missed
+        global_data.daily_distribution_data.high_water_mark = 2;
+        global_data.daily_distribution_data.yesterday_supply = 10;
+        global_data.daily_distribution_data.last_daily_distribution_time = first_day + 3 * Days::UTC.length;
+        let distribution_values = DailyDistributionValues {
+            interest_distributed: 3,
+            ubi_distributed: 3,
+            bounty_distributed: 0,
+        };
+        global_data.record_daily_stats(first_day, 2, &distribution_values);
+
+        let stats: Vec<&DailyStats> = global_data.daily_stats.iter().collect();
+        assert_eq!(stats.len(), 3);
+        let first = stats[2];
+        assert_eq!(first.day, first_day);
+        assert_eq!((first.proofs_accepted, first.unique_miners, first.tokens_mined), (1, 1, 2));
+        assert_eq!((first.interest_distributed, first.ubi_distributed), (3, 3));
+        assert_eq!(first.valid_blockhash, valid_blockhash);
+        for (n, missed) in stats[..2].iter().rev().enumerate() {
+            assert_eq!(missed.day, first_day + (n as i64 + 1) * Days::UTC.length);
+            assert_eq!((missed.proofs_accepted, missed.tokens_mined, missed.interest_distributed), (0, 0, 0));
+            assert_eq!((missed.high_water_mark, missed.supply), (2, 10));
+            assert_eq!(missed.valid_blockhash, Hash::default());
+        }
+    }
+}
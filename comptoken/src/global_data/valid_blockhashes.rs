@@ -1,53 +1,368 @@
-use spl_token_2022::solana_program::{hash::Hash, slot_hashes::SlotHash};
+use spl_token_2022::solana_program::{
+    clock::{Clock, Slot},
+    hash::{hashv, Hash},
+    slot_hashes::SlotHash,
+    sysvar::Sysvar,
+};
 
-use comptoken_utils::{get_current_time, normalize_time, SEC_PER_DAY};
+use comptoken_utils::{Days, TimeSource};
 
-use crate::{constants::*, VerifiedAccountInfo};
+use crate::{constants::*, proof_scheme::ProofSchemeKind, VerifiedAccountInfo};
 
 #[repr(C)]
 #[derive(Debug)]
 pub struct ValidBlockhashes {
+    // the next challenge, derived by the first update once the slots its announcement committed to are produced.
+    // until then it is the valid blockhash, which the next challenge is chained from
     pub announced_blockhash: Hash,
     pub announced_blockhash_time: i64,
+    // the first slot whose hash is mixed into the next challenge. it is after the announcing slot, so the hashes
+    // aren't known to whoever announces, and is fixed before they are, so whoever promotes can't choose them either
+    pub announced_blockhash_slot: Slot,
     pub valid_blockhash: Hash,
     pub valid_blockhash_time: i64,
+    pub previous_valid_blockhash: Hash,
+    pub previous_valid_blockhash_time: i64,
+    // the scheme proofs against each blockhash are mined with
+    pub valid_proof_scheme: ProofSchemeKind,
+    pub previous_proof_scheme: ProofSchemeKind,
+    // becomes the valid proof scheme when the valid blockhash next rolls over
+    pub next_proof_scheme: ProofSchemeKind,
+    // padding: [u8; 5],
 }
 
 impl ValidBlockhashes {
-    pub(super) fn initialize(&mut self, slot_hash_account: &VerifiedAccountInfo) {
-        self.update(slot_hash_account);
+    pub(super) fn initialize(&mut self, slot_hash_account: &VerifiedAccountInfo, days: &Days, clock: &impl TimeSource) {
+        self.next_proof_scheme = ProofSchemeKind::Sha256;
+        self.update(slot_hash_account, days, clock);
     }
 
-    pub fn update(&mut self, slot_hash_account: &VerifiedAccountInfo) {
-        if self.is_announced_blockhash_stale() {
-            self.announced_blockhash = get_most_recent_blockhash(slot_hash_account);
-            // This is necessary for the case where a day's update has been "skipped"
-            self.announced_blockhash_time =
-                normalize_time(get_current_time() + ANNOUNCEMENT_INTERVAL) - ANNOUNCEMENT_INTERVAL;
+    pub fn update(&mut self, slot_hash_account: &VerifiedAccountInfo, days: &Days, clock: &impl TimeSource) {
+        let data = slot_hash_account.try_borrow_data().unwrap();
+        self.update_with(get_slot_hashes(&data), Clock::get().unwrap().slot, days, clock);
+    }
+
+    /// Announces a new blockhash and makes it valid straight away, as if the day had just changed. There are no
+    /// future slots to commit to, so the blockhash is derived from the most recent ones instead.
+    #[cfg(feature = "testmode")]
+    pub fn force_rotation(&mut self, slot_hash_account: &VerifiedAccountInfo, days: &Days, clock: &impl TimeSource) {
+        let data = slot_hash_account.try_borrow_data().unwrap();
+        self.rotate_with(get_slot_hashes(&data), days, clock);
+    }
+
+    #[cfg(feature = "testmode")]
+    fn rotate_with(&mut self, slot_hashes: &[SlotHash], days: &Days, clock: &impl TimeSource) {
+        assert!(!slot_hashes.is_empty(), "no slot hashes to derive a challenge from");
+        let most_recent = &slot_hashes[..slot_hashes.len().min(CHALLENGE_SLOT_HASH_COUNT)];
+        self.announce(most_recent[most_recent.len() - 1].0, days, clock);
+        self.announced_blockhash = derive_challenge(&self.announced_blockhash, most_recent);
+        self.promote_announced_blockhash(days, clock);
+    }
+
+    /// `slot_hashes` are the contents of the `SlotHashes` sysvar, most recent first, and `current_slot` the slot the
+    /// update happens in
+    fn update_with(&mut self, slot_hashes: &[SlotHash], current_slot: Slot, days: &Days, clock: &impl TimeSource) {
+        if self.is_announced_blockhash_stale(days, clock) {
+            self.announce(current_slot + 1, days, clock);
+        }
+        if self.is_announcement_outstanding() && !self.is_announced_blockhash_derived() {
+            match committed_slot_hashes(slot_hashes, self.announced_blockhash_slot) {
+                CommittedSlotHashes::Available(committed) => {
+                    self.announced_blockhash = derive_challenge(&self.announced_blockhash, committed);
+                }
+                // the next update after they are produced derives it
+                CommittedSlotHashes::Pending => {}
+                // no one updated in time to derive it, so commit to new slots without changing the announcement
+                CommittedSlotHashes::Expired => self.announced_blockhash_slot = current_slot + 1,
+            }
         }
-        if self.is_valid_blockhash_stale() {
-            self.valid_blockhash = self.announced_blockhash;
-            self.valid_blockhash_time = normalize_time(get_current_time());
+        if self.is_valid_blockhash_stale(days, clock) && self.is_announced_blockhash_derived() {
+            self.promote_announced_blockhash(days, clock);
         }
     }
 
-    pub fn is_announced_blockhash_stale(&self) -> bool {
-        get_current_time() > self.announced_blockhash_time + SEC_PER_DAY
+    fn announce(&mut self, first_slot: Slot, days: &Days, clock: &impl TimeSource) {
+        self.announced_blockhash_slot = first_slot;
+        // This is necessary for the case where a day's update has been "skipped"
+        self.announced_blockhash_time = days.start_of(clock.now() + ANNOUNCEMENT_INTERVAL) - ANNOUNCEMENT_INTERVAL;
+    }
+
+    fn promote_announced_blockhash(&mut self, days: &Days, clock: &impl TimeSource) {
+        self.previous_valid_blockhash = self.valid_blockhash;
+        self.previous_valid_blockhash_time = self.valid_blockhash_time;
+        self.valid_blockhash = self.announced_blockhash;
+        self.valid_blockhash_time = clock.today(days);
+        self.previous_proof_scheme = self.valid_proof_scheme;
+        self.valid_proof_scheme = self.next_proof_scheme;
     }
 
-    pub fn is_valid_blockhash_stale(&self) -> bool {
-        get_current_time() > self.valid_blockhash_time + SEC_PER_DAY
+    /// whether the last announcement is for a day after the valid blockhash's, so it is yet to be promoted
+    fn is_announcement_outstanding(&self) -> bool {
+        self.announced_blockhash_time + ANNOUNCEMENT_INTERVAL > self.valid_blockhash_time
+    }
+
+    /// whether the outstanding announcement's blockhash has been derived from the slots it committed to, so it can be
+    /// promoted
+    pub fn is_announced_blockhash_derived(&self) -> bool {
+        self.is_announcement_outstanding() && self.announced_blockhash != self.valid_blockhash
+    }
+
+    pub fn is_announced_blockhash_stale(&self, days: &Days, clock: &impl TimeSource) -> bool {
+        clock.now() > self.announced_blockhash_time + days.length
+    }
+
+    pub fn is_valid_blockhash_stale(&self, days: &Days, clock: &impl TimeSource) -> bool {
+        clock.now() > self.valid_blockhash_time + days.length
+    }
+
+    /// the blockhash that became valid at the start of `day`, if it is still known
+    pub fn blockhash_valid_on(&self, day: i64) -> Option<Hash> {
+        if self.valid_blockhash_time == day {
+            Some(self.valid_blockhash)
+        } else if self.previous_valid_blockhash_time == day {
+            Some(self.previous_valid_blockhash)
+        } else {
+            None
+        }
+    }
+
+    /// The scheme proofs against `blockhash` are mined with, or `None` if they are not accepted. A blockhash stays
+    /// accepted for `grace_period` seconds after it stops being the valid blockhash, so proofs mined just before the
+    /// change are not lost.
+    pub fn proof_scheme_for(
+        &self, blockhash: &Hash, grace_period: i64, days: &Days, clock: &impl TimeSource,
+    ) -> Option<ProofSchemeKind> {
+        let is_accepted =
+            |hash: &Hash, time: i64| hash == blockhash && clock.now() <= time + days.length + grace_period;
+        // the valid blockhash may not have been updated yet, in which case it is the one in its grace period
+        if is_accepted(&self.valid_blockhash, self.valid_blockhash_time) {
+            Some(self.valid_proof_scheme)
+        } else if is_accepted(&self.previous_valid_blockhash, self.previous_valid_blockhash_time) {
+            Some(self.previous_proof_scheme)
+        } else {
+            None
+        }
     }
 }
 
-fn get_most_recent_blockhash(slot_hash_account: &VerifiedAccountInfo) -> Hash {
+fn get_slot_hashes(data: &[u8]) -> &[SlotHash] {
     // slothashes is too large to deserialize with the normal methods
     // based on https://github.com/solana-labs/solana/issues/33015
-    let data = slot_hash_account.try_borrow_data().unwrap();
     let len: usize = usize::from_ne_bytes(data[0..8].try_into().expect("correct size"));
-    let slot_hashes: &[SlotHash] =
-        unsafe { std::slice::from_raw_parts(data.as_ptr().offset(8) as *const SlotHash, len) };
+    unsafe { std::slice::from_raw_parts(data.as_ptr().offset(8) as *const SlotHash, len) }
+}
+
+/// Where the slot hashes an announcement committed to are in the `SlotHashes` sysvar
+#[derive(Debug, PartialEq)]
+pub enum CommittedSlotHashes<'a> {
+    /// fewer than `CHALLENGE_SLOT_HASH_COUNT` slots have been produced since the first committed slot
+    Pending,
+    /// the committed slots and their hashes, most recent first
+    Available(&'a [SlotHash]),
+    /// the sysvar no longer reaches back to the first committed slot
+    Expired,
+}
+
+/// The `CHALLENGE_SLOT_HASH_COUNT` slot hashes from `first_slot` onwards in `slot_hashes`, which is most recent first.
+/// Skipped slots have no hash, so these are the first that were produced rather than a fixed range.
+pub fn committed_slot_hashes(slot_hashes: &[SlotHash], first_slot: Slot) -> CommittedSlotHashes<'_> {
+    let reaches_first_slot = slot_hashes.last().is_some_and(|(oldest, _)| *oldest <= first_slot);
+    if !reaches_first_slot {
+        return CommittedSlotHashes::Expired;
+    }
+    let produced = slot_hashes.partition_point(|(slot, _)| *slot >= first_slot);
+    if produced < CHALLENGE_SLOT_HASH_COUNT {
+        CommittedSlotHashes::Pending
+    } else {
+        CommittedSlotHashes::Available(&slot_hashes[produced - CHALLENGE_SLOT_HASH_COUNT..produced])
+    }
+}
+
+/// The next mining challenge: the previous one hashed with each of `slot_hashes`' slots and hashes in order
+pub fn derive_challenge(previous_challenge: &Hash, slot_hashes: &[SlotHash]) -> Hash {
+    let slots: Vec<[u8; 8]> = slot_hashes.iter().map(|(slot, _)| slot.to_le_bytes()).collect();
+    let mut input: Vec<&[u8]> = vec![previous_challenge.as_ref()];
+    for ((_, hash), slot) in slot_hashes.iter().zip(&slots) {
+        input.push(slot);
+        input.push(hash.as_ref());
+    }
+    hashv(&input)
+}
+
+#[cfg(test)]
+mod test {
+    use comptoken_utils::MockClock;
+
+    use super::*;
 
-    // get the hash from the most recent slot
-    slot_hashes[0].1
+    fn zeroed() -> ValidBlockhashes {
+        ValidBlockhashes {
+            announced_blockhash: Hash::default(),
+            announced_blockhash_time: 0,
+            announced_blockhash_slot: 0,
+            valid_blockhash: Hash::default(),
+            valid_blockhash_time: 0,
+            previous_valid_blockhash: Hash::default(),
+            previous_valid_blockhash_time: 0,
+            valid_proof_scheme: ProofSchemeKind::Sha256,
+            previous_proof_scheme: ProofSchemeKind::Sha256,
+            next_proof_scheme: ProofSchemeKind::Sha256,
+        }
+    }
+
+    /// the `SlotHashes` sysvar just before `current_slot`, holding every slot in `oldest..current_slot`
+    fn produced(oldest: Slot, current_slot: Slot) -> Vec<SlotHash> {
+        (oldest..current_slot).rev().map(|slot| (slot, hashv(&[&slot.to_le_bytes()]))).collect()
+    }
+
+    #[test]
+    fn test_blockhash_rollover_and_grace_period() {
+        check_blockhash_rollover_and_grace_period(Days::UTC);
+        // a test deployment's 10 minute days, starting 2 minutes past every 10 minutes
+        check_blockhash_rollover_and_grace_period(Days { length: 600, offset: 120 });
+    }
+
+    fn check_blockhash_rollover_and_grace_period(days: Days) {
+        let grace_period = 60;
+        let count = CHALLENGE_SLOT_HASH_COUNT as Slot;
+        let day = |n: i64| n * days.length + days.offset;
+        let clock = MockClock::new(day(10) + 1);
+        let mut valid_blockhashes = zeroed();
+
+        // the announcement commits to the slots after its own, so nothing is valid until they are produced
+        valid_blockhashes.update_with(&produced(0, 100), 100, &days, &clock);
+        assert_eq!(valid_blockhashes.announced_blockhash_time, day(10) - ANNOUNCEMENT_INTERVAL);
+        assert_eq!(valid_blockhashes.announced_blockhash_slot, 101);
+        assert_eq!(valid_blockhashes.valid_blockhash, Hash::default());
+        valid_blockhashes.update_with(&produced(0, 100 + count), 100 + count, &days, &clock);
+        assert_eq!(valid_blockhashes.valid_blockhash, Hash::default());
+
+        let committed = produced(101, 101 + count);
+        let first = derive_challenge(&Hash::default(), &committed);
+        valid_blockhashes.update_with(&produced(0, 101 + count), 101 + count, &days, &clock);
+        assert_eq!(valid_blockhashes.valid_blockhash, first);
+        assert_eq!(valid_blockhashes.valid_blockhash_time, day(10));
+
+        // nothing changes until the next announcement
+        valid_blockhashes.update_with(&produced(0, 200), 200, &days, &clock);
+        assert_eq!(valid_blockhashes.announced_blockhash_slot, 101);
+        assert_eq!(valid_blockhashes.valid_blockhash, first);
+
+        // the next blockhash is announced shortly before the day changes, and derived as soon as its slots are produced
+        clock.set(day(11) - ANNOUNCEMENT_INTERVAL + 1);
+        valid_blockhashes.update_with(&produced(0, 300), 300, &days, &clock);
+        assert_eq!(valid_blockhashes.announced_blockhash_slot, 301);
+        assert_eq!(valid_blockhashes.announced_blockhash, first);
+        let second = derive_challenge(&first, &produced(301, 301 + count));
+        valid_blockhashes.update_with(&produced(0, 350), 350, &days, &clock);
+        assert_eq!(valid_blockhashes.announced_blockhash, second);
+        assert_eq!(valid_blockhashes.valid_blockhash, first);
+
+        // and becomes valid once the day changes, keeping the old one for the grace period
+        clock.set(day(11) + 1);
+        valid_blockhashes.update_with(&produced(200, 600), 600, &days, &clock);
+        assert_eq!(valid_blockhashes.valid_blockhash, second);
+        assert_eq!(valid_blockhashes.previous_valid_blockhash, first);
+        assert!(valid_blockhashes.proof_scheme_for(&second, grace_period, &days, &clock).is_some());
+        assert!(valid_blockhashes.proof_scheme_for(&first, grace_period, &days, &clock).is_some());
+
+        clock.set(day(11) + grace_period);
+        assert!(valid_blockhashes.proof_scheme_for(&first, grace_period, &days, &clock).is_some());
+        clock.advance(1);
+        assert!(valid_blockhashes.proof_scheme_for(&first, grace_period, &days, &clock).is_none());
+        assert!(valid_blockhashes.proof_scheme_for(&second, grace_period, &days, &clock).is_some());
+    }
+
+    #[test]
+    fn test_promotion_slot_does_not_change_challenge() {
+        let days = Days::UTC;
+        let clock = MockClock::new(10 * days.length + 1);
+        let mut announced = zeroed();
+        announced.update_with(&produced(0, 100), 100, &days, &clock);
+
+        // whichever slot promotes the announced blockhash, it is derived from the same committed slots
+        let challenges: Vec<Hash> = [120, 250, 400]
+            .into_iter()
+            .map(|current_slot| {
+                let mut valid_blockhashes = zeroed();
+                valid_blockhashes.announced_blockhash_slot = announced.announced_blockhash_slot;
+                valid_blockhashes.announced_blockhash_time = announced.announced_blockhash_time;
+                valid_blockhashes.update_with(&produced(0, current_slot), current_slot, &days, &clock);
+                valid_blockhashes.valid_blockhash
+            })
+            .collect();
+        assert_ne!(challenges[0], Hash::default());
+        assert!(challenges.iter().all(|challenge| *challenge == challenges[0]));
+    }
+
+    #[test]
+    fn test_expired_commitment_is_renewed() {
+        let days = Days::UTC;
+        let clock = MockClock::new(10 * days.length + 1);
+        let mut valid_blockhashes = zeroed();
+        valid_blockhashes.update_with(&produced(0, 100), 100, &days, &clock);
+
+        // the sysvar only keeps the most recent slots, so late promotions commit to new ones instead
+        valid_blockhashes.update_with(&produced(488, 1_000), 1_000, &days, &clock);
+        assert_eq!(valid_blockhashes.announced_blockhash_slot, 1_001);
+        assert_eq!(valid_blockhashes.announced_blockhash_time, 10 * days.length - ANNOUNCEMENT_INTERVAL);
+        assert_eq!(valid_blockhashes.valid_blockhash, Hash::default());
+
+        let committed = produced(1_001, 1_001 + CHALLENGE_SLOT_HASH_COUNT as Slot);
+        valid_blockhashes.update_with(&produced(600, 1_100), 1_100, &days, &clock);
+        assert_eq!(valid_blockhashes.valid_blockhash, derive_challenge(&Hash::default(), &committed));
+    }
+
+    #[cfg(feature = "testmode")]
+    #[test]
+    fn test_force_rotation() {
+        let days = Days::UTC;
+        let clock = MockClock::new(10 * days.length + 1);
+        let mut valid_blockhashes = zeroed();
+        valid_blockhashes.update_with(&produced(0, 100), 100, &days, &clock);
+        valid_blockhashes.update_with(&produced(0, 200), 200, &days, &clock);
+        let first = valid_blockhashes.valid_blockhash;
+
+        // mid day, so nothing would normally change
+        clock.advance(days.length / 2);
+        valid_blockhashes.rotate_with(&produced(0, 300), &days, &clock);
+        let second = valid_blockhashes.valid_blockhash;
+        assert_eq!(second, derive_challenge(&first, &produced(300 - CHALLENGE_SLOT_HASH_COUNT as Slot, 300)));
+        assert_eq!(valid_blockhashes.previous_valid_blockhash, first);
+
+        // and the regular schedule carries on from the rotated blockhash
+        valid_blockhashes.update_with(&produced(0, 400), 400, &days, &clock);
+        assert_eq!(valid_blockhashes.valid_blockhash, second);
+    }
+
+    #[test]
+    fn test_committed_slot_hashes() {
+        // slot 104 was skipped
+        let mut slot_hashes = produced(90, 120);
+        slot_hashes.retain(|(slot, _)| *slot != 104);
+        let CommittedSlotHashes::Available(committed) = committed_slot_hashes(&slot_hashes, 101) else {
+            panic!("the committed slots have been produced");
+        };
+        let slots: Vec<Slot> = committed.iter().map(|(slot, _)| *slot).collect();
+        assert_eq!(slots, [109, 108, 107, 106, 105, 103, 102, 101]);
+
+        assert_eq!(committed_slot_hashes(&slot_hashes, 113), CommittedSlotHashes::Pending);
+        assert_eq!(committed_slot_hashes(&slot_hashes, 89), CommittedSlotHashes::Expired);
+        assert_eq!(committed_slot_hashes(&[], 89), CommittedSlotHashes::Expired);
+    }
+
+    #[test]
+    fn test_derive_challenge() {
+        let previous = Hash::new_unique();
+        let slot_hashes = produced(0, CHALLENGE_SLOT_HASH_COUNT as Slot);
+        let challenge = derive_challenge(&previous, &slot_hashes);
+
+        // every slot hash matters, as does the previous challenge
+        for i in 0..CHALLENGE_SLOT_HASH_COUNT {
+            let mut changed = slot_hashes.clone();
+            changed[i].1 = Hash::new_unique();
+            assert_ne!(derive_challenge(&previous, &changed), challenge);
+        }
+        assert_ne!(derive_challenge(&Hash::new_unique(), &slot_hashes), challenge);
+    }
 }
@@ -0,0 +1,85 @@
+use spl_token_2022::solana_program::{blake3, hash, keccak};
+
+use hash::Hash;
+
+/// A hash function proofs can be mined with. Each implementation uses the matching solana syscall on chain.
+// ensure implementations remain consistent with comptoken_proof.js
+pub trait ProofScheme {
+    fn hashv(vals: &[&[u8]]) -> Hash;
+}
+
+pub struct Sha256;
+
+impl ProofScheme for Sha256 {
+    fn hashv(vals: &[&[u8]]) -> Hash {
+        hash::hashv(vals)
+    }
+}
+
+pub struct Keccak256;
+
+impl ProofScheme for Keccak256 {
+    fn hashv(vals: &[&[u8]]) -> Hash {
+        Hash::new_from_array(keccak::hashv(vals).to_bytes())
+    }
+}
+
+pub struct Blake3;
+
+impl ProofScheme for Blake3 {
+    fn hashv(vals: &[&[u8]]) -> Hash {
+        Hash::new_from_array(blake3::hashv(vals).to_bytes())
+    }
+}
+
+/// Which `ProofScheme` proofs against a blockhash are mined with, as stored in `GlobalData`
+// ensure this remains consistent with comptoken_proof.js
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ProofSchemeKind {
+    Sha256 = 0,
+    Keccak256 = 1,
+    Blake3 = 2,
+}
+
+impl ProofSchemeKind {
+    pub fn hashv(self, vals: &[&[u8]]) -> Hash {
+        match self {
+            ProofSchemeKind::Sha256 => Sha256::hashv(vals),
+            ProofSchemeKind::Keccak256 => Keccak256::hashv(vals),
+            ProofSchemeKind::Blake3 => Blake3::hashv(vals),
+        }
+    }
+}
+
+impl TryFrom<u8> for ProofSchemeKind {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ProofSchemeKind::Sha256),
+            1 => Ok(ProofSchemeKind::Keccak256),
+            2 => Ok(ProofSchemeKind::Blake3),
+            _ => Err(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_schemes_differ() {
+        let vals: &[&[u8]] = &[b"comptoken", &[1, 2, 3]];
+        let sha256 = ProofSchemeKind::Sha256.hashv(vals);
+        let keccak = ProofSchemeKind::Keccak256.hashv(vals);
+        let blake3 = ProofSchemeKind::Blake3.hashv(vals);
+
+        assert_eq!(sha256, hash::hash(b"comptoken\x01\x02\x03"));
+        assert_ne!(sha256, keccak);
+        assert_ne!(sha256, blake3);
+        assert_ne!(keccak, blake3);
+    }
+}
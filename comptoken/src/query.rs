@@ -0,0 +1,481 @@
+//! The `query_global_data` instruction's pages, and the client side helpers to fetch and decode them.
+//!
+//! Return data is capped at `MAX_RETURN_DATA` bytes, less than the global data, so each selection is split into
+//! pages of whole records. Every page starts with a header naming the layout version, the selection, and how many
+//! pages it has, so clients can fetch page 0 and then know how many more to ask for.
+
+use std::fmt::{self, Display};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use spl_token_2022::solana_program::{
+    hash::{Hash, HASH_BYTES},
+    program::MAX_RETURN_DATA,
+    pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+use comptoken_utils::SEC_PER_DAY;
+
+use crate::{accounts::DailyStats, global_data::GlobalData};
+
+/// bumped whenever the layout of a page changes, ensure this remains consistent with common.js
+pub const QUERY_VERSION: u8 = 1;
+//  page header:
+//      u8: QUERY_VERSION
+//      u8: the selector
+//      u16: the page index
+//      u16: the number of pages
+pub const PAGE_HEADER_SIZE: usize = 6;
+pub const MAX_PAGE_PAYLOAD_SIZE: usize = MAX_RETURN_DATA - PAGE_HEADER_SIZE;
+
+/// What part of the global data to query
+// ensure this remains consistent with common.js
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum QuerySelector {
+    //  i64 and u64 fields: distribution bounty, blockhash grace period, v1 proof deadline, day length, day offset
+    //  followed by the admin
+    Parameters = 0,
+    //  u64: high water mark, yesterday's supply, last daily distribution time, mined since the last distribution
+    //  f64: the current (most recent) interest rate, and the APY it would compound to
+    Distribution = 1,
+    //  f64 interest rates, most recent first
+    InterestHistory = 2,
+    //  `DailyStats`, most recent first: an i64 day, 7 u64 counts in declaration order, and the valid blockhash
+    StatsHistory = 3,
+    //  i64: announced blockhash time, u64: announced blockhash slot, i64: valid and previous valid blockhash times
+    BlockhashTimes = 4,
+}
+
+impl QuerySelector {
+    /// every page of a selection holds a whole number of these
+    pub const fn record_size(self) -> usize {
+        match self {
+            QuerySelector::Parameters => 5 * 8 + PUBKEY_BYTES,
+            QuerySelector::Distribution => 6 * 8,
+            QuerySelector::InterestHistory => 8,
+            QuerySelector::StatsHistory => 8 * 8 + HASH_BYTES,
+            QuerySelector::BlockhashTimes => 4 * 8,
+        }
+    }
+
+    pub const fn records_per_page(self) -> usize {
+        MAX_PAGE_PAYLOAD_SIZE / self.record_size()
+    }
+}
+
+impl TryFrom<u8> for QuerySelector {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(QuerySelector::Parameters),
+            1 => Ok(QuerySelector::Distribution),
+            2 => Ok(QuerySelector::InterestHistory),
+            3 => Ok(QuerySelector::StatsHistory),
+            4 => Ok(QuerySelector::BlockhashTimes),
+            _ => Err(value),
+        }
+    }
+}
+
+/// the APY that `interest` per day compounds to, for days `day_length` seconds long
+pub fn apy(interest: f64, day_length: i64) -> f64 {
+    let days_per_year = (365 * SEC_PER_DAY) as f64 / day_length as f64;
+    (1. + interest).powf(days_per_year) - 1.
+}
+
+/// how many records the selection has
+fn record_count(global_data: &GlobalData, selector: QuerySelector) -> usize {
+    match selector {
+        QuerySelector::InterestHistory => global_data.daily_distribution_data.into_iter().count(),
+        QuerySelector::StatsHistory => global_data.daily_stats.iter().count(),
+        QuerySelector::Parameters | QuerySelector::Distribution | QuerySelector::BlockhashTimes => 1,
+    }
+}
+
+/// the selection's records from the `skip`th on, at most `take` of them, back to back
+fn records(global_data: &GlobalData, selector: QuerySelector, skip: usize, take: usize) -> Vec<u8> {
+    let config = &global_data.config;
+    let daily_distribution_data = &global_data.daily_distribution_data;
+    let valid_blockhashes = &global_data.valid_blockhashes;
+    let mut data = Vec::with_capacity(take * selector.record_size());
+    match selector {
+        QuerySelector::Parameters => {
+            data.extend(config.distribution_bounty.to_le_bytes());
+            for value in [
+                config.blockhash_grace_period,
+                config.v1_proof_deadline,
+                config.days.length,
+                config.days.offset,
+            ] {
+                data.extend(value.to_le_bytes());
+            }
+            data.extend(config.admin.to_bytes());
+        }
+        QuerySelector::Distribution => {
+            data.extend(daily_distribution_data.high_water_mark.to_le_bytes());
+            data.extend(daily_distribution_data.yesterday_supply.to_le_bytes());
+            data.extend(daily_distribution_data.last_daily_distribution_time.to_le_bytes());
+            data.extend(daily_distribution_data.mined_since_last_distribution.to_le_bytes());
+            let interest = daily_distribution_data.newest_interest();
+            data.extend(interest.to_le_bytes());
+            data.extend(apy(interest, config.days.length).to_le_bytes());
+        }
+        QuerySelector::InterestHistory => {
+            for interest in daily_distribution_data.into_iter().skip(skip).take(take) {
+                data.extend(interest.to_le_bytes());
+            }
+        }
+        QuerySelector::StatsHistory => {
+            for stats in global_data.daily_stats.iter().skip(skip).take(take) {
+                data.extend(stats.day.to_le_bytes());
+                for value in [
+                    stats.proofs_accepted,
+                    stats.unique_miners,
+                    stats.tokens_mined,
+                    stats.high_water_mark,
+                    stats.supply,
+                    stats.interest_distributed,
+                    stats.ubi_distributed,
+                ] {
+                    data.extend(value.to_le_bytes());
+                }
+                data.extend(stats.valid_blockhash.to_bytes());
+            }
+        }
+        QuerySelector::BlockhashTimes => {
+            data.extend(valid_blockhashes.announced_blockhash_time.to_le_bytes());
+            data.extend(valid_blockhashes.announced_blockhash_slot.to_le_bytes());
+            data.extend(valid_blockhashes.valid_blockhash_time.to_le_bytes());
+            data.extend(valid_blockhashes.previous_valid_blockhash_time.to_le_bytes());
+        }
+    }
+    data
+}
+
+/// the return data of `query_global_data`: the header followed by the page's records, or `None` if the selection
+/// doesn't have that many pages
+pub fn page(global_data: &GlobalData, selector: QuerySelector, page: u16) -> Option<Vec<u8>> {
+    let records_per_page = selector.records_per_page();
+    // an empty selection still has a page, so clients can tell it is empty
+    let page_count = record_count(global_data, selector).div_ceil(records_per_page).max(1);
+    if page as usize >= page_count {
+        return None;
+    }
+
+    // only the requested page's records are encoded
+    let records = records(global_data, selector, page as usize * records_per_page, records_per_page);
+    let mut data = Vec::with_capacity(PAGE_HEADER_SIZE + records.len());
+    data.push(QUERY_VERSION);
+    data.push(selector as u8);
+    data.extend(page.to_le_bytes());
+    data.extend((page_count as u16).to_le_bytes());
+    data.extend(records);
+    Some(data)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    UnsupportedVersion { version: u8 },
+    UnexpectedPage { selector: u8, page: u16, page_count: u16 },
+    InvalidSize { size: usize },
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::UnsupportedVersion { version } => {
+                write!(f, "query version {version} is not supported, expected {QUERY_VERSION}")
+            }
+            QueryError::UnexpectedPage { selector, page, page_count } => {
+                write!(f, "unexpected page {page} of {page_count} for selector {selector}")
+            }
+            QueryError::InvalidSize { size } => write!(f, "{size} bytes is not a valid query page"),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// A page of `query_global_data` return data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryPage<'a> {
+    pub selector: u8,
+    pub page: u16,
+    pub page_count: u16,
+    pub records: &'a [u8],
+}
+
+impl<'a> QueryPage<'a> {
+    pub fn from_bytes(data: &'a [u8]) -> Result<Self, QueryError> {
+        if data.len() < PAGE_HEADER_SIZE {
+            return Err(QueryError::InvalidSize { size: data.len() });
+        }
+        if data[0] != QUERY_VERSION {
+            return Err(QueryError::UnsupportedVersion { version: data[0] });
+        }
+        Ok(QueryPage {
+            selector: data[1],
+            page: u16::from_le_bytes([data[2], data[3]]),
+            page_count: u16::from_le_bytes([data[4], data[5]]),
+            records: &data[PAGE_HEADER_SIZE..],
+        })
+    }
+}
+
+/// Fetches every page of `selector` with `fetch_page`, e.g. by simulating `query_global_data`, and decodes them
+pub fn fetch<E: From<QueryError>>(
+    selector: QuerySelector, mut fetch_page: impl FnMut(u16) -> Result<Vec<u8>, E>,
+) -> Result<QueryResult, E> {
+    let mut records = Vec::new();
+    let mut page_count = 1;
+    let mut page = 0;
+    while page < page_count {
+        let data = fetch_page(page)?;
+        let query_page = QueryPage::from_bytes(&data)?;
+        // every page has to agree on the page count, or the global data changed between fetches
+        if query_page.selector != selector as u8
+            || query_page.page != page
+            || (page > 0 && query_page.page_count != page_count)
+        {
+            return Err(QueryError::UnexpectedPage {
+                selector: query_page.selector,
+                page: query_page.page,
+                page_count: query_page.page_count,
+            }
+            .into());
+        }
+        page_count = query_page.page_count;
+        records.extend_from_slice(query_page.records);
+        page += 1;
+    }
+    Ok(QueryResult::from_records(selector, &records)?)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Parameters {
+    pub distribution_bounty: u64,
+    pub blockhash_grace_period: i64,
+    pub v1_proof_deadline: i64,
+    pub day_length: i64,
+    pub day_offset: i64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::accounts::as_string"))]
+    pub admin: Pubkey,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct DistributionSummary {
+    pub high_water_mark: u64,
+    pub yesterday_supply: u64,
+    pub last_daily_distribution_time: i64,
+    pub mined_since_last_distribution: u64,
+    pub interest: f64,
+    pub apy: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct BlockhashTimes {
+    pub announced_blockhash_time: i64,
+    pub announced_blockhash_slot: u64,
+    pub valid_blockhash_time: i64,
+    pub previous_valid_blockhash_time: i64,
+}
+
+/// A whole selection, decoded
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum QueryResult {
+    Parameters(Parameters),
+    Distribution(DistributionSummary),
+    /// most recent first
+    InterestHistory(Vec<f64>),
+    /// most recent first
+    StatsHistory(Vec<DailyStats>),
+    BlockhashTimes(BlockhashTimes),
+}
+
+impl QueryResult {
+    pub fn from_records(selector: QuerySelector, data: &[u8]) -> Result<Self, QueryError> {
+        let record_size = selector.record_size();
+        let is_valid_size = match selector {
+            QuerySelector::InterestHistory | QuerySelector::StatsHistory => {
+                data.chunks_exact(record_size).remainder().is_empty()
+            }
+            _ => data.len() == record_size,
+        };
+        if !is_valid_size {
+            return Err(QueryError::InvalidSize { size: data.len() });
+        }
+        let word = |record: &[u8], i: usize| -> [u8; 8] { record[8 * i..8 * i + 8].try_into().unwrap() };
+        Ok(match selector {
+            QuerySelector::Parameters => QueryResult::Parameters(Parameters {
+                distribution_bounty: u64::from_le_bytes(word(data, 0)),
+                blockhash_grace_period: i64::from_le_bytes(word(data, 1)),
+                v1_proof_deadline: i64::from_le_bytes(word(data, 2)),
+                day_length: i64::from_le_bytes(word(data, 3)),
+                day_offset: i64::from_le_bytes(word(data, 4)),
+                admin: Pubkey::new_from_array(data[40..40 + PUBKEY_BYTES].try_into().unwrap()),
+            }),
+            QuerySelector::Distribution => QueryResult::Distribution(DistributionSummary {
+                high_water_mark: u64::from_le_bytes(word(data, 0)),
+                yesterday_supply: u64::from_le_bytes(word(data, 1)),
+                last_daily_distribution_time: i64::from_le_bytes(word(data, 2)),
+                mined_since_last_distribution: u64::from_le_bytes(word(data, 3)),
+                interest: f64::from_le_bytes(word(data, 4)),
+                apy: f64::from_le_bytes(word(data, 5)),
+            }),
+            QuerySelector::InterestHistory => QueryResult::InterestHistory(
+                data.chunks_exact(record_size).map(|record| f64::from_le_bytes(word(record, 0))).collect(),
+            ),
+            QuerySelector::StatsHistory => QueryResult::StatsHistory(
+                data.chunks_exact(record_size)
+                    .map(|record| DailyStats {
+                        day: i64::from_le_bytes(word(record, 0)),
+                        proofs_accepted: u64::from_le_bytes(word(record, 1)),
+                        unique_miners: u64::from_le_bytes(word(record, 2)),
+                        tokens_mined: u64::from_le_bytes(word(record, 3)),
+                        high_water_mark: u64::from_le_bytes(word(record, 4)),
+                        supply: u64::from_le_bytes(word(record, 5)),
+                        interest_distributed: u64::from_le_bytes(word(record, 6)),
+                        ubi_distributed: u64::from_le_bytes(word(record, 7)),
+                        valid_blockhash: Hash::new_from_array(record[64..64 + HASH_BYTES].try_into().unwrap()),
+                    })
+                    .collect(),
+            ),
+            QuerySelector::BlockhashTimes => QueryResult::BlockhashTimes(BlockhashTimes {
+                announced_blockhash_time: i64::from_le_bytes(word(data, 0)),
+                announced_blockhash_slot: u64::from_le_bytes(word(data, 1)),
+                valid_blockhash_time: i64::from_le_bytes(word(data, 2)),
+                previous_valid_blockhash_time: i64::from_le_bytes(word(data, 3)),
+            }),
+        })
+    }
+}
+
+impl Display for QueryResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryResult::Parameters(parameters) => {
+                writeln!(f, "Distribution Bounty: {}", parameters.distribution_bounty)?;
+                writeln!(f, "Blockhash Grace Period: {}", parameters.blockhash_grace_period)?;
+                writeln!(f, "V1 Proof Deadline: {}", parameters.v1_proof_deadline)?;
+                writeln!(f, "Day Length: {} (offset {})", parameters.day_length, parameters.day_offset)?;
+                write!(f, "Admin: {}", parameters.admin)
+            }
+            QueryResult::Distribution(summary) => {
+                writeln!(f, "High Water Mark: {}", summary.high_water_mark)?;
+                writeln!(f, "Yesterday's Supply: {}", summary.yesterday_supply)?;
+                writeln!(f, "Last Daily Distribution: {}", summary.last_daily_distribution_time)?;
+                writeln!(f, "Mined Since Last Distribution: {}", summary.mined_since_last_distribution)?;
+                write!(f, "Interest: {} (APY {})", summary.interest, summary.apy)
+            }
+            QueryResult::InterestHistory(interests) => {
+                write!(f, "Interest Rates:")?;
+                for interest in interests {
+                    write!(f, " {interest}")?;
+                }
+                Ok(())
+            }
+            QueryResult::StatsHistory(history) => {
+                for stats in history {
+                    writeln!(
+                        f,
+                        "Day {}: {} proofs by {} miners, {} mined, {} interest, {} UBI, supply {}, blockhash {}",
+                        stats.day,
+                        stats.proofs_accepted,
+                        stats.unique_miners,
+                        stats.tokens_mined,
+                        stats.interest_distributed,
+                        stats.ubi_distributed,
+                        stats.supply,
+                        stats.valid_blockhash
+                    )?;
+                }
+                Ok(())
+            }
+            QueryResult::BlockhashTimes(times) => {
+                writeln!(
+                    f,
+                    "Announced Blockhash: at {} (from slot {})",
+                    times.announced_blockhash_time, times.announced_blockhash_slot
+                )?;
+                writeln!(f, "Valid Blockhash: at {}", times.valid_blockhash_time)?;
+                write!(f, "Previous Valid Blockhash: at {}", times.previous_valid_blockhash_time)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{accounts::GLOBAL_DATA_SIZE, global_data::daily_stats};
+
+    use super::*;
+
+    fn fetch_from(global_data: &GlobalData, selector: QuerySelector) -> Result<QueryResult, QueryError> {
+        fetch(selector, |index| Ok::<_, QueryError>(page(global_data, selector, index).unwrap()))
+    }
+
+    #[test]
+    fn test_history_reassembles_across_pages() {
+        let mut data = vec![0_u8; GLOBAL_DATA_SIZE];
+        let global_data: &mut GlobalData = data.as_mut_slice().into();
+        for interest in 1..=365 {
+            global_data.daily_distribution_data.insert(interest as f64);
+        }
+        assert_eq!(page(global_data, QuerySelector::InterestHistory, 0).unwrap()[4..6], 3_u16.to_le_bytes());
+        assert!(page(global_data, QuerySelector::InterestHistory, 3).is_none(), "there are only 3 pages");
+        let Ok(QueryResult::InterestHistory(interests)) = fetch_from(global_data, QuerySelector::InterestHistory)
+        else {
+            panic!("expected the interest history");
+        };
+        assert_eq!(interests.len(), 365);
+        assert_eq!((interests[0], interests[364]), (365., 1.), "most recent first");
+
+        // the stats history is empty until a day ends
+        assert_eq!(fetch_from(global_data, QuerySelector::StatsHistory), Ok(QueryResult::StatsHistory(vec![])));
+        let stats = daily_stats::DailyStats { day: SEC_PER_DAY, proofs_accepted: 3, ..Default::default() };
+        global_data.daily_stats.historic_stats[..11].fill(stats);
+        global_data.daily_stats.oldest_stats = 11;
+        let Ok(QueryResult::StatsHistory(history)) = fetch_from(global_data, QuerySelector::StatsHistory) else {
+            panic!("expected the stats history");
+        };
+        assert_eq!(history.len(), 11, "split over two pages");
+        assert_eq!(history[0], (&stats).into());
+    }
+
+    #[test]
+    fn test_distribution_summary() {
+        let mut data = vec![0_u8; GLOBAL_DATA_SIZE];
+        let global_data: &mut GlobalData = data.as_mut_slice().into();
+        global_data.config.days.length = SEC_PER_DAY;
+        global_data.daily_distribution_data.high_water_mark = 7;
+        global_data.daily_distribution_data.insert(0.5);
+        global_data.daily_distribution_data.insert(0.001);
+        let Ok(QueryResult::Distribution(summary)) = fetch_from(global_data, QuerySelector::Distribution) else {
+            panic!("expected the distribution summary");
+        };
+        assert_eq!(summary.high_water_mark, 7);
+        assert_eq!(summary.interest, 0.001, "the most recent interest");
+        assert!((summary.apy - (1.001_f64.powi(365) - 1.)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rejects_mismatched_pages() {
+        let mut data = vec![0_u8; GLOBAL_DATA_SIZE];
+        let global_data: &mut GlobalData = data.as_mut_slice().into();
+        let result = fetch(QuerySelector::Parameters, |index| {
+            Ok::<_, QueryError>(page(global_data, QuerySelector::BlockhashTimes, index).unwrap())
+        });
+        assert!(matches!(result, Err(QueryError::UnexpectedPage { selector: 4, .. })));
+
+        let mut old_version = page(global_data, QuerySelector::Parameters, 0).unwrap();
+        old_version[0] = QUERY_VERSION + 1;
+        assert_eq!(QueryPage::from_bytes(&old_version), Err(QueryError::UnsupportedVersion { version: 2 }));
+    }
+}
@@ -0,0 +1,151 @@
+//! Instructions that let tests put the program into otherwise unreachable states.
+//!
+//! They are only compiled in with the `testmode` feature, which production deployments must never enable.
+
+use spl_token_2022::solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    pubkey::Pubkey,
+};
+
+use comptoken_utils::{user_data::UserData, user_identity::UserIdentity, verbose_msg, SysvarClock};
+
+use crate::{global_data::GlobalData, mint, read_config, verify_accounts::*, ProgramResult};
+
+pub fn test_mint(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    //  accounts order:
+    //      Comptoken Mint account
+    //      Testuser Comptoken Wallet
+    //      Global Data (also Mint Authority)
+    //      Solana Token 2022
+    //  instruction data:
+    //      u64: the amount to mint
+
+    let account_info_iter = &mut accounts.iter();
+    let comptoken_mint_account = next_account_info(account_info_iter)?;
+    verbose_msg!("Comptoken Mint Key: {:?}", comptoken_mint_account.key);
+    let user_comptoken_wallet_account = next_account_info(account_info_iter)?;
+    verbose_msg!("User Comptoken Wallet Key: {:?}", user_comptoken_wallet_account.key);
+    let global_data_account = next_account_info(account_info_iter)?;
+    verbose_msg!("Global Data Key: {:?}", global_data_account.key);
+    let _solana_token_account = next_account_info(account_info_iter)?;
+    verbose_msg!("Solana Token Key: {:?}", _solana_token_account.key);
+
+    let global_data_account = verify_global_data_account(global_data_account, program_id, false);
+    let config = read_config(&global_data_account);
+    let comptoken_mint_account = verify_comptoken_mint(comptoken_mint_account, &config, true);
+    let user_comptoken_wallet_account =
+        verify_user_comptoken_wallet_account(user_comptoken_wallet_account, &config, false, true);
+
+    let amount = u64::from_le_bytes(instruction_data[0..8].try_into().expect("correct size"));
+    verbose_msg!("amount: {}", amount);
+
+    mint(&comptoken_mint_account, &global_data_account, &user_comptoken_wallet_account, amount, &config)
+}
+
+pub fn set_last_interest_payout_date(
+    program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8],
+) -> ProgramResult {
+    //  accounts order:
+    //      [w] User's Data
+    //      [] User's Comptoken Wallet
+    //      [] Comptoken Global Data
+    //  instruction data:
+    //      i64: the last interest payout date, e.g. 200 days ago to test a user who hasn't claimed since
+
+    let account_info_iter = &mut accounts.iter();
+    let user_data_account = next_account_info(account_info_iter)?;
+    let user_comptoken_wallet_account = next_account_info(account_info_iter)?;
+    let global_data_account = next_account_info(account_info_iter)?;
+
+    let global_data_account = verify_global_data_account(global_data_account, program_id, false);
+    let config = read_config(&global_data_account);
+    let user_comptoken_wallet_account =
+        verify_user_comptoken_wallet_account(user_comptoken_wallet_account, &config, false, false);
+    let user_data_account =
+        verify_user_data_account(user_data_account, &user_comptoken_wallet_account, program_id, true);
+
+    let date = i64::from_le_bytes(instruction_data[0..8].try_into().expect("correct size"));
+    verbose_msg!("last interest payout date: {}", date);
+    let user_data: &mut UserData = (&user_data_account).into();
+    user_data.last_interest_payout_date = date;
+
+    Ok(())
+}
+
+pub fn set_verified_human(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    //  accounts order:
+    //      [w] User's Identity
+    //      [] Comptoken Global Data
+    //  instruction data:
+    //      u8: 1 if the owner is a verified human, 0 if not
+
+    let account_info_iter = &mut accounts.iter();
+    let user_identity_account = next_account_info(account_info_iter)?;
+    let global_data_account = next_account_info(account_info_iter)?;
+
+    let global_data_account = verify_global_data_account(global_data_account, program_id, false);
+    let config = read_config(&global_data_account);
+    let owner = {
+        let data = user_identity_account.try_borrow_data().expect("account data is not borrowed");
+        let user_identity: &UserIdentity = data.as_ref().into();
+        user_identity.owner
+    };
+    let user_identity_account = verify_user_identity_account(user_identity_account, &owner, &config, program_id, true);
+
+    let is_verified_human = match instruction_data[0] {
+        0 => false,
+        1 => true,
+        _ => panic!("is_verified_human must be 0 or 1"),
+    };
+    verbose_msg!("is verified human: {}", is_verified_human);
+    let user_identity: &mut UserIdentity = (&user_identity_account).into();
+    user_identity.is_verified_human = is_verified_human;
+
+    Ok(())
+}
+
+pub fn inject_historic_interests(
+    program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8],
+) -> ProgramResult {
+    //  accounts order:
+    //      [w] Comptoken Global Data
+    //  instruction data:
+    //      f64s: interests to record, oldest first, as if each had been paid by a distribution
+
+    let account_info_iter = &mut accounts.iter();
+    let global_data_account = next_account_info(account_info_iter)?;
+
+    let global_data_account = verify_global_data_account(global_data_account, program_id, true);
+    let global_data: &mut GlobalData = (&global_data_account).into();
+
+    assert_eq!(instruction_data.len() % 8, 0, "interests are 8 bytes each");
+    for interest in instruction_data.chunks_exact(8) {
+        let interest = f64::from_le_bytes(interest.try_into().unwrap());
+        verbose_msg!("interest: {}", interest);
+        global_data.daily_distribution_data.insert(interest);
+    }
+
+    Ok(())
+}
+
+pub fn force_blockhash_rotation(
+    program_id: &Pubkey, accounts: &[AccountInfo], _instruction_data: &[u8],
+) -> ProgramResult {
+    //  accounts order:
+    //      [w] Comptoken Global Data
+    //      [] Solana SlotHashes Sysvar
+
+    let account_info_iter = &mut accounts.iter();
+    let global_data_account = next_account_info(account_info_iter)?;
+    let slot_hashes_account = next_account_info(account_info_iter)?;
+
+    let global_data_account = verify_global_data_account(global_data_account, program_id, true);
+    let slot_hashes_account = verify_slothashes_account(slot_hashes_account);
+    let global_data: &mut GlobalData = (&global_data_account).into();
+
+    global_data
+        .valid_blockhashes
+        .force_rotation(&slot_hashes_account, &global_data.config.days, &SysvarClock);
+
+    Ok(())
+}
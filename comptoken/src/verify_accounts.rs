@@ -1,8 +1,13 @@
-use spl_token_2022::solana_program::{account_info::AccountInfo, pubkey::Pubkey};
+use spl_token_2022::{
+    extension::StateWithExtensions,
+    solana_program::{account_info::AccountInfo, pubkey::Pubkey, system_program},
+    state::Account,
+};
 
-use crate::generated::{
-    COMPTOKEN_MINT_ADDRESS, COMPTO_GLOBAL_DATA_ACCOUNT_SEEDS, COMPTO_INTEREST_BANK_ACCOUNT_SEEDS,
-    COMPTO_UBI_BANK_ACCOUNT_SEEDS, TRANSFER_HOOK_ID,
+use comptoken_utils::{
+    config::{Config, EXTRA_ACCOUNT_METAS_SEED, GLOBAL_DATA_SEED, INTEREST_BANK_SEED, UBI_BANK_SEED},
+    user_data::UserData,
+    user_identity::UserIdentity,
 };
 
 pub use comptoken_utils::verify_accounts::VerifiedAccountInfo;
@@ -11,78 +16,194 @@ pub fn verify_payer_account<'a>(account: &AccountInfo<'a>) -> VerifiedAccountInf
     VerifiedAccountInfo::verify_account_signer_or_writable(account, true, true)
 }
 
-pub fn verify_comptoken_mint<'a>(account: &AccountInfo<'a>, needs_writable: bool) -> VerifiedAccountInfo<'a> {
-    VerifiedAccountInfo::verify_specific_address(account, &COMPTOKEN_MINT_ADDRESS, false, needs_writable)
+pub fn verify_admin_account<'a>(account: &AccountInfo<'a>, admin: &Pubkey) -> VerifiedAccountInfo<'a> {
+    // the admin also pays for any extra space the mint needs
+    VerifiedAccountInfo::verify_specific_address(account, admin, true, true)
+}
+
+pub fn verify_system_program<'a>(account: &AccountInfo<'a>) -> VerifiedAccountInfo<'a> {
+    VerifiedAccountInfo::verify_specific_address(account, &system_program::ID, false, false)
+}
+
+pub fn verify_new_comptoken_mint<'a>(account: &AccountInfo<'a>) -> VerifiedAccountInfo<'a> {
+    // a fresh keypair, which keys every other account of the instance
+    VerifiedAccountInfo::verify_account_signer_or_writable(account, true, true)
+}
+
+pub fn verify_comptoken_mint<'a>(
+    account: &AccountInfo<'a>, config: &Config, needs_writable: bool,
+) -> VerifiedAccountInfo<'a> {
+    VerifiedAccountInfo::verify_specific_address(account, &config.mint, false, needs_writable)
+}
+
+pub fn verify_new_global_data_account<'a>(
+    account: &AccountInfo<'a>, mint: &VerifiedAccountInfo, program_id: &Pubkey,
+) -> (VerifiedAccountInfo<'a>, u8) {
+    VerifiedAccountInfo::verify_pda(account, program_id, &[GLOBAL_DATA_SEED, mint.key.as_ref()], false, true)
 }
 
 pub fn verify_global_data_account<'a>(
     account: &AccountInfo<'a>, program_id: &Pubkey, needs_writable: bool,
 ) -> VerifiedAccountInfo<'a> {
-    VerifiedAccountInfo::verify_pda_with_bump(
-        account,
-        program_id,
-        COMPTO_GLOBAL_DATA_ACCOUNT_SEEDS,
-        false,
-        needs_writable,
-    )
+    // the global data is the PDA of the mint it stores, so it vouches for the rest of its config
+    assert_eq!(account.owner, program_id, "comptoken must be initialized first");
+    let config = {
+        let data = account.try_borrow_data().expect("account data is not borrowed");
+        let config: &Config = data.as_ref().into();
+        *config
+    };
+    VerifiedAccountInfo::verify_pda_with_bump(account, program_id, &config.global_data_seeds(), false, needs_writable)
+}
+
+pub fn verify_new_interest_bank_account<'a>(
+    account: &AccountInfo<'a>, mint: &VerifiedAccountInfo, program_id: &Pubkey,
+) -> (VerifiedAccountInfo<'a>, u8) {
+    VerifiedAccountInfo::verify_pda(account, program_id, &[INTEREST_BANK_SEED, mint.key.as_ref()], false, true)
 }
 
 pub fn verify_interest_bank_account<'a>(
-    account: &AccountInfo<'a>, program_id: &Pubkey, needs_writable: bool,
+    account: &AccountInfo<'a>, config: &Config, needs_writable: bool,
 ) -> VerifiedAccountInfo<'a> {
-    VerifiedAccountInfo::verify_pda_with_bump(
-        account,
-        program_id,
-        COMPTO_INTEREST_BANK_ACCOUNT_SEEDS,
-        false,
-        needs_writable,
-    )
+    VerifiedAccountInfo::verify_specific_address(account, &config.interest_bank, false, needs_writable)
+}
+
+pub fn verify_new_ubi_bank_account<'a>(
+    account: &AccountInfo<'a>, mint: &VerifiedAccountInfo, program_id: &Pubkey,
+) -> (VerifiedAccountInfo<'a>, u8) {
+    VerifiedAccountInfo::verify_pda(account, program_id, &[UBI_BANK_SEED, mint.key.as_ref()], false, true)
 }
 
 pub fn verify_ubi_bank_account<'a>(
-    account: &AccountInfo<'a>, program_id: &Pubkey, needs_writable: bool,
+    account: &AccountInfo<'a>, config: &Config, needs_writable: bool,
 ) -> VerifiedAccountInfo<'a> {
-    VerifiedAccountInfo::verify_pda_with_bump(account, program_id, COMPTO_UBI_BANK_ACCOUNT_SEEDS, false, needs_writable)
+    VerifiedAccountInfo::verify_specific_address(account, &config.ubi_bank, false, needs_writable)
 }
 
 pub fn verify_user_comptoken_wallet_account<'a>(
-    account: &AccountInfo<'a>, needs_signer: bool, needs_writable: bool,
+    account: &AccountInfo<'a>, config: &Config, needs_signer: bool, needs_writable: bool,
 ) -> VerifiedAccountInfo<'a> {
-    // TODO: verify comptoken user wallet accounts
+    assert_eq!(*account.owner, spl_token_2022::ID, "comptoken wallets are token 2022 accounts");
+    let data = account.try_borrow_data().expect("account data is not borrowed");
+    let wallet = StateWithExtensions::<Account>::unpack(&data).expect("valid token account");
+    assert_eq!(wallet.base.mint, config.mint, "wallet holds comptokens");
     VerifiedAccountInfo::verify_account_signer_or_writable(account, needs_signer, needs_writable)
 }
 
-pub fn verify_user_data_account<'a>(
+pub fn verify_user_comptoken_wallet_owner<'a>(
+    account: &AccountInfo<'a>, user_comptoken_wallet_account: &VerifiedAccountInfo,
+) -> VerifiedAccountInfo<'a> {
+    let data = user_comptoken_wallet_account.try_borrow_data().expect("account data is not borrowed");
+    let wallet = StateWithExtensions::<Account>::unpack(&data).expect("verified comptoken wallet");
+    // the owner also pays for any accounts created for the wallet
+    VerifiedAccountInfo::verify_specific_address(account, &wallet.base.owner, true, true)
+}
+
+pub fn verify_new_user_data_account<'a>(
     user_data_account: &AccountInfo<'a>, user_comptoken_wallet_account: &VerifiedAccountInfo, program_id: &Pubkey,
-    needs_writable: bool,
 ) -> (VerifiedAccountInfo<'a>, u8) {
     VerifiedAccountInfo::verify_pda(
         user_data_account,
         program_id,
         &[user_comptoken_wallet_account.key.as_ref()],
         false,
+        true,
+    )
+}
+
+pub fn verify_user_data_account<'a>(
+    user_data_account: &AccountInfo<'a>, user_comptoken_wallet_account: &VerifiedAccountInfo, program_id: &Pubkey,
+    needs_writable: bool,
+) -> VerifiedAccountInfo<'a> {
+    // the bump stored at creation avoids searching for it on every proof and claim
+    assert_eq!(user_data_account.owner, program_id, "user data must be created first");
+    let bump = {
+        let data = user_data_account.try_borrow_data().expect("account data is not borrowed");
+        let user_data: &UserData = data.as_ref().try_into().expect("valid user data");
+        user_data.bump
+    };
+    VerifiedAccountInfo::verify_pda_with_bump(
+        user_data_account,
+        program_id,
+        &[user_comptoken_wallet_account.key.as_ref(), &[bump]],
+        false,
         needs_writable,
     )
 }
 
+pub fn verify_new_user_identity_account<'a>(
+    account: &AccountInfo<'a>, owner: &Pubkey, config: &Config, program_id: &Pubkey,
+) -> (VerifiedAccountInfo<'a>, u8) {
+    // identities are per instance, so UBI claimed from one instance doesn't count against another
+    VerifiedAccountInfo::verify_pda(
+        account,
+        program_id,
+        &[b"User Identity", config.mint.as_ref(), owner.as_ref()],
+        false,
+        true,
+    )
+}
+
+pub fn verify_user_identity_account<'a>(
+    account: &AccountInfo<'a>, owner: &Pubkey, config: &Config, program_id: &Pubkey, needs_writable: bool,
+) -> VerifiedAccountInfo<'a> {
+    assert_eq!(account.owner, program_id, "user identity must be created first");
+    let bump = {
+        let data = account.try_borrow_data().expect("account data is not borrowed");
+        let user_identity: &UserIdentity = data.as_ref().into();
+        user_identity.bump
+    };
+    VerifiedAccountInfo::verify_pda_with_bump(
+        account,
+        program_id,
+        &[b"User Identity", config.mint.as_ref(), owner.as_ref(), &[bump]],
+        false,
+        needs_writable,
+    )
+}
+
+pub fn verify_bank_data_pda<'a>(
+    account: &AccountInfo<'a>, seeds: &[&[u8]], program_id: &Pubkey,
+) -> VerifiedAccountInfo<'a> {
+    // the transfer hook requires a "user data" account for the banks, but they don't actually exist. the hook marks
+    // its user data accounts writable, so these must be too
+    VerifiedAccountInfo::verify_pda_with_bump(account, program_id, seeds, false, true)
+}
+
 pub fn verify_slothashes_account<'a>(account: &AccountInfo<'a>) -> VerifiedAccountInfo<'a> {
     assert!(solana_program::sysvar::slot_hashes::check_id(account.key));
     VerifiedAccountInfo::verify_sysvar::<solana_program::sysvar::slot_hashes::SlotHashes>(account)
 }
 
-pub fn verify_validation_account<'a>(
-    account: &AccountInfo<'a>, mint: &VerifiedAccountInfo<'a>, transfer_hook_program: &VerifiedAccountInfo<'a>,
-) -> VerifiedAccountInfo<'a> {
+pub fn verify_new_validation_account<'a>(
+    account: &AccountInfo<'a>, transfer_hook_program: &VerifiedAccountInfo<'a>, mint: &VerifiedAccountInfo,
+) -> (VerifiedAccountInfo<'a>, u8) {
     VerifiedAccountInfo::verify_pda(
         account,
         transfer_hook_program.key,
-        &[b"extra-account-metas", mint.key.as_ref()],
+        &[EXTRA_ACCOUNT_METAS_SEED, mint.key.as_ref()],
         false,
+        true,
+    )
+}
+
+pub fn verify_validation_account<'a>(
+    account: &AccountInfo<'a>, transfer_hook_program: &VerifiedAccountInfo<'a>, config: &Config, needs_writable: bool,
+) -> VerifiedAccountInfo<'a> {
+    VerifiedAccountInfo::verify_pda_with_bump(
+        account,
+        transfer_hook_program.key,
+        &config.extra_account_metas_seeds(),
         false,
+        needs_writable,
     )
-    .0
 }
 
-pub fn verify_transfer_hook_program<'a>(account: &AccountInfo<'a>) -> VerifiedAccountInfo<'a> {
-    VerifiedAccountInfo::verify_specific_address(account, &TRANSFER_HOOK_ID, false, false)
+pub fn verify_new_transfer_hook_program<'a>(account: &AccountInfo<'a>) -> VerifiedAccountInfo<'a> {
+    // any transfer hook program can be chosen when the instance is initialized
+    assert!(account.executable, "the transfer hook must be a program");
+    VerifiedAccountInfo::verify_account_signer_or_writable(account, false, false)
+}
+
+pub fn verify_transfer_hook_program<'a>(account: &AccountInfo<'a>, config: &Config) -> VerifiedAccountInfo<'a> {
+    VerifiedAccountInfo::verify_specific_address(account, &config.transfer_hook_program, false, false)
 }
@@ -12,16 +12,12 @@ PROJECT_PATH = TEST_PATH.parent
 COMPTOKEN_SRC_PATH = PROJECT_PATH / "comptoken"
 TRANSFER_HOOK_SRC_PATH = PROJECT_PATH / "comptoken-transfer-hook"
 CACHE_PATH = TEST_PATH / ".cache"
-COMPTOKEN_GENERATED_PATH = COMPTOKEN_SRC_PATH / "src/generated"
-TRANSFER_HOOK_GENERATED_PATH = TRANSFER_HOOK_SRC_PATH / "src/generated"
-
-COMPTO_GENERATED_RS_FILE = COMPTOKEN_GENERATED_PATH / "comptoken_generated.rs"
-TRANSFER_HOOK_GENERATED_RS_FILE = TRANSFER_HOOK_GENERATED_PATH / "comptoken_generated.rs"
 COMPTO_SO = PROJECT_PATH / "target/deploy/comptoken.so"
 
 COMPTO_PROGRAM_ID_JSON = CACHE_PATH / "compto_program_id.json"
 COMPTO_TRANSFER_HOOK_ID_JSON = CACHE_PATH / "compto_transfer_hook_id.json"
 COMPTOKEN_MINT_JSON = CACHE_PATH / "comptoken_mint.json"
+COMPTOKEN_MINT_KEYPAIR_JSON = CACHE_PATH / "comptoken_mint_keypair.json"
 TEST_USER_ACCOUNT_JSON = CACHE_PATH / "test_user_account.json"
 COMPTO_GLOBAL_DATA_ACCOUNT_JSON = CACHE_PATH / "compto_global_data_account.json"
 COMPTO_INTEREST_BANK_ACCOUNT_JSON = CACHE_PATH / "compto_interest_bank_account.json"
@@ -100,79 +96,29 @@ def write(path: Path, data: str):
     with open(path, "w") as file:
         file.write(data)
 
-def generateComptokenAddressFile(
-    globalDataSeed: int, interestBankSeed: int, UBIBankSeed: int, mint_address: str, transfer_hook_address: str
-):
-    print(f"Generating {COMPTO_GENERATED_RS_FILE}...")
-    file_data = f"""\
-// AUTOGENERATED DO NOT TOUCH
-// generated by test/common.py
-
-// A given seed and program id have a 50% chance of creating a valid PDA.
-// Before building/deploying, we find the canonical seed by running 
-//      `solana find-program-derived-address <program_id>`
-// This is an efficiency optimization. We are using a static seed to create the PDA with no bump.
-// We ensure when deploying that the program id is one that only needs the seed above and no bump.
-// This is because 
-//      (1) create_program_address is not safe if using a user provided bump.
-//      (2) find_program_address is expensive and we want to avoid iterations.
-
-use spl_token_2022::solana_program::{{pubkey, pubkey::Pubkey}};
-
-pub const COMPTOKEN_MINT_ADDRESS: Pubkey = pubkey!("{mint_address}");
-
-pub const TRANSFER_HOOK_ID: Pubkey = pubkey!("{transfer_hook_address}");
-
-pub const COMPTO_GLOBAL_DATA_ACCOUNT_BUMP: u8 = {globalDataSeed};
-pub const COMPTO_INTEREST_BANK_ACCOUNT_BUMP: u8 = {interestBankSeed};
-pub const COMPTO_UBI_BANK_ACCOUNT_BUMP: u8 = {UBIBankSeed};\
-"""
-    write(COMPTO_GENERATED_RS_FILE, file_data)
-
-def generateTransferHookAddressFile(
-    comptoken_address: str, extraAccountMetasSeed: int, mint_address: str, interest_address: str, ubi_address: str
-):
-    print(f"Generating {TRANSFER_HOOK_GENERATED_RS_FILE}...")
-    file_data = f"""\
-// AUTOGENERATED DO NOT TOUCH
-// generated by test/common.py
-
-// A given seed and program id have a 50% chance of creating a valid PDA.
-// Before building/deploying, we find the canonical seed by running 
-//      `solana find-program-derived-address <program_id>`
-// This is an efficiency optimization. We are using a static seed to create the PDA with no bump.
-// We ensure when deploying that the program id is one that only needs the seed above and no bump.
-// This is because 
-//      (1) create_program_address is not safe if using a user provided bump.
-//      (2) find_program_address is expensive and we want to avoid iterations.
-
-use spl_token_2022::solana_program::{{pubkey, pubkey::Pubkey}};
-
-pub const COMPTOKEN_ID: Pubkey = pubkey!("{comptoken_address}");
-
-pub const EXTRA_ACCOUNT_METAS_BUMP: u8 = {extraAccountMetasSeed};\
-pub const MINT_ADDRESS: Pubkey = pubkey!("{mint_address}");
-
-pub const COMPTO_INTEREST_BANK_ACCOUNT_PUBKEY: Pubkey = pubkey!("{interest_address}");
-pub const COMPTO_UBI_BANK_ACCOUNT_PUBKEY: Pubkey = pubkey!("{ubi_address}");
-"""
-    write(TRANSFER_HOOK_GENERATED_RS_FILE, file_data)
-
-def setGlobalDataPDA(programId: str) -> PDA:
-    pda = PDA(programId, "Global Data")
+# every comptoken instance is keyed by its mint, so its PDAs are derived from the mint's address
+def setGlobalDataPDA(programId: str, mint_pubkey: Pubkey) -> PDA:
+    pda = PDA(programId, "Global Data", mint_pubkey)
     write(COMPTO_GLOBAL_DATA_ACCOUNT_JSON, json.dumps(pda))
     return pda
 
-def setInterestBankPDA(programId: str) -> PDA:
-    pda = PDA(programId, "Interest Bank")
+def setInterestBankPDA(programId: str, mint_pubkey: Pubkey) -> PDA:
+    pda = PDA(programId, "Interest Bank", mint_pubkey)
     write(COMPTO_INTEREST_BANK_ACCOUNT_JSON, json.dumps(pda))
     return pda
 
-def setUBIBankPDA(programId: str) -> PDA:
-    pda = PDA(programId, "UBI Bank")
+def setUBIBankPDA(programId: str, mint_pubkey: Pubkey) -> PDA:
+    pda = PDA(programId, "UBI Bank", mint_pubkey)
     write(COMPTO_UBI_BANK_ACCOUNT_JSON, json.dumps(pda))
     return pda
 
+def setMint() -> Pubkey:
+    # the mint is a new keypair, it signs when the comptoken program is initialized
+    createKeyPair(COMPTOKEN_MINT_KEYPAIR_JSON)
+    address = run(f"solana-keygen pubkey {COMPTOKEN_MINT_KEYPAIR_JSON}")
+    write(COMPTOKEN_MINT_JSON, json.dumps({"address": address}))
+    return Pubkey(address)
+
 def setExtraAccountMetasPDA(programId: str, mint_pubkey: Pubkey) -> PDA:
     pda = PDA(programId, "extra-account-metas", mint_pubkey)
     write(EXTRA_ACCOUNT_METAS_ACCOUNT_JSON, json.dumps(pda))
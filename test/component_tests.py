@@ -4,38 +4,29 @@ from argparse import ArgumentParser, Namespace
 
 from common import *
 
+# the compute units each test's transactions used when last recorded, checked to catch regressions. it is committed, and
+# only rewritten when run with --record-compute-units
+COMPUTE_UNITS_JSON = TEST_PATH / "compute_units.json"
+
 
 def generateFiles():
     print("generating files...")
     # create cache if it doesn't exist
     run(f"[ -d {CACHE_PATH} ] || mkdir {CACHE_PATH} ")
-    run(f"[ -d {COMPTOKEN_GENERATED_PATH} ] || mkdir {COMPTOKEN_GENERATED_PATH} ")
-    run(f"[ -d {TRANSFER_HOOK_GENERATED_PATH} ] || mkdir {TRANSFER_HOOK_GENERATED_PATH} ")
     # programId
     comptokenProgramId = randAddress()
     transferHookId = randAddress()
     generateMockComptokenProgramIdFile(comptokenProgramId)
     generateMockTransferHookProgramIdFile(transferHookId)
     # mint
-    mint_address = generateMockMint()
+    mint = setMint()
     # pdas
-    globalDataSeed = setGlobalDataPDA(comptokenProgramId)["bumpSeed"]
-
-    interestBankPDA = setInterestBankPDA(comptokenProgramId)
-    interestBankSeed = interestBankPDA["bumpSeed"]
-    interestBankAddress = interestBankPDA["address"]
-    UBIBankPDA = setUBIBankPDA(comptokenProgramId)
-    UBIBankSeed = UBIBankPDA["bumpSeed"]
-    UBIBankAddress = UBIBankPDA["address"]
-
-    extraAccountMetasSeed = setExtraAccountMetasPDA(transferHookId, Pubkey(mint_address))["bumpSeed"]
+    setGlobalDataPDA(comptokenProgramId, mint)
+    setInterestBankPDA(comptokenProgramId, mint)
+    setUBIBankPDA(comptokenProgramId, mint)
+    setExtraAccountMetasPDA(transferHookId, mint)
     # test user
     generateTestUser()
-    # rust file
-    generateComptokenAddressFile(globalDataSeed, interestBankSeed, UBIBankSeed, mint_address, transferHookId)
-    generateTransferHookAddressFile(
-        comptokenProgramId, extraAccountMetasSeed, mint_address, interestBankAddress, UBIBankAddress
-    )
     print("done generating files")
 
 def generateMockComptokenProgramIdFile(programId: str):
@@ -44,24 +35,12 @@ def generateMockComptokenProgramIdFile(programId: str):
 def generateMockTransferHookProgramIdFile(programId: str):
     write(COMPTO_TRANSFER_HOOK_ID_JSON, json.dumps({"programId": programId}))
 
-def generateMockMint() -> str:
-    address = randAddress()
-    file_data = f'''\
-{{
-    "commandName": "CreateToken",
-    "commandOutput": {{
-        "address": "{address}",
-        "decimals": {MINT_DECIMALS},
-        "transactionData": {{
-            "signature": ""
-        }}
-    }}
-}}\
-'''
-    write(COMPTOKEN_MINT_JSON, file_data)
-    return address
-
-def runTest(args: Namespace, file: str) -> bool:
+def parseComputeUnits(stdout: str) -> list[int]:
+    # every test logs the compute units of the transactions it checks as `computeUnitsConsumed: <n>`
+    prefix = "computeUnitsConsumed:"
+    return [int(line[len(prefix):]) for line in stdout.splitlines() if line.startswith(prefix)]
+
+def runTest(args: Namespace, file: str) -> list[int] | None:
     print(f"running {file}")
     env = os.environ
     env["SBF_OUT_DIR"] = str(PROJECT_PATH / "target/deploy/")
@@ -71,36 +50,79 @@ def runTest(args: Namespace, file: str) -> bool:
         if args.verbose >= 1:
             print(stdout)
         print(f"✅ \033[92m{file}\033[0m passed")
-        return True
+        return parseComputeUnits(stdout)
     except SubprocessFailedException as e:
         print(f"❌ \033[91m{file}\033[0m failed")
         print(e)
+        return None
+
+def checkComputeUnits(args: Namespace, file: str, computeUnits: list[int], baseline: dict[str, list[int]]) -> bool:
+    if args.record_compute_units:
+        print(f"recording compute units for {file}: {computeUnits}")
+        baseline[file] = computeUnits
+        return True
+    expected = baseline.get(file)
+    if expected is None or len(expected) != len(computeUnits):
+        print(f"❌ \033[91m{file}\033[0m measured {computeUnits} compute units, but the baseline has {expected}")
+        print("    rerun with --record-compute-units if the change is intended")
         return False
+    passed = True
+    for used, recorded in zip(computeUnits, expected):
+        # improvements are failures too, so the baseline stays tight enough to catch the next regression
+        if abs(used - recorded) > recorded * args.compute_unit_threshold:
+            print(f"❌ \033[91m{file}\033[0m used {used} compute units, but the baseline has {recorded}")
+            passed = False
+    if not passed:
+        print("    rerun with --record-compute-units if the change is intended")
+    return passed
 
 def runTests(args: Namespace, tests: list[str]):
     print("running tests...")
 
+    if not COMPUTE_UNITS_JSON.exists() and not args.record_compute_units:
+        print(f"❌ \033[91m{COMPUTE_UNITS_JSON}\033[0m is missing, record it with --record-compute-units")
+        raise SystemExit(1)
+    baseline: dict[str, list[int]] = json.loads(COMPUTE_UNITS_JSON.read_text()) if COMPUTE_UNITS_JSON.exists() else {}
     passed = 0
     for test in tests:
-        passed += runTest(args, test)
+        computeUnits = runTest(args, test)
+        passed += computeUnits is not None and checkComputeUnits(args, test, computeUnits, baseline)
     failed = len(tests) - passed
+    if args.record_compute_units:
+        write(COMPUTE_UNITS_JSON, json.dumps(baseline, indent=4, sort_keys=True) + "\n")
     print()
     print(f"passed: {passed}    failed: {failed}")
+    if failed:
+        raise SystemExit(1)
 
 def parseArgs():
     parser = ArgumentParser(prog="comptoken component tests")
     parser.add_argument("--verbose", "-v", action="count", default=0)
     parser.add_argument("--no-build", action="store_false", dest="build")
+    parser.add_argument(
+        "--record-compute-units",
+        action="store_true",
+        help="overwrite the compute unit baseline instead of checking against it",
+    )
+    parser.add_argument(
+        "--compute-unit-threshold",
+        type=float,
+        default=0.05,
+        help="the fraction compute units may differ from the baseline before a test fails",
+    )
 
     return parser.parse_args()
 
 if __name__ == "__main__":
     comptoken_tests: list[str] = [
-        "mint", "initializeComptokenProgram", "createUserDataAccount", "proofSubmission", "getValidBlockhashes",
-        "getOwedComptokens", "dailyDistributionEvent"
+        "mint", "initializeComptokenProgram", "createUserIdentity", "createUserDataAccount", "proofSubmission", "getValidBlockhashes",
+        "getOwedComptokens", "dailyDistributionEvent", "dailyDistributionCatchUp",
+        "getDistributionStatus", "proofGracePeriod", "updateComptokenMetadata", "setProofScheme", "proofSchemes", "proofVersions",
+        "audit", "previewOwedComptokens", "proofSubmissionSettlement", "testmode",
+        "queryGlobalData"
     ]
     transfer_hook_tests: list[str] = [
-        "initialize_extra_account_meta_list", "execute"
+        "initialize_extra_account_meta_list", "execute", "executeDormantWallets"
     ]
 
     tests = list(map(lambda test: "comptoken-tests/" + test, comptoken_tests)
@@ -2,8 +2,9 @@ import { ACCOUNT_SIZE, AccountLayout, AccountState, ExtraAccountMetaLayout, MINT
 import { PublicKey } from "@solana/web3.js";
 
 import {
-    compto_extra_account_metas_account_pubkey, compto_program_id_pubkey, compto_transfer_hook_id_pubkey, comptoken_mint_pubkey, DEFAULT_ANNOUNCE_TIME,
-    DEFAULT_DISTRIBUTION_TIME, global_data_account_pubkey, Instruction, interest_bank_account_pubkey, ubi_bank_account_pubkey,
+    compto_extra_account_metas_account_bump, compto_extra_account_metas_account_pubkey, compto_program_id_pubkey, compto_transfer_hook_id_pubkey, comptoken_mint_pubkey,
+    DEFAULT_ANNOUNCE_TIME, DEFAULT_BLOCKHASH_GRACE_PERIOD, DEFAULT_DAY_LENGTH, DEFAULT_DAY_OFFSET, DEFAULT_DISTRIBUTION_BOUNTY, DEFAULT_DISTRIBUTION_TIME, DEFAULT_V1_PROOF_DEADLINE, global_data_account_bump,
+    global_data_account_pubkey, Instruction, interest_bank_account_bump, interest_bank_account_pubkey, ProofScheme, ubi_bank_account_bump, ubi_bank_account_pubkey,
 } from "./common.js";
 
 export const BIG_NUMBER = 1_000_000_000;
@@ -40,7 +41,16 @@ export function numAsU16ToLEBytes(num) {
 export function numAsU32ToLEBytes(num) {
     let buffer = Buffer.alloc(4);
     buffer.writeUInt32LE(num);
-    return Array.from({ length: 2 }, (v, i) => buffer.readUint8(i));
+    return Array.from({ length: 4 }, (v, i) => buffer.readUint8(i));
+}
+
+/**
+ * @param {string} str
+ * @returns {number[]} the u32 length followed by the utf-8 bytes, the same as borsh
+ */
+export function stringAsLEBytes(str) {
+    const bytes = Buffer.from(str, "utf-8");
+    return [...numAsU32ToLEBytes(bytes.length), ...bytes];
 }
 
 /**
@@ -138,7 +148,7 @@ export function isArrayEqual(left, right) {
 
 // =============================== Classes ===============================
 
-class ExtensionType {
+export class ExtensionType {
     // u16 discriminated type for an extension
     // https://github.com/solana-labs/solana-program-library/blob/master/token/program-2022/src/extension/mod.rs#L1042-L1115
     static Uninitialized = 0;
@@ -206,6 +216,26 @@ export class TLV {
         return new TLV(ExtensionType.TransferHookAccount, 1, value);
     }
 
+    /**
+     * @param {PublicKey} metadataAddress
+     * @param {PublicKey | null} authority
+     * @returns {TLV}
+     */
+    static metadataPointer(metadataAddress, authority = null) {
+        authority = getOptionOr(toOption(authority), () => PublicKey.default).val;
+        let value = Uint8Array.from([...authority.toBytes(), ...metadataAddress.toBytes()]);
+        return new TLV(ExtensionType.MetadataPointer, 64, value);
+    }
+
+    /**
+     * @param {TokenMetadata} metadata
+     * @returns {TLV}
+     */
+    static tokenMetadata(metadata) {
+        let value = metadata.toBytes();
+        return new TLV(ExtensionType.TokenMetadata, value.length, value);
+    }
+
     /**
      * @returns {Uint8Array}
      */
@@ -372,21 +402,225 @@ export class MintAccount extends AccountWithExtensions {
         return mintAccount;
     }
 }
+export class TokenMetadata {
+    // structure derived from
+    // https://github.com/solana-labs/solana-program-library/blob/master/token-metadata/interface/src/state.rs
+    updateAuthority; //  optional PublicKey
+    mint; //  PublicKey
+    name; //  string
+    symbol; //  string
+    uri; //  string
+    additionalMetadata; //  [[string, string]]
+
+    /**
+     * @param {PublicKey | null} updateAuthority
+     * @param {PublicKey} mint
+     * @param {string} name
+     * @param {string} symbol
+     * @param {string} uri
+     * @param {[string, string][]} additionalMetadata
+     */
+    constructor(updateAuthority, mint, name, symbol, uri, additionalMetadata = []) {
+        this.updateAuthority = toOption(updateAuthority);
+        this.mint = mint;
+        this.name = name;
+        this.symbol = symbol;
+        this.uri = uri;
+        this.additionalMetadata = additionalMetadata;
+    }
+
+    /**
+     * @returns {Uint8Array}
+     */
+    toBytes() {
+        const updateAuthority = getOptionOr(this.updateAuthority, () => PublicKey.default).val;
+        return Uint8Array.from([
+            ...updateAuthority.toBytes(),
+            ...this.mint.toBytes(),
+            ...stringAsLEBytes(this.name),
+            ...stringAsLEBytes(this.symbol),
+            ...stringAsLEBytes(this.uri),
+            ...numAsU32ToLEBytes(this.additionalMetadata.length),
+            ...this.additionalMetadata.flatMap(([key, value]) => [...stringAsLEBytes(key), ...stringAsLEBytes(value)]),
+        ]);
+    }
+
+    /**
+     * @param {Uint8Array} bytes
+     * @returns {TokenMetadata}
+     */
+    static fromBytes(bytes) {
+        const dataView = new DataView(bytes.buffer.slice(bytes.byteOffset));
+        let index = 64;
+        const decodeString = () => {
+            const length = dataView.getUint32(index, true);
+            index += 4;
+            const str = Buffer.from(bytes.subarray(index, index + length)).toString("utf-8");
+            index += length;
+            return str;
+        };
+        const updateAuthority = new PublicKey(bytes.subarray(0, 32));
+        const mint = new PublicKey(bytes.subarray(32, 64));
+        const name = decodeString();
+        const symbol = decodeString();
+        const uri = decodeString();
+        const additionalMetadataLength = dataView.getUint32(index, true);
+        index += 4;
+        let additionalMetadata = [];
+        for (let i = 0; i < additionalMetadataLength; ++i) {
+            additionalMetadata.push([decodeString(), decodeString()]);
+        }
+        return new TokenMetadata(
+            updateAuthority.equals(PublicKey.default) ? null : updateAuthority, mint, name, symbol, uri, additionalMetadata
+        );
+    }
+}
+
+/**
+ * @typedef {Object} Bumps
+ * @property {number} globalData
+ * @property {number} interestBank
+ * @property {number} ubiBank
+ * @property {number} interestBankData
+ * @property {number} ubiBankData
+ * @property {number} extraAccountMetas
+ */
+
+/**
+ * @typedef {Object} Instance the accounts making up a comptoken instance
+ * @property {PublicKey} mint
+ * @property {PublicKey} transferHookProgram
+ * @property {PublicKey} interestBank
+ * @property {PublicKey} ubiBank
+ * @property {Bumps} bumps
+ */
+
+export class Config {
+    distributionBounty; //  u64
+    blockhashGracePeriod; //  i64
+    admin; //  PublicKey
+    v1ProofDeadline; //  i64
+    days; //  { length: i64, offset: i64 }
+    instance; //  Instance
+
+    static SIZE = 208; //   remain consistent with rust
+
+    /**
+     * @param {bigint} distributionBounty
+     * @param {bigint} blockhashGracePeriod
+     * @param {PublicKey} admin
+     * @param {bigint} v1ProofDeadline
+     * @param {{ length: bigint, offset: bigint }} days
+     * @param {Instance} instance
+     */
+    constructor(
+        distributionBounty,
+        blockhashGracePeriod,
+        admin,
+        v1ProofDeadline,
+        days = { length: DEFAULT_DAY_LENGTH, offset: DEFAULT_DAY_OFFSET },
+        instance = get_default_instance()
+    ) {
+        this.distributionBounty = distributionBounty;
+        this.blockhashGracePeriod = blockhashGracePeriod;
+        this.admin = admin;
+        this.v1ProofDeadline = v1ProofDeadline;
+        this.days = days;
+        this.instance = instance;
+    }
+
+    /**
+     * @returns {Uint8Array}
+     */
+    toBytes() {
+        const bumps = this.instance.bumps;
+        return new Uint8Array([
+            ...bigintAsU64ToBytes(this.distributionBounty),
+            ...bigintAsU64ToBytes(this.blockhashGracePeriod),
+            ...this.admin.toBytes(),
+            ...bigintAsU64ToBytes(this.v1ProofDeadline),
+            ...bigintAsU64ToBytes(this.days.length),
+            ...bigintAsU64ToBytes(this.days.offset),
+            ...this.instance.mint.toBytes(),
+            ...this.instance.transferHookProgram.toBytes(),
+            ...this.instance.interestBank.toBytes(),
+            ...this.instance.ubiBank.toBytes(),
+            bumps.globalData,
+            bumps.interestBank,
+            bumps.ubiBank,
+            bumps.interestBankData,
+            bumps.ubiBankData,
+            bumps.extraAccountMetas,
+            0, 0, // padding
+        ]);
+    }
+
+    /**
+     * @param {Uint8Array} bytes
+     * @returns {Config}
+     */
+    static fromBytes(bytes) {
+        const dataView = new DataView(bytes.buffer.slice(bytes.byteOffset));
+        return new Config(
+            dataView.getBigUint64(0, true),
+            dataView.getBigInt64(8, true),
+            new PublicKey(bytes.subarray(16, 48)),
+            dataView.getBigInt64(48, true),
+            { length: dataView.getBigInt64(56, true), offset: dataView.getBigInt64(64, true) },
+            {
+                mint: new PublicKey(bytes.subarray(72, 104)),
+                transferHookProgram: new PublicKey(bytes.subarray(104, 136)),
+                interestBank: new PublicKey(bytes.subarray(136, 168)),
+                ubiBank: new PublicKey(bytes.subarray(168, 200)),
+                bumps: {
+                    globalData: bytes[200],
+                    interestBank: bytes[201],
+                    ubiBank: bytes[202],
+                    interestBankData: bytes[203],
+                    ubiBankData: bytes[204],
+                    extraAccountMetas: bytes[205],
+                },
+            }
+        );
+    }
+}
+
 export class ValidBlockhashes {
     announcedBlockhash; //  blockhash
     announcedBlockhashTime; //  i64
+    announcedBlockhashSlot; //  u64
     validBlockhash; //  blockhash
     validBlockhashTime; //  i64
+    previousValidBlockhash; //  blockhash
+    previousValidBlockhashTime; //  i64
+    validProofScheme; //  u8
+    previousProofScheme; //  u8
+    nextProofScheme; //  u8
+
+    static SIZE = 136; //   remain consistent with rust
 
     /**
-     * @param {{ blockhash: Uint8Array; time: bigint }} announced
+     * @param {{ blockhash: Uint8Array; time: bigint; slot?: bigint }} announced
      * @param {{ blockhash: Uint8Array; time: bigint }} valid
+     * @param {{ blockhash: Uint8Array; time: bigint }} previous
+     * @param {{ valid: number; previous: number; next: number }} proofSchemes
      */
-    constructor(announced, valid) {
+    constructor(
+        announced,
+        valid,
+        previous = { blockhash: new Uint8Array(32), time: 0n },
+        proofSchemes = { valid: ProofScheme.SHA256, previous: ProofScheme.SHA256, next: ProofScheme.SHA256 }
+    ) {
         this.announcedBlockhash = announced.blockhash;
         this.announcedBlockhashTime = announced.time;
+        this.announcedBlockhashSlot = announced.slot ?? 0n;
         this.validBlockhash = valid.blockhash;
         this.validBlockhashTime = valid.time;
+        this.previousValidBlockhash = previous.blockhash;
+        this.previousValidBlockhashTime = previous.time;
+        this.validProofScheme = proofSchemes.valid;
+        this.previousProofScheme = proofSchemes.previous;
+        this.nextProofScheme = proofSchemes.next;
     }
 
     /**
@@ -396,8 +630,15 @@ export class ValidBlockhashes {
         return new Uint8Array([
             ...this.announcedBlockhash,
             ...bigintAsU64ToBytes(this.announcedBlockhashTime),
+            ...bigintAsU64ToBytes(this.announcedBlockhashSlot),
             ...this.validBlockhash,
             ...bigintAsU64ToBytes(this.validBlockhashTime),
+            ...this.previousValidBlockhash,
+            ...bigintAsU64ToBytes(this.previousValidBlockhashTime),
+            this.validProofScheme,
+            this.previousProofScheme,
+            this.nextProofScheme,
+            ...new Uint8Array(5), // padding
         ]);
     }
 
@@ -408,8 +649,10 @@ export class ValidBlockhashes {
     static fromBytes(bytes) {
         const dataView = new DataView(bytes.buffer.slice(bytes.byteOffset));
         return new ValidBlockhashes(
-            { blockhash: bytes.subarray(0, 32), time: dataView.getBigInt64(32, true) },
-            { blockhash: bytes.subarray(40, 72), time: dataView.getBigInt64(72, true) },
+            { blockhash: bytes.subarray(0, 32), time: dataView.getBigInt64(32, true), slot: dataView.getBigUint64(40, true) },
+            { blockhash: bytes.subarray(48, 80), time: dataView.getBigInt64(80, true) },
+            { blockhash: bytes.subarray(88, 120), time: dataView.getBigInt64(120, true) },
+            { valid: bytes[128], previous: bytes[129], next: bytes[130] },
         );
     }
 }
@@ -418,22 +661,26 @@ export class DailyDistributionData {
     yesterdaySupply; //  u64
     highWaterMark; //  u64
     lastDailyDistributionTime; //  i64
+    minedSinceLastDistribution; //  u64
     oldestInterest; //  usize
     historicInterests; //  [f64; 365]
 
     static HISTORY_SIZE = 365; //   remain consistent with rust
+    static SIZE = 40 + 8 * DailyDistributionData.HISTORY_SIZE;
 
     /**
      * @param {bigint} yesterdaySupply
      * @param {bigint} highWaterMark
      * @param {bigint} lastDailyDistributionTime
+     * @param {bigint} minedSinceLastDistribution
      * @param {bigint} oldestInterest
      * @param {number[]} historicInterests
      */
-    constructor(yesterdaySupply, highWaterMark, lastDailyDistributionTime, oldestInterest, historicInterests) {
+    constructor(yesterdaySupply, highWaterMark, lastDailyDistributionTime, minedSinceLastDistribution, oldestInterest, historicInterests) {
         this.yesterdaySupply = yesterdaySupply;
         this.highWaterMark = highWaterMark;
         this.lastDailyDistributionTime = lastDailyDistributionTime;
+        this.minedSinceLastDistribution = minedSinceLastDistribution;
         this.oldestInterest = oldestInterest;
         this.historicInterests = [
             ...historicInterests.map((num) => num),
@@ -449,6 +696,7 @@ export class DailyDistributionData {
             ...bigintAsU64ToBytes(this.yesterdaySupply),
             ...bigintAsU64ToBytes(this.highWaterMark),
             ...bigintAsU64ToBytes(this.lastDailyDistributionTime),
+            ...bigintAsU64ToBytes(this.minedSinceLastDistribution),
             ...bigintAsU64ToBytes(this.oldestInterest),
             ...this.historicInterests.flatMap((num) => numAsDoubleToLEBytes(num)),
         ]);
@@ -465,7 +713,205 @@ export class DailyDistributionData {
             dataView.getBigUint64(8, true),
             dataView.getBigInt64(16, true),
             dataView.getBigUint64(24, true),
-            LEBytesToDoubleArray(bytes.subarray(32)),
+            dataView.getBigUint64(32, true),
+            LEBytesToDoubleArray(bytes.subarray(40, DailyDistributionData.SIZE)),
+        );
+    }
+}
+
+export class Ledger {
+    totalMined; //  u64
+    totalInterestDistributed; //  u64
+    totalUbiDistributed; //  u64
+    totalBountyDistributed; //  u64
+    totalInterestClaimed; //  u64
+    totalUbiClaimed; //  u64
+
+    static SIZE = 48; //   remain consistent with rust
+
+    /**
+     * @param {bigint} totalMined
+     * @param {bigint} totalInterestDistributed
+     * @param {bigint} totalUbiDistributed
+     * @param {bigint} totalBountyDistributed
+     * @param {bigint} totalInterestClaimed
+     * @param {bigint} totalUbiClaimed
+     */
+    constructor(
+        totalMined, totalInterestDistributed, totalUbiDistributed, totalBountyDistributed, totalInterestClaimed,
+        totalUbiClaimed,
+    ) {
+        this.totalMined = totalMined;
+        this.totalInterestDistributed = totalInterestDistributed;
+        this.totalUbiDistributed = totalUbiDistributed;
+        this.totalBountyDistributed = totalBountyDistributed;
+        this.totalInterestClaimed = totalInterestClaimed;
+        this.totalUbiClaimed = totalUbiClaimed;
+    }
+
+    /**
+     * @returns {Uint8Array}
+     */
+    toBytes() {
+        return new Uint8Array([
+            ...bigintAsU64ToBytes(this.totalMined),
+            ...bigintAsU64ToBytes(this.totalInterestDistributed),
+            ...bigintAsU64ToBytes(this.totalUbiDistributed),
+            ...bigintAsU64ToBytes(this.totalBountyDistributed),
+            ...bigintAsU64ToBytes(this.totalInterestClaimed),
+            ...bigintAsU64ToBytes(this.totalUbiClaimed),
+        ]);
+    }
+
+    /**
+     * @param {Uint8Array} bytes
+     * @returns {Ledger}
+     */
+    static fromBytes(bytes) {
+        let dataView = new DataView(bytes.buffer.slice(bytes.byteOffset));
+        return new Ledger(
+            dataView.getBigUint64(0, true),
+            dataView.getBigUint64(8, true),
+            dataView.getBigUint64(16, true),
+            dataView.getBigUint64(24, true),
+            dataView.getBigUint64(32, true),
+            dataView.getBigUint64(40, true),
+        );
+    }
+}
+
+export class DailyStats {
+    day; //  i64
+    proofsAccepted; //  u64
+    uniqueMiners; //  u64
+    tokensMined; //  u64
+    highWaterMark; //  u64
+    supply; //  u64
+    interestDistributed; //  u64
+    ubiDistributed; //  u64
+    validBlockhash; //  blockhash
+
+    static SIZE = 96; //   remain consistent with rust
+
+    /**
+     * @param {{ day: bigint, proofsAccepted: bigint, uniqueMiners: bigint, tokensMined: bigint, highWaterMark: bigint,
+     *           supply: bigint, interestDistributed: bigint, ubiDistributed: bigint, validBlockhash: Uint8Array }} stats
+     */
+    constructor(stats) {
+        this.day = stats.day;
+        this.proofsAccepted = stats.proofsAccepted;
+        this.uniqueMiners = stats.uniqueMiners;
+        this.tokensMined = stats.tokensMined;
+        this.highWaterMark = stats.highWaterMark;
+        this.supply = stats.supply;
+        this.interestDistributed = stats.interestDistributed;
+        this.ubiDistributed = stats.ubiDistributed;
+        this.validBlockhash = stats.validBlockhash;
+    }
+
+    /**
+     * @returns {DailyStats}
+     */
+    static empty() {
+        return new DailyStats({
+            day: 0n, proofsAccepted: 0n, uniqueMiners: 0n, tokensMined: 0n, highWaterMark: 0n, supply: 0n,
+            interestDistributed: 0n, ubiDistributed: 0n, validBlockhash: new Uint8Array(32),
+        });
+    }
+
+    /**
+     * @returns {Uint8Array}
+     */
+    toBytes() {
+        return new Uint8Array([
+            ...bigintAsU64ToBytes(this.day),
+            ...bigintAsU64ToBytes(this.proofsAccepted),
+            ...bigintAsU64ToBytes(this.uniqueMiners),
+            ...bigintAsU64ToBytes(this.tokensMined),
+            ...bigintAsU64ToBytes(this.highWaterMark),
+            ...bigintAsU64ToBytes(this.supply),
+            ...bigintAsU64ToBytes(this.interestDistributed),
+            ...bigintAsU64ToBytes(this.ubiDistributed),
+            ...this.validBlockhash,
+        ]);
+    }
+
+    /**
+     * @param {Uint8Array} bytes
+     * @returns {DailyStats}
+     */
+    static fromBytes(bytes) {
+        const dataView = new DataView(bytes.buffer.slice(bytes.byteOffset));
+        return new DailyStats({
+            day: dataView.getBigInt64(0, true),
+            proofsAccepted: dataView.getBigUint64(8, true),
+            uniqueMiners: dataView.getBigUint64(16, true),
+            tokensMined: dataView.getBigUint64(24, true),
+            highWaterMark: dataView.getBigUint64(32, true),
+            supply: dataView.getBigUint64(40, true),
+            interestDistributed: dataView.getBigUint64(48, true),
+            ubiDistributed: dataView.getBigUint64(56, true),
+            validBlockhash: bytes.slice(64, 96),
+        });
+    }
+}
+
+export class DailyStatsHistory {
+    proofsSinceLastDistribution; //  u64
+    minersSinceLastDistribution; //  u64
+    oldestStats; //  usize
+    historicStats; //  [DailyStats; 64]
+
+    static HISTORY_SIZE = 64; //   remain consistent with rust
+    static SIZE = 24 + DailyStatsHistory.HISTORY_SIZE * DailyStats.SIZE;
+
+    /**
+     * @param {bigint} proofsSinceLastDistribution
+     * @param {bigint} minersSinceLastDistribution
+     * @param {bigint} oldestStats
+     * @param {DailyStats[]} historicStats
+     */
+    constructor(proofsSinceLastDistribution, minersSinceLastDistribution, oldestStats, historicStats) {
+        this.proofsSinceLastDistribution = proofsSinceLastDistribution;
+        this.minersSinceLastDistribution = minersSinceLastDistribution;
+        this.oldestStats = oldestStats;
+        this.historicStats = [
+            ...historicStats,
+            ...Array.from({ length: DailyStatsHistory.HISTORY_SIZE - historicStats.length }, () => DailyStats.empty()),
+        ];
+    }
+
+    /**
+     * @returns {DailyStats} the stats of the most recently ended day
+     */
+    newest() {
+        const index = (Number(this.oldestStats) + DailyStatsHistory.HISTORY_SIZE - 1) % DailyStatsHistory.HISTORY_SIZE;
+        return this.historicStats[index];
+    }
+
+    /**
+     * @returns {Uint8Array}
+     */
+    toBytes() {
+        return new Uint8Array([
+            ...bigintAsU64ToBytes(this.proofsSinceLastDistribution),
+            ...bigintAsU64ToBytes(this.minersSinceLastDistribution),
+            ...bigintAsU64ToBytes(this.oldestStats),
+            ...this.historicStats.flatMap((stats) => [...stats.toBytes()]),
+        ]);
+    }
+
+    /**
+     * @param {Uint8Array} bytes
+     * @returns {DailyStatsHistory}
+     */
+    static fromBytes(bytes) {
+        const dataView = new DataView(bytes.buffer.slice(bytes.byteOffset));
+        return new DailyStatsHistory(
+            dataView.getBigUint64(0, true),
+            dataView.getBigUint64(8, true),
+            dataView.getBigUint64(16, true),
+            chunkArray(bytes.subarray(24, DailyStatsHistory.SIZE), DailyStats.SIZE).map((stats) => DailyStats.fromBytes(stats)),
         );
     }
 }
@@ -473,18 +919,27 @@ export class DailyDistributionData {
 export class GlobalDataAccount {
     address;
     owner;
+    config;
     validBlockhashes;
     dailyDistributionData;
+    ledger;
+    dailyStats;
 
     /**
+     * @param {Config} config
      * @param {ValidBlockhashes} validBlockhashes
      * @param {DailyDistributionData} dailyDistributionData
+     * @param {Ledger} ledger
+     * @param {DailyStatsHistory} dailyStats
      */
-    constructor(validBlockhashes, dailyDistributionData) {
+    constructor(config, validBlockhashes, dailyDistributionData, ledger, dailyStats = new DailyStatsHistory(0n, 0n, 0n, [])) {
         this.address = global_data_account_pubkey;
         this.owner = compto_program_id_pubkey;
+        this.config = config;
         this.validBlockhashes = validBlockhashes;
         this.dailyDistributionData = dailyDistributionData;
+        this.ledger = ledger;
+        this.dailyStats = dailyStats;
     }
 
     /**
@@ -495,7 +950,13 @@ export class GlobalDataAccount {
             address: this.address,
             info: {
                 lamports: BIG_NUMBER,
-                data: new Uint8Array([...this.validBlockhashes.toBytes(), ...this.dailyDistributionData.toBytes()]),
+                data: new Uint8Array([
+                    ...this.config.toBytes(),
+                    ...this.validBlockhashes.toBytes(),
+                    ...this.dailyDistributionData.toBytes(),
+                    ...this.ledger.toBytes(),
+                    ...this.dailyStats.toBytes(),
+                ]),
                 owner: this.owner,
                 executable: false,
             },
@@ -508,9 +969,16 @@ export class GlobalDataAccount {
      * @returns {GlobalDataAccount}
      */
     static fromAccountInfoBytes(address, accountInfo) {
+        const validBlockhashesStart = Config.SIZE;
+        const dailyDistributionDataStart = validBlockhashesStart + ValidBlockhashes.SIZE;
+        const ledgerStart = dailyDistributionDataStart + DailyDistributionData.SIZE;
+        const dailyStatsStart = ledgerStart + Ledger.SIZE;
         return new GlobalDataAccount(
-            ValidBlockhashes.fromBytes(accountInfo.data.subarray(0, 80)),
-            DailyDistributionData.fromBytes(accountInfo.data.subarray(80)),
+            Config.fromBytes(accountInfo.data.subarray(0, validBlockhashesStart)),
+            ValidBlockhashes.fromBytes(accountInfo.data.subarray(validBlockhashesStart, dailyDistributionDataStart)),
+            DailyDistributionData.fromBytes(accountInfo.data.subarray(dailyDistributionDataStart, ledgerStart)),
+            Ledger.fromBytes(accountInfo.data.subarray(ledgerStart, dailyStatsStart)),
+            DailyStatsHistory.fromBytes(accountInfo.data.subarray(dailyStatsStart)),
         );
     }
 }
@@ -627,28 +1095,43 @@ export class UserDataAccount {
     lamports; // u64
     owner; // PublicKey
     lastInterestPayoutDate; // i64
-    isVerifiedHuman; // bool
+    lastSettledBalance; // u64
+    accruedInterest; // u64
+    userIdentity; // PublicKey
+    bump; // u8
     length; // usize
     recentBlockhash; // Hash
-    proofs; // [Hash]
+    previousLength; // usize
+    previousBlockhash; // Hash
+    proofs; // [Hash] proofs for previousBlockhash first, then those for recentBlockhash
 
     /**
      * @param {PublicKey} address
      * @param {bigint} lamports
      * @param {bigint} lastInterestPayoutDate
-     * @param {boolean} isVerifiedHuman
+     * @param {bigint} lastSettledBalance
+     * @param {bigint} accruedInterest
+     * @param {PublicKey} userIdentity
+     * @param {number} bump
      * @param {bigint} length
      * @param {Uint8Array} recentBlockhash
+     * @param {bigint} previousLength
+     * @param {Uint8Array} previousBlockhash
      * @param {Uint8Array[]} proofs
      */
-    constructor(address, lamports, lastInterestPayoutDate, isVerifiedHuman, length, recentBlockhash, proofs) {
+    constructor(address, lamports, lastInterestPayoutDate, lastSettledBalance, accruedInterest, userIdentity, bump, length, recentBlockhash, previousLength, previousBlockhash, proofs) {
         this.address = address;
         this.lamports = lamports;
         this.owner = compto_program_id_pubkey;
         this.lastInterestPayoutDate = lastInterestPayoutDate;
-        this.isVerifiedHuman = isVerifiedHuman;
+        this.lastSettledBalance = lastSettledBalance;
+        this.accruedInterest = accruedInterest;
+        this.userIdentity = userIdentity;
+        this.bump = bump;
         this.length = length;
         this.recentBlockhash = recentBlockhash;
+        this.previousLength = previousLength;
+        this.previousBlockhash = previousBlockhash;
         this.proofs = proofs;
     }
 
@@ -658,10 +1141,15 @@ export class UserDataAccount {
     toAccount() {
         let buffer = new Uint8Array([
             ...bigintAsU64ToBytes(this.lastInterestPayoutDate),
-            this.isVerifiedHuman ? 1 : 0,
+            ...bigintAsU64ToBytes(this.lastSettledBalance),
+            ...bigintAsU64ToBytes(this.accruedInterest),
+            ...this.userIdentity.toBytes(),
+            this.bump,
             ...[0, 0, 0, 0, 0, 0, 0], // padding
             ...bigintAsU64ToBytes(this.length),
             ...this.recentBlockhash,
+            ...bigintAsU64ToBytes(this.previousLength),
+            ...this.previousBlockhash,
             ...this.proofs.reduce((a, b) => Uint8Array.from([...a, ...b]), new Uint8Array()),
         ]);
         return {
@@ -686,10 +1174,84 @@ export class UserDataAccount {
             address,
             accountInfo.lamports,
             dataView.getBigInt64(0, true),
-            dataView.getUint8(8) === 0 ? false : true,
+            dataView.getBigUint64(8, true),
             dataView.getBigUint64(16, true),
-            accountInfo.data.subarray(24, 56),
-            LEBytesToBlockhashArray(accountInfo.data.subarray(56)),
+            new PublicKey(accountInfo.data.subarray(24, 56)),
+            dataView.getUint8(56),
+            dataView.getBigUint64(64, true),
+            accountInfo.data.subarray(72, 104),
+            dataView.getBigUint64(104, true),
+            accountInfo.data.subarray(112, 144),
+            LEBytesToBlockhashArray(accountInfo.data.subarray(144)),
+        );
+    }
+}
+
+export class UserIdentityAccount {
+    address; // PublicKey
+    lamports; // u64
+    owner; // PublicKey
+    identityOwner; // PublicKey
+    lastUbiPayoutDate; // i64
+    isVerifiedHuman; // bool
+    bump; // u8
+
+    static SIZE = 48; //   remain consistent with rust
+
+    /**
+     * @param {PublicKey} address
+     * @param {bigint} lamports
+     * @param {PublicKey} identityOwner
+     * @param {bigint} lastUbiPayoutDate
+     * @param {boolean} isVerifiedHuman
+     * @param {number} bump
+     */
+    constructor(address, lamports, identityOwner, lastUbiPayoutDate, isVerifiedHuman, bump) {
+        this.address = address;
+        this.lamports = lamports;
+        this.owner = compto_program_id_pubkey;
+        this.identityOwner = identityOwner;
+        this.lastUbiPayoutDate = lastUbiPayoutDate;
+        this.isVerifiedHuman = isVerifiedHuman;
+        this.bump = bump;
+    }
+
+    /**
+     * @returns {AddedAccount}
+     */
+    toAccount() {
+        let buffer = new Uint8Array([
+            ...this.identityOwner.toBytes(),
+            ...bigintAsU64ToBytes(this.lastUbiPayoutDate),
+            this.isVerifiedHuman ? 1 : 0,
+            this.bump,
+            ...[0, 0, 0, 0, 0, 0], // padding
+        ]);
+        return {
+            address: this.address,
+            info: {
+                lamports: this.lamports,
+                data: buffer,
+                owner: this.owner,
+                executable: false,
+            },
+        };
+    }
+
+    /**
+     * @param {PublicKey} address
+     * @param {AccountInfoBytes} accountInfo
+     * @returns {UserIdentityAccount}
+     */
+    static fromAccountInfoBytes(address, accountInfo) {
+        const dataView = new DataView(accountInfo.data.buffer.slice(accountInfo.data.byteOffset));
+        return new UserIdentityAccount(
+            address,
+            accountInfo.lamports,
+            new PublicKey(accountInfo.data.subarray(0, 32)),
+            dataView.getBigInt64(32, true),
+            dataView.getUint8(40) === 0 ? false : true,
+            dataView.getUint8(41),
         );
     }
 }
@@ -876,16 +1438,40 @@ export function get_default_comptoken_mint() {
         .addExtension(TLV.transferHook(compto_transfer_hook_id_pubkey));
 }
 
+/**
+ * @returns {Instance} the instance described by the cached addresses
+ */
+export function get_default_instance() {
+    // the transfer hook requires a "user data" account for the banks, but they don't actually exist
+    const bankDataBump = (bank) => PublicKey.findProgramAddressSync([bank.toBytes()], compto_program_id_pubkey)[1];
+    return {
+        mint: comptoken_mint_pubkey,
+        transferHookProgram: compto_transfer_hook_id_pubkey,
+        interestBank: interest_bank_account_pubkey,
+        ubiBank: ubi_bank_account_pubkey,
+        bumps: {
+            globalData: global_data_account_bump,
+            interestBank: interest_bank_account_bump,
+            ubiBank: ubi_bank_account_bump,
+            interestBankData: bankDataBump(interest_bank_account_pubkey),
+            ubiBankData: bankDataBump(ubi_bank_account_pubkey),
+            extraAccountMetas: compto_extra_account_metas_account_bump,
+        },
+    };
+}
+
 /**
  * @returns {GlobalDataAccount}
  */
 export function get_default_global_data() {
     return new GlobalDataAccount(
+        new Config(DEFAULT_DISTRIBUTION_BOUNTY, DEFAULT_BLOCKHASH_GRACE_PERIOD, PublicKey.default, DEFAULT_V1_PROOF_DEADLINE),
         new ValidBlockhashes(
             { blockhash: Uint8Array.from({ length: 32 }, (v, i) => i), time: DEFAULT_ANNOUNCE_TIME },
             { blockhash: Uint8Array.from({ length: 32 }, (v, i) => 2 * i), time: DEFAULT_DISTRIBUTION_TIME }
         ),
-        new DailyDistributionData(0n, 0n, DEFAULT_DISTRIBUTION_TIME, 0n, []),
+        new DailyDistributionData(0n, 0n, DEFAULT_DISTRIBUTION_TIME, 0n, 0n, []),
+        new Ledger(0n, 0n, 0n, 0n, 0n, 0n),
     );
 }
 
@@ -914,11 +1500,22 @@ export function get_default_unpaid_ubi_bank() {
 }
 
 /**
- * @param {PublicKey} address 
+ * @param {PublicKey} comptokenWallet the wallet the user data belongs to
+ * @param {PublicKey} userIdentity
  * @returns {UserDataAccount}
  */
-export function get_default_user_data_account(address) {
-    return new UserDataAccount(address, BIG_NUMBER, DEFAULT_DISTRIBUTION_TIME, false, 0n, new Uint8Array(32), Array.from({ length: 8 }, (v, i) => new Uint8Array(32)));
+export function get_default_user_data_account(comptokenWallet, userIdentity = PublicKey.default) {
+    const [address, bump] = PublicKey.findProgramAddressSync([comptokenWallet.toBytes()], compto_program_id_pubkey);
+    return new UserDataAccount(address, BIG_NUMBER, DEFAULT_DISTRIBUTION_TIME, 0n, 0n, userIdentity, bump, 0n, new Uint8Array(32), 0n, new Uint8Array(32), Array.from({ length: 8 }, (v, i) => new Uint8Array(32)));
+}
+
+/**
+ * @param {PublicKey} identityOwner
+ * @returns {UserIdentityAccount}
+ */
+export function get_default_user_identity_account(identityOwner) {
+    const [address, bump] = PublicKey.findProgramAddressSync([Buffer.from("User Identity"), comptoken_mint_pubkey.toBytes(), identityOwner.toBytes()], compto_program_id_pubkey);
+    return new UserIdentityAccount(address, BIG_NUMBER, identityOwner, DEFAULT_DISTRIBUTION_TIME, false, bump);
 }
 
 /**
@@ -927,9 +1524,11 @@ export function get_default_user_data_account(address) {
 export function get_default_extra_account_metas_account() {
     return new ExtraAccountMetaAccount(compto_extra_account_metas_account_pubkey, BIG_NUMBER, compto_transfer_hook_id_pubkey, [
         new ExtraAccountMeta(new AddressConfig(AddressConfig.Types.LITERAL, compto_program_id_pubkey), false, false),
-        // 0 refers to senders account, 5 refers to compto program
-        new ExtraAccountMeta(new AddressConfig(AddressConfig.Types.PDA_OTHER_PROGRAM, [new Seed(Seed.Types.ACCOUNT_KEY, 0)], 5), false, false),
-        // 2 refers to recievers account, 5 refers to compto program
-        new ExtraAccountMeta(new AddressConfig(AddressConfig.Types.PDA_OTHER_PROGRAM, [new Seed(Seed.Types.ACCOUNT_KEY, 2)], 5), false, false),
+        // 0 refers to senders account, 5 refers to compto program. writable so the transfer can be settled
+        new ExtraAccountMeta(new AddressConfig(AddressConfig.Types.PDA_OTHER_PROGRAM, [new Seed(Seed.Types.ACCOUNT_KEY, 0)], 5), false, true),
+        // 2 refers to recievers account, 5 refers to compto program. writable so the transfer can be settled
+        new ExtraAccountMeta(new AddressConfig(AddressConfig.Types.PDA_OTHER_PROGRAM, [new Seed(Seed.Types.ACCOUNT_KEY, 2)], 5), false, true),
+        // the global data holds the instance's config
+        new ExtraAccountMeta(new AddressConfig(AddressConfig.Types.LITERAL, global_data_account_pubkey), false, false),
     ]);
 }
\ No newline at end of file
@@ -14,6 +14,35 @@ export const Instruction = {
     DAILY_DISTRIBUTION_EVENT: 4,
     GET_VALID_BLOCKHASHES: 5,
     GET_OWED_COMPTOKENS: 6,
+    GET_DISTRIBUTION_STATUS: 7,
+    UPDATE_COMPTOKEN_METADATA: 8,
+    CREATE_USER_IDENTITY: 9,
+    SET_PROOF_SCHEME: 10,
+    AUDIT: 11,
+    PREVIEW_OWED_COMPTOKENS: 12,
+    QUERY_GLOBAL_DATA: 13,
+    // only in testmode builds
+    TEST_SET_LAST_INTEREST_PAYOUT_DATE: 200,
+    TEST_SET_VERIFIED_HUMAN: 201,
+    TEST_INJECT_HISTORIC_INTERESTS: 202,
+    TEST_FORCE_BLOCKHASH_ROTATION: 203,
+};
+
+// remain consistent with query.rs
+export const QUERY_VERSION = 1;
+export const QuerySelector = {
+    PARAMETERS: 0,
+    DISTRIBUTION: 1,
+    INTEREST_HISTORY: 2,
+    STATS_HISTORY: 3,
+    BLOCKHASH_TIMES: 4,
+};
+
+// remain consistent with proof_scheme.rs
+export const ProofScheme = {
+    SHA256: 0,
+    KECCAK256: 1,
+    BLAKE3: 2,
 };
 
 export const DEFAULT_START_TIME = 1_721_940_656n;
@@ -21,19 +50,33 @@ export const DEFAULT_DISTRIBUTION_TIME = 1_721_865_600n; // DEFAULT_START_TIME -
 export const DEFAULT_ANNOUNCE_TIME = 1_721_865_300n; // DEFAULT_DISTRIBUTION_TIME - (5 * 60) <-- 5 minutes before distribution
 export const SEC_PER_DAY = 86_400n;
 export const MINT_DECIMALS = 0; // MAGIC NUMBER keep consistent with python and rust
+export const DEFAULT_DISTRIBUTION_BOUNTY = 1_000n;
+export const DEFAULT_BLOCKHASH_GRACE_PERIOD = 300n; // 5 minutes
+export const DEFAULT_V1_PROOF_DEADLINE = DEFAULT_START_TIME + SEC_PER_DAY; // v1 proofs are still accepted on the start day
+export const DEFAULT_DAY_LENGTH = SEC_PER_DAY;
+export const DEFAULT_DAY_OFFSET = 0n; // days start at midnight UTC
+export const DEFAULT_NAME = "Comptoken";
+export const DEFAULT_SYMBOL = "CPT";
+export const DEFAULT_URI = "https://compto.com/metadata.json";
 
 // Read Cache Files
 import global_data_account from "../.cache/compto_global_data_account.json" assert { type: "json" };
 export const global_data_account_str = global_data_account["address"];
+export const global_data_account_bump = global_data_account["bumpSeed"];
 
 import interest_bank_account from "../.cache/compto_interest_bank_account.json" assert { type: "json" };
 export const interest_bank_account_str = interest_bank_account["address"];
+export const interest_bank_account_bump = interest_bank_account["bumpSeed"];
 
 import ubi_bank_account from "../.cache/compto_ubi_bank_account.json" assert { type: "json" };
 export const ubi_bank_account_str = ubi_bank_account["address"];
+export const ubi_bank_account_bump = ubi_bank_account["bumpSeed"];
 
 import comptoken_id from "../.cache/comptoken_mint.json" assert { type: "json" };
-export const comptoken_mint_str = comptoken_id["commandOutput"]["address"];
+export const comptoken_mint_str = comptoken_id["address"];
+
+import comptoken_mint_keypair_ from "../.cache/comptoken_mint_keypair.json" assert { type: "json" };
+export const comptoken_mint_keypair_num_arr = comptoken_mint_keypair_;
 
 import compto_program_id from "../.cache/compto_program_id.json" assert { type: "json" };
 export const compto_program_id_str = compto_program_id["programId"];
@@ -43,6 +86,7 @@ export const compto_transfer_hook_id_str = compto_transfer_hook_id["programId"];
 
 import compto_extra_account_metas_account from "../.cache/compto_extra_account_metas_account.json" assert { type: "json" };
 export const compto_extra_account_metas_account_str = compto_extra_account_metas_account["address"];
+export const compto_extra_account_metas_account_bump = compto_extra_account_metas_account["bumpSeed"];
 
 import testuser_comptoken_wallet_ from "../.cache/test_user_account.json" assert { type: "json" };
 export const testuser_comptoken_wallet_num_arr = testuser_comptoken_wallet_;
@@ -58,5 +102,7 @@ export const compto_extra_account_metas_account_pubkey = new PublicKey(bs58.deco
 export const compto_transfer_hook_id_pubkey = new PublicKey(bs58.decode(compto_transfer_hook_id_str));
 
 // KeyPair
+// the mint keys the comptoken instance, it only signs when the instance is initialized
+export const comptoken_mint_keypair = Keypair.fromSecretKey(new Uint8Array(comptoken_mint_keypair_num_arr));
 let solana_id = JSON.parse(fs.readFileSync(os.homedir() + "/.config/solana/id.json").toString());
 export const me_keypair = Keypair.fromSecretKey(new Uint8Array(solana_id));
@@ -0,0 +1,116 @@
+import { Transaction, TransactionInstruction } from "@solana/web3.js";
+import { Clock, start } from "solana-bankrun";
+
+import {
+    get_default_comptoken_mint, get_default_global_data, get_default_unpaid_interest_bank, get_default_unpaid_ubi_bank,
+} from "../accounts.js";
+import { Assert } from "../assert.js";
+import { compto_program_id_pubkey, DEFAULT_START_TIME, Instruction, SEC_PER_DAY } from "../common.js";
+
+// ensure these remain consistent with audit.rs
+const AuditCheck = {
+    SUPPLY_MISMATCH: 1 << 0,
+    INTEREST_BANK_INSOLVENT: 1 << 1,
+    UBI_BANK_INSOLVENT: 1 << 2,
+    BLOCKHASH_STALE: 1 << 3,
+    DISTRIBUTION_OVERDUE: 1 << 4,
+    LEDGER_INCONSISTENT: 1 << 5,
+};
+
+async function test_audit() {
+    let global_data = get_default_global_data();
+    global_data.ledger.totalMined = 10n;
+    global_data.ledger.totalInterestDistributed = 6n;
+    global_data.ledger.totalUbiDistributed = 4n;
+    global_data.ledger.totalInterestClaimed = 2n;
+    let comptoken_mint = get_default_comptoken_mint();
+    comptoken_mint.supply = 20n;
+    let interest_bank = get_default_unpaid_interest_bank();
+    interest_bank.amount = 4n;
+    let ubi_bank = get_default_unpaid_ubi_bank();
+    ubi_bank.amount = 4n;
+
+    const context = await start(
+        [{ name: "comptoken", programId: compto_program_id_pubkey }],
+        [comptoken_mint.toAccount(), global_data.toAccount(), interest_bank.toAccount(), ubi_bank.toAccount()]
+    );
+
+    const client = context.banksClient;
+    const payer = context.payer;
+    const blockhash = context.lastBlockhash;
+    const keys = [
+        // the mint's supply is checked against the ledger
+        { pubkey: comptoken_mint.address, isSigner: false, isWritable: false },
+        // stores the ledger, the valid blockhashes, and when the last distribution happened
+        { pubkey: global_data.address, isSigner: false, isWritable: false },
+        // must hold all unclaimed interest
+        { pubkey: interest_bank.address, isSigner: false, isWritable: false },
+        // must hold all unclaimed UBI
+        { pubkey: ubi_bank.address, isSigner: false, isWritable: false },
+    ];
+
+    const ixs = [new TransactionInstruction({ programId: compto_program_id_pubkey, keys, data: Buffer.from([Instruction.AUDIT]) })];
+    const tx = new Transaction();
+    tx.recentBlockhash = blockhash;
+    tx.add(...ixs);
+    tx.sign(payer);
+
+    /**
+     * @param {Uint8Array} data
+     */
+    function decodeReport(data) {
+        const dataView = new DataView(data.buffer.slice(data.byteOffset));
+        return {
+            failedChecks: data[0],
+            supply: dataView.getBigUint64(1, true),
+            totalIssued: dataView.getBigUint64(9, true),
+            interestBankBalance: dataView.getBigUint64(17, true),
+            interestLiabilities: dataView.getBigUint64(25, true),
+            ubiBankBalance: dataView.getBigUint64(33, true),
+            ubiLiabilities: dataView.getBigUint64(41, true),
+        };
+    }
+
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME));
+    let result = await client.simulateTransaction(tx);
+    console.log("computeUnitsConsumed: %d", result.meta.computeUnitsConsumed);
+    let report = decodeReport(result.meta.returnData.data);
+    Assert.assertEqual(report.failedChecks, 0, "a consistent program passes");
+    Assert.assertEqual(report.supply, 20n, "supply");
+    Assert.assertEqual(report.totalIssued, 20n, "total issued");
+    Assert.assertEqual(report.interestBankBalance, 4n, "interest bank balance");
+    Assert.assertEqual(report.interestLiabilities, 4n, "unclaimed interest");
+    Assert.assertEqual(report.ubiBankBalance, 4n, "ubi bank balance");
+    Assert.assertEqual(report.ubiLiabilities, 4n, "unclaimed ubi");
+
+    // comptokens minted outside the ledger and a bank paying out more than it was given
+    comptoken_mint.supply = 21n;
+    interest_bank.amount = 3n;
+    for (const account of [comptoken_mint.toAccount(), interest_bank.toAccount()]) {
+        context.setAccount(account.address, account.info);
+    }
+    result = await client.simulateTransaction(tx);
+    report = decodeReport(result.meta.returnData.data);
+    Assert.assertEqual(
+        report.failedChecks, AuditCheck.SUPPLY_MISMATCH | AuditCheck.INTEREST_BANK_INSOLVENT, "supply and interest bank fail"
+    );
+
+    // nobody has run the distribution for a whole day
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME + 2n * SEC_PER_DAY));
+    result = await client.simulateTransaction(tx);
+    report = decodeReport(result.meta.returnData.data);
+    Assert.assert(report.failedChecks & AuditCheck.BLOCKHASH_STALE, "blockhash is stale");
+    Assert.assert(report.failedChecks & AuditCheck.DISTRIBUTION_OVERDUE, "distribution is overdue");
+    Assert.assert(!(report.failedChecks & AuditCheck.UBI_BANK_INSOLVENT), "ubi bank is still solvent");
+
+    // a ledger that records more ubi claimed than distributed
+    global_data.ledger.totalUbiClaimed = 5n;
+    const global_data_account = global_data.toAccount();
+    context.setAccount(global_data_account.address, global_data_account.info);
+    result = await client.simulateTransaction(tx);
+    report = decodeReport(result.meta.returnData.data);
+    Assert.assert(report.failedChecks & AuditCheck.LEDGER_INCONSISTENT, "ledger is inconsistent");
+    Assert.assertEqual(report.ubiLiabilities, 0n, "inconsistent liabilities are reported as 0");
+}
+
+(async () => { await test_audit(); })();
@@ -1,9 +1,11 @@
 import { PublicKey, SystemProgram, Transaction, TransactionInstruction } from "@solana/web3.js";
 import { Clock, start } from "solana-bankrun";
 
-import { get_default_comptoken_mint, get_default_global_data, UserDataAccount } from "../accounts.js";
+import {
+    get_default_comptoken_mint, get_default_comptoken_wallet, get_default_global_data, get_default_user_identity_account, UserDataAccount,
+} from "../accounts.js";
 import { Assert } from "../assert.js";
-import { compto_program_id_pubkey, Instruction, testuser_comptoken_wallet_pubkey } from "../common.js";
+import { compto_program_id_pubkey, global_data_account_pubkey, Instruction, testuser_comptoken_wallet_pubkey } from "../common.js";
 
 async function test_createUserDataAccount() {
     const context = await start(
@@ -18,38 +20,80 @@ async function test_createUserDataAccount() {
     const payer = context.payer;
     const blockhash = context.lastBlockhash;
     const rent = await client.getRent()
-    let user_data_account = PublicKey.findProgramAddressSync([testuser_comptoken_wallet_pubkey.toBytes()], compto_program_id_pubkey)[0];
-
-    const keys = [
-        // the payer of the rent for the account
-        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
-        // the data account tied to the comptoken wallet
-        { pubkey: user_data_account, isSigner: false, isWritable: true },
-        // the payers comptoken wallet (comptoken token acct)
-        { pubkey: testuser_comptoken_wallet_pubkey, isSigner: false, isWritable: false },
-        // system account is used to create the account
-        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
-    ];
+    // the payer must own the comptoken wallet, which isn't known until the context is started
+    const wallet = get_default_comptoken_wallet(testuser_comptoken_wallet_pubkey, payer.publicKey).toAccount();
+    context.setAccount(wallet.address, wallet.info);
+    const identity = get_default_user_identity_account(payer.publicKey).toAccount();
+    context.setAccount(identity.address, identity.info);
+    const others_wallet = get_default_comptoken_wallet(PublicKey.unique(), PublicKey.unique()).toAccount();
+    context.setAccount(others_wallet.address, others_wallet.info);
+    let other_mint_wallet = get_default_comptoken_wallet(PublicKey.unique(), payer.publicKey);
+    other_mint_wallet.mint = PublicKey.unique();
+    other_mint_wallet = other_mint_wallet.toAccount();
+    context.setAccount(other_mint_wallet.address, other_mint_wallet.info);
+
+    /**
+     * @param {PublicKey} comptoken_wallet
+     * @returns {{ pubkey: PublicKey, isSigner: boolean, isWritable: boolean }[]}
+     */
+    function getKeys(comptoken_wallet) {
+        return [
+            // the payer of the rent for the account, who owns the comptoken wallet
+            { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+            // the data account tied to the comptoken wallet
+            { pubkey: PublicKey.findProgramAddressSync([comptoken_wallet.toBytes()], compto_program_id_pubkey)[0], isSigner: false, isWritable: true },
+            // the payers comptoken wallet (comptoken token acct)
+            { pubkey: comptoken_wallet, isSigner: false, isWritable: false },
+            // system account is used to create the account
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            // the payers identity, which the user data links to
+            { pubkey: identity.address, isSigner: false, isWritable: false },
+            // the global data holds the config the wallet and identity are checked against
+            { pubkey: global_data_account_pubkey, isSigner: false, isWritable: false },
+        ];
+    }
+    const [user_data_account, user_data_bump] = PublicKey.findProgramAddressSync([testuser_comptoken_wallet_pubkey.toBytes()], compto_program_id_pubkey);
 
     // MAGIC NUMBER: CHANGE NEEDS TO BE REFLECTED IN user_data.rs
-    const PROOF_STORAGE_MIN_SIZE = 88n;
+    const PROOF_STORAGE_MIN_SIZE = 176n;
     const rentExemptAmount = await rent.minimumBalance(PROOF_STORAGE_MIN_SIZE);
 
-    let data = Buffer.alloc(17);
+    // 1 byte for the instruction, 8 bytes for the proof capacity
+    let data = Buffer.alloc(9);
     data.writeUInt8(Instruction.CREATE_USER_DATA_ACCOUNT, 0);
-    data.writeBigInt64LE(rentExemptAmount, 1);
-    data.writeBigInt64LE(PROOF_STORAGE_MIN_SIZE, 9);
+    data.writeBigUInt64LE(1n, 1);
+
+    context.setClock(new Clock(0n, 0n, 0n, 0n, 1_721_940_656n));
+    for (const [comptoken_wallet, msg] of [
+        [others_wallet.address, "wallet owned by someone other than the payer is rejected"],
+        [other_mint_wallet.address, "wallet for another mint is rejected"],
+    ]) {
+        const tx = new Transaction();
+        tx.recentBlockhash = blockhash;
+        tx.add(new TransactionInstruction({ programId: compto_program_id_pubkey, keys: getKeys(comptoken_wallet), data }));
+        tx.sign(payer);
+        const result = await client.simulateTransaction(tx);
+        Assert.assertNotNull(result.result, msg);
+    }
 
-    const ixs = [new TransactionInstruction({ programId: compto_program_id_pubkey, keys, data })];
+    const ixs = [new TransactionInstruction({ programId: compto_program_id_pubkey, keys: getKeys(testuser_comptoken_wallet_pubkey), data })];
     const tx = new Transaction();
     tx.recentBlockhash = blockhash;
     tx.add(...ixs);
     tx.sign(payer);
-    context.setClock(new Clock(0n, 0n, 0n, 0n, 1_721_940_656n));
     const meta = await client.processTransaction(tx);
-    const finalUserData = UserDataAccount.fromAccountInfoBytes(user_data_account, await client.getAccount(user_data_account));
+
+    console.log("logMessages: %s", meta.logMessages);
+    console.log("computeUnitsConsumed: %d", meta.computeUnitsConsumed);
+    console.log("returnData: %s", meta.returnData);
+
+    const account = await client.getAccount(user_data_account);
+    Assert.assertEqual(BigInt(account.data.length), PROOF_STORAGE_MIN_SIZE, "user data has space for one proof");
+    Assert.assertEqual(account.lamports, rentExemptAmount, "user data is rent exempt");
+    const finalUserData = UserDataAccount.fromAccountInfoBytes(user_data_account, account);
     Assert.assertEqual(finalUserData.lastInterestPayoutDate, 1_721_865_600n, "user data lastInterestPayoutDate");
-    Assert.assert(!finalUserData.isVerifiedHuman, "user data isVerifiedHuman");
+    Assert.assert(finalUserData.userIdentity.equals(identity.address), "user data is linked to the payers identity");
+    Assert.assertEqual(finalUserData.bump, user_data_bump, "user data stores its bump");
 }
 
 (async () => { await test_createUserDataAccount(); })();
\ No newline at end of file
@@ -0,0 +1,52 @@
+import { PublicKey, SystemProgram, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { Clock, start } from "solana-bankrun";
+
+import { get_default_global_data, UserIdentityAccount } from "../accounts.js";
+import { Assert } from "../assert.js";
+import { compto_program_id_pubkey, comptoken_mint_pubkey, DEFAULT_DISTRIBUTION_TIME, DEFAULT_START_TIME, global_data_account_pubkey, Instruction } from "../common.js";
+
+async function test_createUserIdentity() {
+    const context = await start([{ name: "comptoken", programId: compto_program_id_pubkey }], [get_default_global_data().toAccount()]);
+
+    const client = context.banksClient;
+    const payer = context.payer;
+    const blockhash = context.lastBlockhash;
+    const rent = await client.getRent();
+    const [user_identity_address, user_identity_bump] = PublicKey.findProgramAddressSync([Buffer.from("User Identity"), comptoken_mint_pubkey.toBytes(), payer.publicKey.toBytes()], compto_program_id_pubkey);
+
+    const keys = [
+        // the owner of the comptoken wallets the identity is for, who pays for the account
+        { pubkey: payer.publicKey, isSigner: true, isWritable: true },
+        // the identity account to be created
+        { pubkey: user_identity_address, isSigner: false, isWritable: true },
+        // system account is used to create the account
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        // identities are per comptoken instance
+        { pubkey: global_data_account_pubkey, isSigner: false, isWritable: false },
+    ];
+    const data = Buffer.from([Instruction.CREATE_USER_IDENTITY]);
+
+    const ixs = [new TransactionInstruction({ programId: compto_program_id_pubkey, keys, data })];
+    const tx = new Transaction();
+    tx.recentBlockhash = blockhash;
+    tx.add(...ixs);
+    tx.sign(payer);
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME));
+    const meta = await client.processTransaction(tx);
+
+    console.log("logMessages: %s", meta.logMessages);
+    console.log("computeUnitsConsumed: %d", meta.computeUnitsConsumed);
+    console.log("returnData: %s", meta.returnData);
+
+    const account = await client.getAccount(user_identity_address);
+    Assert.assertNotNull(account);
+    Assert.assertEqual(BigInt(account.data.length), BigInt(UserIdentityAccount.SIZE), "user identity size");
+    Assert.assertEqual(account.lamports, await rent.minimumBalance(BigInt(UserIdentityAccount.SIZE)), "user identity is rent exempt");
+    const finalUserIdentity = UserIdentityAccount.fromAccountInfoBytes(user_identity_address, account);
+    Assert.assert(finalUserIdentity.identityOwner.equals(payer.publicKey), "user identity owner");
+    Assert.assertEqual(finalUserIdentity.lastUbiPayoutDate, DEFAULT_DISTRIBUTION_TIME, "user identity lastUbiPayoutDate");
+    Assert.assert(!finalUserIdentity.isVerifiedHuman, "user identity isVerifiedHuman");
+    Assert.assertEqual(finalUserIdentity.bump, user_identity_bump, "user identity stores its bump");
+}
+
+(async () => { await test_createUserIdentity(); })();
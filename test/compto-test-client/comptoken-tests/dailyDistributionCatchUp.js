@@ -0,0 +1,89 @@
+import { Keypair, SYSVAR_SLOT_HASHES_PUBKEY, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { Clock, start } from "solana-bankrun";
+
+import { TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
+import { get_default_comptoken_mint, get_default_comptoken_wallet, get_default_global_data, get_default_unpaid_interest_bank, get_default_unpaid_ubi_bank, get_default_user_data_account, GlobalDataAccount } from "../accounts.js";
+import { Assert } from "../assert.js";
+import { compto_program_id_pubkey, DEFAULT_DISTRIBUTION_TIME, DEFAULT_START_TIME, Instruction, SEC_PER_DAY } from "../common.js";
+
+// MAGIC NUMBER: ensure this remains consistent with constants.rs
+const MAX_DISTRIBUTION_DAYS_PER_CALL = 30n;
+
+async function test_dailyDistributionCatchUp() {
+    let comptoken_mint = get_default_comptoken_mint();
+    comptoken_mint.supply += 1n;
+    let global_data = get_default_global_data();
+    global_data.dailyDistributionData.minedSinceLastDistribution = 2n;
+    let interest_bank = get_default_unpaid_interest_bank();
+    let ubi_bank = get_default_unpaid_ubi_bank();
+    let caller_wallet = get_default_comptoken_wallet(Keypair.generate().publicKey, Keypair.generate().publicKey);
+    let caller_data = get_default_user_data_account(caller_wallet.address);
+    const context = await start(
+        [{ name: "comptoken", programId: compto_program_id_pubkey }],
+        [
+            comptoken_mint.toAccount(),
+            global_data.toAccount(),
+            interest_bank.toAccount(),
+            ubi_bank.toAccount(),
+            caller_wallet.toAccount(),
+            caller_data.toAccount(),
+        ]
+    );
+
+    const client = context.banksClient;
+    const payer = context.payer;
+    const keys = [
+        { pubkey: comptoken_mint.address, isSigner: false, isWritable: true },
+        { pubkey: global_data.address, isSigner: false, isWritable: true },
+        { pubkey: interest_bank.address, isSigner: false, isWritable: true },
+        { pubkey: ubi_bank.address, isSigner: false, isWritable: true },
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+        { pubkey: SYSVAR_SLOT_HASHES_PUBKEY, isSigner: false, isWritable: false },
+        { pubkey: caller_wallet.address, isSigner: false, isWritable: true },
+        { pubkey: caller_data.address, isSigner: false, isWritable: true },
+    ];
+
+    /**
+     * @param {bigint} time
+     * @returns {Promise<GlobalDataAccount>}
+     */
+    let slot = 1n;
+    async function distributeAt(time) {
+        // each transaction needs a new blockhash, otherwise it would be a duplicate
+        context.warpToSlot(++slot);
+        const tx = new Transaction();
+        tx.recentBlockhash = (await client.getLatestBlockhash())[0];
+        tx.add(new TransactionInstruction({ programId: compto_program_id_pubkey, keys, data: Buffer.from([Instruction.DAILY_DISTRIBUTION_EVENT]) }));
+        tx.sign(payer);
+        context.setClock(new Clock(0n, 0n, 0n, 0n, time));
+        await client.processTransaction(tx);
+        const account = await client.getAccount(global_data.address);
+        Assert.assertNotNull(account);
+        return GlobalDataAccount.fromAccountInfoBytes(global_data.address, account);
+    }
+
+    // three days have passed since the last distribution
+    let dailyDistributionData = (await distributeAt(DEFAULT_START_TIME + 3n * SEC_PER_DAY)).dailyDistributionData;
+    Assert.assertEqual(dailyDistributionData.lastDailyDistributionTime, DEFAULT_DISTRIBUTION_TIME + 3n * SEC_PER_DAY, "caught up to today");
+    Assert.assertEqual(dailyDistributionData.oldestInterest, 3n, "an interest is recorded for every missed day");
+    Assert.assert(dailyDistributionData.historicInterests[0] > 0, "the mining is attributed to the first missed day");
+    Assert.assertEqual(dailyDistributionData.historicInterests[1], 0, "no interest on days without mining");
+    Assert.assertEqual(dailyDistributionData.historicInterests[2], 0, "no interest on days without mining");
+
+    // a gap longer than a single call can process is caught up on by further calls
+    const today = DEFAULT_START_TIME + 3n * SEC_PER_DAY + (MAX_DISTRIBUTION_DAYS_PER_CALL + 5n) * SEC_PER_DAY;
+    dailyDistributionData = (await distributeAt(today)).dailyDistributionData;
+    Assert.assertEqual(
+        dailyDistributionData.lastDailyDistributionTime,
+        DEFAULT_DISTRIBUTION_TIME + (3n + MAX_DISTRIBUTION_DAYS_PER_CALL) * SEC_PER_DAY,
+        "only the maximum number of days are processed"
+    );
+    dailyDistributionData = (await distributeAt(today)).dailyDistributionData;
+    Assert.assertEqual(
+        dailyDistributionData.lastDailyDistributionTime,
+        DEFAULT_DISTRIBUTION_TIME + (3n + MAX_DISTRIBUTION_DAYS_PER_CALL + 5n) * SEC_PER_DAY,
+        "the rest of the days are processed by the next call"
+    );
+}
+
+(async () => { await test_dailyDistributionCatchUp(); })();
@@ -1,17 +1,29 @@
-import { SYSVAR_SLOT_HASHES_PUBKEY, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { Keypair, SYSVAR_SLOT_HASHES_PUBKEY, Transaction, TransactionInstruction } from "@solana/web3.js";
 import { Clock, start } from "solana-bankrun";
 
 import { TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
-import { get_default_comptoken_mint, get_default_global_data, get_default_unpaid_interest_bank, get_default_unpaid_ubi_bank, GlobalDataAccount, MintAccount, TokenAccount } from "../accounts.js";
+import { get_default_comptoken_mint, get_default_comptoken_wallet, get_default_global_data, get_default_unpaid_interest_bank, get_default_unpaid_ubi_bank, get_default_user_data_account, GlobalDataAccount, isArrayEqual, MintAccount, TokenAccount, UserDataAccount } from "../accounts.js";
 import { Assert } from "../assert.js";
-import { compto_program_id_pubkey, DEFAULT_ANNOUNCE_TIME, DEFAULT_DISTRIBUTION_TIME, DEFAULT_START_TIME, Instruction, SEC_PER_DAY } from "../common.js";
+import { CHALLENGE_SLOT_HASH_COUNT, deriveChallenge } from "../comptoken_proof.js";
+import { compto_program_id_pubkey, DEFAULT_ANNOUNCE_TIME, DEFAULT_DISTRIBUTION_TIME, DEFAULT_START_TIME, Instruction, ProofScheme, SEC_PER_DAY } from "../common.js";
 
 async function test_dailyDistributionEvent() {
     let comptoken_mint = get_default_comptoken_mint();
-    comptoken_mint.supply += 1n;
+    // supply minted other than by mining, which the distribution must ignore
+    comptoken_mint.supply += 5n;
     let global_data = get_default_global_data();
+    global_data.dailyDistributionData.minedSinceLastDistribution = 2n;
+    global_data.ledger.totalMined = 2n;
+    global_data.dailyStats.proofsSinceLastDistribution = 1n;
+    global_data.dailyStats.minersSinceLastDistribution = 1n;
+    global_data.validBlockhashes.nextProofScheme = ProofScheme.BLAKE3;
+    // the last announcement was promoted at the start of today, so the next one has yet to be derived
+    global_data.validBlockhashes.announcedBlockhash = global_data.validBlockhashes.validBlockhash;
     let interest_bank = get_default_unpaid_interest_bank();
     let ubi_bank = get_default_unpaid_ubi_bank();
+    const caller = Keypair.generate();
+    let caller_wallet = get_default_comptoken_wallet(Keypair.generate().publicKey, caller.publicKey);
+    let caller_data = get_default_user_data_account(caller_wallet.address);
     const context = await start(
         [{ name: "comptoken", programId: compto_program_id_pubkey }],
         [
@@ -19,6 +31,8 @@ async function test_dailyDistributionEvent() {
             global_data.toAccount(),
             interest_bank.toAccount(),
             ubi_bank.toAccount(),
+            caller_wallet.toAccount(),
+            caller_data.toAccount(),
         ]
     );
 
@@ -38,6 +52,10 @@ async function test_dailyDistributionEvent() {
         { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
         // program will pull a recent hash from slothashes sysvar if a new valid blockhash is needed.  
         { pubkey: SYSVAR_SLOT_HASHES_PUBKEY, isSigner: false, isWritable: false },
+        // comptoken token account that receives the distribution bounty
+        { pubkey: caller_wallet.address, isSigner: false, isWritable: true },
+        // caller's user data, settled so the bounty earns interest from today
+        { pubkey: caller_data.address, isSigner: false, isWritable: true },
     ];
 
     let data = Buffer.from([Instruction.DAILY_DISTRIBUTION_EVENT])
@@ -61,6 +79,10 @@ async function test_dailyDistributionEvent() {
     context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME + SEC_PER_DAY));
     const meta = await client.processTransaction(tx);
 
+    console.log("logMessages: %s", meta.logMessages);
+    console.log("computeUnitsConsumed: %d", meta.computeUnitsConsumed);
+    console.log("returnData: %s", meta.returnData);
+
     account = await client.getAccount(comptoken_mint.address);
     Assert.assertNotNull(account);
     const finalMint = MintAccount.fromAccountInfoBytes(comptoken_mint.address, account);
@@ -72,14 +94,26 @@ async function test_dailyDistributionEvent() {
     const validBlockhash = finalGlobalData.validBlockhashes;
     const dailyDistributionData = finalGlobalData.dailyDistributionData;
     Assert.assertEqual(validBlockhash.announcedBlockhashTime, DEFAULT_ANNOUNCE_TIME + SEC_PER_DAY, "the announced blockhash time has been updated");
-    Assert.assertNotEqual(validBlockhash.announcedBlockhash, global_data.validBlockhashes.announcedBlockhash, "announced blockhash has changed"); // TODO: can the actual blockhash be predicted/gotten?
-    Assert.assertEqual(validBlockhash.validBlockhashTime, DEFAULT_DISTRIBUTION_TIME + SEC_PER_DAY, "the valid blockhash time has been updated");
-    Assert.assertNotEqual(validBlockhash.validBlockhash, global_data.validBlockhashes.validBlockhash, "valid blockhash has changed");
+    Assert.assert(validBlockhash.announcedBlockhashSlot > 0n, "the announcement commits to the slots after it");
+    Assert.assert(isArrayEqual(validBlockhash.announcedBlockhash, global_data.validBlockhashes.announcedBlockhash), "announced blockhash is derived once the slots are produced");
+    Assert.assert(isArrayEqual(validBlockhash.validBlockhash, global_data.validBlockhashes.validBlockhash), "valid blockhash waits for the committed slots");
 
     Assert.assertEqual(dailyDistributionData.highWaterMark, 2n, "highwater mark has increased"); // TODO: find a better way to get oracle value
     Assert.assertEqual(dailyDistributionData.lastDailyDistributionTime, DEFAULT_DISTRIBUTION_TIME + SEC_PER_DAY, "last daily distribution time has updated");
     Assert.assertEqual(dailyDistributionData.yesterdaySupply, finalMint.supply, "yesterdays supply is where the mint is after");
     Assert.assertEqual(dailyDistributionData.oldestInterest, global_data.dailyDistributionData.oldestInterest + 1n, "oldest interests has increased");
+    Assert.assertEqual(dailyDistributionData.minedSinceLastDistribution, 0n, "the mining counter is reset");
+    const dailyStats = finalGlobalData.dailyStats;
+    Assert.assertEqual(dailyStats.oldestStats, 1n, "the day's stats were recorded");
+    const stats = dailyStats.newest();
+    Assert.assertEqual(stats.day, DEFAULT_DISTRIBUTION_TIME, "the stats are for the day that ended");
+    Assert.assertEqual(stats.proofsAccepted, 1n, "proofs accepted");
+    Assert.assertEqual(stats.uniqueMiners, 1n, "unique miners");
+    Assert.assertEqual(stats.tokensMined, 2n, "tokens mined");
+    Assert.assertEqual(stats.highWaterMark, dailyDistributionData.highWaterMark, "high water mark");
+    Assert.assertEqual(stats.supply, dailyDistributionData.yesterdaySupply, "supply");
+    Assert.assert(isArrayEqual(stats.validBlockhash, global_data.validBlockhashes.validBlockhash), "the day's valid blockhash");
+    Assert.assertEqual(dailyStats.proofsSinceLastDistribution, 0n, "the proof counter is reset");
 
     account = await client.getAccount(interest_bank.address);
     Assert.assertNotNull(account);
@@ -90,6 +124,55 @@ async function test_dailyDistributionEvent() {
     Assert.assertNotNull(account);
     const finalUbiBank = TokenAccount.fromAccountInfoBytes(ubi_bank.address, account);
     Assert.assert(finalUbiBank.amount > ubi_bank.amount, "interest bank has increased");
+
+    account = await client.getAccount(caller_wallet.address);
+    Assert.assertNotNull(account);
+    const finalCallerWallet = TokenAccount.fromAccountInfoBytes(caller_wallet.address, account);
+    Assert.assertEqual(finalCallerWallet.amount, caller_wallet.amount + global_data.config.distributionBounty, "caller has received the bounty");
+    account = await client.getAccount(caller_data.address);
+    Assert.assertNotNull(account);
+    const finalCallerData = UserDataAccount.fromAccountInfoBytes(caller_data.address, account);
+    Assert.assertEqual(finalCallerData.lastSettledBalance, finalCallerWallet.amount, "the bounty earns interest from today");
+    Assert.assertEqual(finalInterestBank.amount, finalUbiBank.amount, "interest and ubi are distributed evenly");
+
+    // the first update once the committed slots are produced derives the announced blockhash and makes it valid
+    const firstSlot = validBlockhash.announcedBlockhashSlot;
+    for (let slot = firstSlot; slot <= firstSlot + BigInt(CHALLENGE_SLOT_HASH_COUNT); ++slot) {
+        context.warpToSlot(slot);
+    }
+    const updateKeys = [
+        { pubkey: global_data.address, isSigner: false, isWritable: true },
+        { pubkey: SYSVAR_SLOT_HASHES_PUBKEY, isSigner: false, isWritable: false },
+    ];
+    const updateTx = new Transaction();
+    updateTx.recentBlockhash = (await client.getLatestBlockhash())[0];
+    updateTx.add(new TransactionInstruction({ programId: compto_program_id_pubkey, keys: updateKeys, data: Buffer.from([Instruction.GET_VALID_BLOCKHASHES]) }));
+    updateTx.sign(payer);
+    await client.processTransaction(updateTx);
+
+    const slotHashes = await client.getAccount(SYSVAR_SLOT_HASHES_PUBKEY);
+    Assert.assertNotNull(slotHashes);
+    const challenge = deriveChallenge(global_data.validBlockhashes.announcedBlockhash, slotHashes.data, firstSlot);
+    Assert.assertNotNull(challenge, "the committed slots have been produced");
+    account = await client.getAccount(global_data.address);
+    Assert.assertNotNull(account);
+    const promoted = GlobalDataAccount.fromAccountInfoBytes(global_data.address, account).validBlockhashes;
+    Assert.assert(challenge.equals(Buffer.from(promoted.validBlockhash)), "valid blockhash is derived from the committed slot hashes");
+    Assert.assert(isArrayEqual(promoted.announcedBlockhash, promoted.validBlockhash), "the announced blockhash is the one promoted");
+    Assert.assertEqual(promoted.validBlockhashTime, DEFAULT_DISTRIBUTION_TIME + SEC_PER_DAY, "the valid blockhash time has been updated");
+    Assert.assertEqual(promoted.validProofScheme, ProofScheme.BLAKE3, "the next proof scheme is valid after the rollover");
+    Assert.assertEqual(promoted.previousProofScheme, ProofScheme.SHA256, "the previous blockhash keeps its proof scheme");
+    Assert.assertEqual(
+        finalMint.supply,
+        comptoken_mint.supply + finalInterestBank.amount + finalUbiBank.amount + finalCallerWallet.amount,
+        "bounty comes out of the distribution"
+    );
+
+    const ledger = finalGlobalData.ledger;
+    Assert.assertEqual(ledger.totalMined, global_data.ledger.totalMined, "distributing doesn't count as mining");
+    Assert.assertEqual(ledger.totalInterestDistributed, finalInterestBank.amount, "interest is recorded in the ledger");
+    Assert.assertEqual(ledger.totalUbiDistributed, finalUbiBank.amount, "UBI is recorded in the ledger");
+    Assert.assertEqual(ledger.totalBountyDistributed, finalCallerWallet.amount, "the bounty is recorded in the ledger");
 }
 
 (async () => { await test_dailyDistributionEvent(); })();
@@ -0,0 +1,50 @@
+import { Transaction, TransactionInstruction } from "@solana/web3.js";
+import { Clock, start } from "solana-bankrun";
+
+import { get_default_global_data } from "../accounts.js";
+import { Assert } from "../assert.js";
+import { compto_program_id_pubkey, DEFAULT_DISTRIBUTION_TIME, DEFAULT_START_TIME, Instruction, SEC_PER_DAY } from "../common.js";
+
+async function test_getDistributionStatus() {
+    let global_data = get_default_global_data();
+    const context = await start(
+        [{ name: "comptoken", programId: compto_program_id_pubkey }],
+        [global_data.toAccount()]
+    );
+
+    const client = context.banksClient;
+    const payer = context.payer;
+    const blockhash = context.lastBlockhash;
+    const keys = [
+        // stores information for/from the daily distribution
+        { pubkey: global_data.address, isSigner: false, isWritable: false },
+    ];
+
+    const ixs = [new TransactionInstruction({ programId: compto_program_id_pubkey, keys, data: Buffer.from([Instruction.GET_DISTRIBUTION_STATUS]) })];
+    const tx = new Transaction();
+    tx.recentBlockhash = blockhash;
+    tx.add(...ixs);
+    tx.sign(payer);
+
+    /**
+     * @param {Uint8Array} data
+     */
+    function decodeStatus(data) {
+        const dataView = new DataView(data.buffer.slice(data.byteOffset));
+        return { isDue: data[0] !== 0, nextDistributionTime: dataView.getBigInt64(1, true), bounty: dataView.getBigUint64(9, true) };
+    }
+
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME));
+    let result = await client.simulateTransaction(tx);
+    let status = decodeStatus(result.meta.returnData.data);
+    Assert.assert(!status.isDue, "distribution is not due on the same day");
+    Assert.assertEqual(status.nextDistributionTime, DEFAULT_DISTRIBUTION_TIME + SEC_PER_DAY, "next distribution time");
+    Assert.assertEqual(status.bounty, global_data.config.distributionBounty, "distribution bounty");
+
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME + SEC_PER_DAY));
+    result = await client.simulateTransaction(tx);
+    status = decodeStatus(result.meta.returnData.data);
+    Assert.assert(status.isDue, "distribution is due the next day");
+}
+
+(async () => { await test_getDistributionStatus(); })();
@@ -10,8 +10,11 @@ import {
     get_default_unpaid_interest_bank,
     get_default_unpaid_ubi_bank,
     get_default_user_data_account,
+    get_default_user_identity_account,
+    GlobalDataAccount,
     TokenAccount,
-    UserDataAccount
+    UserDataAccount,
+    UserIdentityAccount
 } from "../accounts.js";
 import { Assert } from "../assert.js";
 import {
@@ -29,9 +32,12 @@ async function test_getOwedComptokens() {
     comptoken_mint.supply = 292_004n
     let user_wallet = get_default_comptoken_wallet(testuser_comptoken_wallet_pubkey, PublicKey.unique());
     user_wallet.amount = 2n;
-    let user_data_account_address = PublicKey.findProgramAddressSync([user_wallet.address.toBytes()], compto_program_id_pubkey)[0];
-    let user_data = get_default_user_data_account(user_data_account_address);
+    let user_identity = get_default_user_identity_account(user_wallet.nominalOwner);
+    user_identity.isVerifiedHuman = true;
+    user_identity.lastUbiPayoutDate = DEFAULT_DISTRIBUTION_TIME - SEC_PER_DAY;
+    let user_data = get_default_user_data_account(user_wallet.address, user_identity.address);
     user_data.lastInterestPayoutDate = DEFAULT_DISTRIBUTION_TIME - SEC_PER_DAY;
+    user_data.lastSettledBalance = 2n;
     let global_data = get_default_global_data();
     global_data.dailyDistributionData.historicInterests[0] = 0.5;
     global_data.dailyDistributionData.oldestInterest = 1n;
@@ -49,6 +55,7 @@ async function test_getOwedComptokens() {
         ],
         [
             user_data.toAccount(),
+            user_identity.toAccount(),
             user_wallet.toAccount(),
             comptoken_mint.toAccount(),
             global_data.toAccount(),
@@ -67,8 +74,8 @@ async function test_getOwedComptokens() {
         { pubkey: user_wallet.address, isSigner: false, isWritable: true },
         //  Comptoken Mint lets the token program know what kind of token to move
         { pubkey: comptoken_mint.address, isSigner: false, isWritable: false },
-        //  Comptoken Global Data (also mint authority) stores interest data
-        { pubkey: global_data.address, isSigner: false, isWritable: false },
+        //  Comptoken Global Data (also mint authority) stores interest data and records the claim
+        { pubkey: global_data.address, isSigner: false, isWritable: true },
         //  Comptoken Interest Bank stores comptokens owed for interest
         { pubkey: interest_bank.address, isSigner: false, isWritable: true },
         //  Comptoken UBI Bank stores comptokens owed for UBI
@@ -82,9 +89,11 @@ async function test_getOwedComptokens() {
         //  needed by the transfer hook program
         { pubkey: compto_program_id_pubkey, isSigner: false, isWritable: false },
         //  needed by the transfer hook program (doesn't really exist)
-        { pubkey: PublicKey.findProgramAddressSync([interest_bank.address.toBytes()], compto_program_id_pubkey)[0], isSigner: false, isWritable: false },
+        { pubkey: PublicKey.findProgramAddressSync([interest_bank.address.toBytes()], compto_program_id_pubkey)[0], isSigner: false, isWritable: true },
         //  needed by the transfer hook program (doesn't really exist)
-        { pubkey: PublicKey.findProgramAddressSync([ubi_bank.address.toBytes()], compto_program_id_pubkey)[0], isSigner: false, isWritable: false },
+        { pubkey: PublicKey.findProgramAddressSync([ubi_bank.address.toBytes()], compto_program_id_pubkey)[0], isSigner: false, isWritable: true },
+        //  User's Identity stores when UBI was last paid to any of the owner's wallets
+        { pubkey: user_identity.address, isSigner: false, isWritable: true },
     ];
 
     let data = Buffer.from([Instruction.GET_OWED_COMPTOKENS]);
@@ -110,6 +119,18 @@ async function test_getOwedComptokens() {
     Assert.assertNotNull(account);
     let finalUserData = UserDataAccount.fromAccountInfoBytes(user_data.address, account);
     Assert.assertEqual(finalUserData.lastInterestPayoutDate, DEFAULT_DISTRIBUTION_TIME, "last interest payout date updated");
+    Assert.assertEqual(finalUserData.lastSettledBalance, 3n, "the paid interest is part of the settled balance");
+    Assert.assertEqual(finalUserData.accruedInterest, 0n, "accrued interest is paid out");
+
+    account = await client.getAccount(user_identity.address);
+    Assert.assertNotNull(account);
+    let finalUserIdentity = UserIdentityAccount.fromAccountInfoBytes(user_identity.address, account);
+    Assert.assertEqual(finalUserIdentity.lastUbiPayoutDate, DEFAULT_DISTRIBUTION_TIME, "last ubi payout date updated");
+
+    account = await client.getAccount(global_data.address);
+    Assert.assertNotNull(account);
+    let finalGlobalData = GlobalDataAccount.fromAccountInfoBytes(global_data.address, account);
+    Assert.assertEqual(finalGlobalData.ledger.totalInterestClaimed, 1n, "claimed interest is recorded");
 }
 
 (async () => { await test_getOwedComptokens(); })();
@@ -1,9 +1,12 @@
 import { SYSVAR_SLOT_HASHES_PUBKEY, Transaction, TransactionInstruction } from "@solana/web3.js";
 import { Clock, start } from "solana-bankrun";
 
-import { get_default_comptoken_mint, get_default_global_data } from "../accounts.js";
+import { get_default_comptoken_mint, get_default_global_data, GlobalDataAccount } from "../accounts.js";
 import { Assert } from "../assert.js";
-import { compto_program_id_pubkey, DEFAULT_START_TIME, global_data_account_pubkey, Instruction } from "../common.js";
+import {
+    compto_program_id_pubkey, DEFAULT_DISTRIBUTION_TIME, DEFAULT_START_TIME, global_data_account_pubkey, Instruction, SEC_PER_DAY
+} from "../common.js";
+import { CHALLENGE_SLOT_HASH_COUNT, deriveChallenge } from "../comptoken_proof.js";
 
 async function test_getValidBlockhashes() {
     let globalData = get_default_global_data();
@@ -34,9 +37,51 @@ async function test_getValidBlockhashes() {
     tx.sign(payer);
     context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME));
     const meta = await client.processTransaction(tx);
+
+    console.log("logMessages: %s", meta.logMessages);
+    console.log("computeUnitsConsumed: %d", meta.computeUnitsConsumed);
+    console.log("returnData: %s", meta.returnData);
+
     const validBlockHashes = { current_block: meta.returnData.data.slice(0, 32), announced_block: meta.returnData.data.slice(32, 64), };
     Assert.assert(validBlockHashes.announced_block.every((v, i) => v === globalData.validBlockhashes.announcedBlockhash[i]), "announced blockhash is globalData default");
     Assert.assert(validBlockHashes.current_block.every((v, i) => v === globalData.validBlockhashes.validBlockhash[i]), "valid blockhash is globalData default");
+    Assert.assertEqual(meta.returnData.data[64], globalData.validBlockhashes.validProofScheme, "valid proof scheme is globalData default");
+    Assert.assertEqual(meta.returnData.data[65], globalData.validBlockhashes.nextProofScheme, "next proof scheme is globalData default");
+
+    // the next blockhash is announced shortly before the day changes, once the last announcement has been promoted
+    globalData.validBlockhashes.announcedBlockhash = globalData.validBlockhashes.validBlockhash;
+    const globalDataAccount = globalData.toAccount();
+    context.setAccount(globalDataAccount.address, globalDataAccount.info);
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_DISTRIBUTION_TIME + SEC_PER_DAY - 60n));
+    /**
+     * @returns {Promise<import("solana-bankrun").BanksTransactionMeta>}
+     */
+    async function getValidBlockhashes() {
+        const tx = new Transaction();
+        tx.recentBlockhash = (await client.getLatestBlockhash())[0];
+        tx.add(...ixs);
+        tx.sign(payer);
+        return await client.processTransaction(tx);
+    }
+    let slot = (await client.getSlot()) + 1n;
+    context.warpToSlot(slot);
+    await getValidBlockhashes();
+    let account = await client.getAccount(global_data_account_pubkey);
+    Assert.assertNotNull(account);
+    const firstSlot = GlobalDataAccount.fromAccountInfoBytes(global_data_account_pubkey, account).validBlockhashes.announcedBlockhashSlot;
+
+    // and derived by the first update once the slots it committed to are produced, before it becomes valid
+    for (slot = firstSlot; slot <= firstSlot + BigInt(CHALLENGE_SLOT_HASH_COUNT); ++slot) {
+        context.warpToSlot(slot);
+    }
+    const announcedMeta = await getValidBlockhashes();
+    const slotHashes = await client.getAccount(SYSVAR_SLOT_HASHES_PUBKEY);
+    Assert.assertNotNull(slotHashes);
+    const challenge = deriveChallenge(globalData.validBlockhashes.validBlockhash, slotHashes.data, firstSlot);
+    Assert.assertNotNull(challenge, "the committed slots have been produced");
+    const returned = announcedMeta.returnData.data;
+    Assert.assert(returned.slice(0, 32).every((v, i) => v === globalData.validBlockhashes.validBlockhash[i]), "valid blockhash is unchanged until the day does");
+    Assert.assert(challenge.equals(Buffer.from(returned.slice(32, 64))), "the next blockhash is returned in advance");
 }
 
 (async () => { await test_getValidBlockhashes(); })();
\ No newline at end of file
@@ -1,18 +1,23 @@
 import { AccountState, TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
-import { SystemProgram, SYSVAR_SLOT_HASHES_PUBKEY, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { PublicKey, SystemProgram, SYSVAR_SLOT_HASHES_PUBKEY, Transaction, TransactionInstruction } from "@solana/web3.js";
 import { Clock, start } from "solana-bankrun";
 
-import { get_default_comptoken_mint, GlobalDataAccount, TokenAccount, } from "../accounts.js";
+import { ExtensionType, ExtraAccountMetaAccount, get_default_instance, GlobalDataAccount, MintAccount, stringAsLEBytes, TokenAccount, TokenMetadata, } from "../accounts.js";
 import { Assert } from "../assert.js";
 import {
-    compto_program_id_pubkey, comptoken_mint_pubkey, DEFAULT_ANNOUNCE_TIME, DEFAULT_DISTRIBUTION_TIME, DEFAULT_START_TIME,
-    global_data_account_pubkey, Instruction, interest_bank_account_pubkey, ubi_bank_account_pubkey
+    compto_extra_account_metas_account_pubkey, compto_program_id_pubkey, compto_transfer_hook_id_pubkey, comptoken_mint_keypair, comptoken_mint_pubkey, DEFAULT_ANNOUNCE_TIME,
+    DEFAULT_BLOCKHASH_GRACE_PERIOD, DEFAULT_DISTRIBUTION_BOUNTY, DEFAULT_DISTRIBUTION_TIME, DEFAULT_NAME, DEFAULT_START_TIME, DEFAULT_SYMBOL, DEFAULT_URI,
+    DEFAULT_DAY_LENGTH, DEFAULT_DAY_OFFSET, DEFAULT_V1_PROOF_DEADLINE, global_data_account_pubkey, Instruction, interest_bank_account_pubkey, MINT_DECIMALS, ubi_bank_account_pubkey
 } from "../common.js";
 
 async function initialize_comptoken_program() {
+    // the mint is created by the program, so it isn't added here
     const context = await start(
-        [{ name: "comptoken", programId: compto_program_id_pubkey }],
-        [get_default_comptoken_mint().toAccount()]
+        [
+            { name: "comptoken", programId: compto_program_id_pubkey },
+            { name: "comptoken_transfer_hook", programId: compto_transfer_hook_id_pubkey },
+        ],
+        []
     );
 
     const client = context.banksClient;
@@ -28,34 +33,45 @@ async function initialize_comptoken_program() {
         { pubkey: interest_bank_account_pubkey, isSigner: false, isWritable: true },
         // the address of the ubi bank account to be created
         { pubkey: ubi_bank_account_pubkey, isSigner: false, isWritable: true },
-        // the comptoken mint account
-        { pubkey: comptoken_mint_pubkey, isSigner: false, isWritable: false },
+        // the comptoken mint to be created, a new keypair which keys the rest of the instance
+        { pubkey: comptoken_mint_pubkey, isSigner: true, isWritable: true },
         // needed because compto program interacts with the system program to create the account
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
         // the token program that will mint the tokens when instructed by the mint authority
         { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
         // program will pull a recent hash from slothashes sysvar if a new valid blockhash is needed.
         { pubkey: SYSVAR_SLOT_HASHES_PUBKEY, isSigner: false, isWritable: false },
+        // the transfer hook program, which stores the extra account metas for transfers
+        { pubkey: compto_transfer_hook_id_pubkey, isSigner: false, isWritable: false },
+        // the address of the extra account metas account to be created
+        { pubkey: compto_extra_account_metas_account_pubkey, isSigner: false, isWritable: true },
     ];
 
     // MAGIC NUMBER: CHANGE NEEDS TO BE REFLECTED IN comptoken.rs
-    const GLOBAL_DATA_SIZE = 3032n;
+    const GLOBAL_DATA_SIZE = 9520n;
     const globalDataRentExemptAmount = await rent.minimumBalance(GLOBAL_DATA_SIZE);
-    const interestBankRentExemptAmount = await rent.minimumBalance(256n);
-    const ubiBankRentExemptAmount = await rent.minimumBalance(256n);
-    console.log("Rent exempt amount: ", globalDataRentExemptAmount);
-    // 1 byte for instruction 3 x 8 bytes for rent exemptions
-    let data = Buffer.alloc(25);
+    // 1 byte for instruction 8 bytes for the distribution bounty 8 bytes for the blockhash grace period
+    // 8 bytes for the v1 proof deadline 8 bytes for the day length 8 bytes for the day offset
+    // followed by the name, symbol, and uri of the token metadata
+    let data = Buffer.alloc(41);
     data.writeUInt8(Instruction.INITIALIZE_STATIC_ACCOUNT, 0);
-    data.writeBigInt64LE(globalDataRentExemptAmount, 1);
-    data.writeBigInt64LE(interestBankRentExemptAmount, 9);
-    data.writeBigInt64LE(ubiBankRentExemptAmount, 17);
+    data.writeBigUInt64LE(DEFAULT_DISTRIBUTION_BOUNTY, 1);
+    data.writeBigInt64LE(DEFAULT_BLOCKHASH_GRACE_PERIOD, 9);
+    data.writeBigInt64LE(DEFAULT_V1_PROOF_DEADLINE, 17);
+    data.writeBigInt64LE(DEFAULT_DAY_LENGTH, 25);
+    data.writeBigInt64LE(DEFAULT_DAY_OFFSET, 33);
+    data = Buffer.concat([
+        data,
+        Buffer.from(stringAsLEBytes(DEFAULT_NAME)),
+        Buffer.from(stringAsLEBytes(DEFAULT_SYMBOL)),
+        Buffer.from(stringAsLEBytes(DEFAULT_URI)),
+    ]);
 
     const ixs = [new TransactionInstruction({ programId: compto_program_id_pubkey, keys, data })];
     const tx = new Transaction();
     tx.recentBlockhash = blockhash;
     tx.add(...ixs);
-    tx.sign(payer);
+    tx.sign(payer, comptoken_mint_keypair);
     context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME));
     const meta = await client.processTransaction(tx);
 
@@ -65,12 +81,24 @@ async function initialize_comptoken_program() {
 
     let account = await client.getAccount(global_data_account_pubkey);
     Assert.assertNotNull(account);
+    Assert.assertEqual(account.lamports, globalDataRentExemptAmount, "global data is rent exempt");
     const finalGlobalData = GlobalDataAccount.fromAccountInfoBytes(global_data_account_pubkey, account);
+    Assert.assertEqual(finalGlobalData.config.distributionBounty, DEFAULT_DISTRIBUTION_BOUNTY, "distribution bounty");
+    Assert.assertEqual(finalGlobalData.config.blockhashGracePeriod, DEFAULT_BLOCKHASH_GRACE_PERIOD, "blockhash grace period");
+    Assert.assert(finalGlobalData.config.admin.equals(payer.publicKey), "the payer is the admin");
+    Assert.assertEqual(finalGlobalData.config.v1ProofDeadline, DEFAULT_V1_PROOF_DEADLINE, "v1 proof deadline");
+    Assert.assertEqual(finalGlobalData.config.days.length, DEFAULT_DAY_LENGTH, "day length");
+    Assert.assertEqual(finalGlobalData.config.days.offset, DEFAULT_DAY_OFFSET, "day offset");
+    // the config records the instance's addresses and bumps so they can be verified at runtime
+    Assert.assertEqual(JSON.stringify(finalGlobalData.config.instance), JSON.stringify(get_default_instance()), "instance config");
     Assert.assertEqual(finalGlobalData.validBlockhashes.announcedBlockhashTime, DEFAULT_ANNOUNCE_TIME, "announced blockhash time");
-    Assert.assertEqual(finalGlobalData.validBlockhashes.validBlockhashTime, DEFAULT_DISTRIBUTION_TIME, "valid blockhash time");
+    Assert.assert(finalGlobalData.validBlockhashes.announcedBlockhashSlot > 0n, "the first blockhash commits to the slots after initialization");
+    Assert.assertEqual(finalGlobalData.validBlockhashes.validBlockhashTime, 0n, "the first blockhash is valid once those slots are produced");
 
     account = await client.getAccount(interest_bank_account_pubkey);
     Assert.assertNotNull(account);
+    const bankRentExemptAmount = await rent.minimumBalance(BigInt(account.data.length));
+    Assert.assertEqual(account.lamports, bankRentExemptAmount, "interest bank is rent exempt");
     const finalInterestBank = TokenAccount.fromAccountInfoBytes(interest_bank_account_pubkey, account);
     Assert.assertEqual(finalInterestBank.amount, 0n, "interest amount");
     Assert.assert(finalInterestBank.mint.equals(comptoken_mint_pubkey), "interest mint");
@@ -79,11 +107,44 @@ async function initialize_comptoken_program() {
 
     account = await client.getAccount(ubi_bank_account_pubkey);
     Assert.assertNotNull(account);
+    Assert.assertEqual(account.lamports, bankRentExemptAmount, "ubi bank is rent exempt");
     const finalUBIBank = TokenAccount.fromAccountInfoBytes(ubi_bank_account_pubkey, account);
     Assert.assertEqual(finalUBIBank.amount, 0n, "ubi amount");
     Assert.assert(finalUBIBank.mint.equals(comptoken_mint_pubkey), "ubi mint");
     Assert.assert(finalUBIBank.nominalOwner.equals(global_data_account_pubkey), "ubi owner");
     Assert.assertEqual(finalUBIBank.state, AccountState.Initialized, "ubi state");
+
+    account = await client.getAccount(comptoken_mint_pubkey);
+    Assert.assertNotNull(account);
+    Assert.assert(account.owner.equals(TOKEN_2022_PROGRAM_ID), "mint is owned by token 2022");
+    Assert.assertEqual(account.lamports, await rent.minimumBalance(BigInt(account.data.length)), "mint is rent exempt");
+    const finalMint = MintAccount.fromAccountInfoBytes(comptoken_mint_pubkey, account);
+    Assert.assertEqual(finalMint.supply, 0n, "mint supply");
+    Assert.assertEqual(finalMint.decimals, MINT_DECIMALS, "mint decimals");
+    Assert.assert(finalMint.mintAuthority.equals(global_data_account_pubkey), "global data is the mint authority");
+    Assert.assertEqual(finalMint.freezeAuthority, null, "mint has no freeze authority");
+    const getExtension = (type) => {
+        const extension = finalMint.extensions.find((extension) => extension.type === type);
+        Assert.assertNotNull(extension);
+        return extension.value;
+    };
+    const transferHook = getExtension(ExtensionType.TransferHook);
+    Assert.assert(new PublicKey(transferHook.subarray(0, 32)).equals(global_data_account_pubkey), "transfer hook authority");
+    Assert.assert(new PublicKey(transferHook.subarray(32, 64)).equals(compto_transfer_hook_id_pubkey), "transfer hook program");
+    const metadataPointer = getExtension(ExtensionType.MetadataPointer);
+    Assert.assert(new PublicKey(metadataPointer.subarray(0, 32)).equals(global_data_account_pubkey), "metadata pointer authority");
+    Assert.assert(new PublicKey(metadataPointer.subarray(32, 64)).equals(comptoken_mint_pubkey), "metadata lives in the mint");
+    const metadata = TokenMetadata.fromBytes(getExtension(ExtensionType.TokenMetadata));
+    Assert.assert(metadata.updateAuthority.equals(global_data_account_pubkey), "metadata update authority");
+    Assert.assert(metadata.mint.equals(comptoken_mint_pubkey), "metadata mint");
+    Assert.assertEqual(metadata.name, DEFAULT_NAME, "metadata name");
+    Assert.assertEqual(metadata.symbol, DEFAULT_SYMBOL, "metadata symbol");
+    Assert.assertEqual(metadata.uri, DEFAULT_URI, "metadata uri");
+
+    account = await client.getAccount(compto_extra_account_metas_account_pubkey);
+    Assert.assertNotNull(account);
+    const finalExtraAccountMetas = ExtraAccountMetaAccount.fromAccountInfoBytes(compto_extra_account_metas_account_pubkey, account);
+    Assert.assertEqual(finalExtraAccountMetas.extraAccountMetas.length, 4, "extra account metas");
 }
 
 (async () => { await initialize_comptoken_program(); })();
@@ -30,17 +30,25 @@ async function test_mint() {
         // the token program that will mint the tokens when instructed by the mint authority
         { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
     ];
-    const ixs = [new TransactionInstruction({ programId: compto_program_id_pubkey, keys, data: Buffer.from([Instruction.TEST]) })];
+    const data = Buffer.alloc(9);
+    data.writeUInt8(Instruction.TEST, 0);
+    data.writeBigUInt64LE(1_000_000n, 1);
+    const ixs = [new TransactionInstruction({ programId: compto_program_id_pubkey, keys, data })];
     const tx = new Transaction();
     tx.recentBlockhash = blockhash;
     tx.add(...ixs);
     tx.sign(payer);
     const meta = await client.processTransaction(tx);
+
+    console.log("logMessages: %s", meta.logMessages);
+    console.log("computeUnitsConsumed: %d", meta.computeUnitsConsumed);
+    console.log("returnData: %s", meta.returnData);
+
     const rawAccount = await client.getAccount(user_wallet_before.address);
     Assert.assertNotNull(rawAccount);
     const user_wallet_after = AccountLayout.decode(rawAccount?.data);
     Assert.assertEqual(
-        user_wallet_before.amount + 2n, // MAGIC NUMBER: ensure it remains consistent with comptoken.rs
+        user_wallet_before.amount + 1_000_000n,
         user_wallet_after.amount
     );
 }
@@ -0,0 +1,70 @@
+import { PublicKey, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { Clock, start } from "solana-bankrun";
+
+import {
+    get_default_comptoken_wallet, get_default_global_data, get_default_user_data_account,
+    get_default_user_identity_account, UserDataAccount,
+} from "../accounts.js";
+import { Assert } from "../assert.js";
+import {
+    compto_program_id_pubkey, DEFAULT_DISTRIBUTION_TIME, DEFAULT_START_TIME, Instruction, SEC_PER_DAY,
+    testuser_comptoken_wallet_pubkey,
+} from "../common.js";
+
+async function test_previewOwedComptokens() {
+    let user_wallet = get_default_comptoken_wallet(testuser_comptoken_wallet_pubkey, PublicKey.unique());
+    // 2 comptokens were held since the last settlement, the other 8 arrived after it
+    user_wallet.amount = 10n;
+    let user_identity = get_default_user_identity_account(user_wallet.nominalOwner);
+    user_identity.isVerifiedHuman = true;
+    user_identity.lastUbiPayoutDate = DEFAULT_DISTRIBUTION_TIME - SEC_PER_DAY;
+    let user_data = get_default_user_data_account(user_wallet.address, user_identity.address);
+    user_data.lastInterestPayoutDate = DEFAULT_DISTRIBUTION_TIME - SEC_PER_DAY;
+    user_data.lastSettledBalance = 2n;
+    user_data.accruedInterest = 3n;
+    let global_data = get_default_global_data();
+    global_data.dailyDistributionData.historicInterests[0] = 0.5;
+    global_data.dailyDistributionData.oldestInterest = 1n;
+
+    const context = await start(
+        [{ name: "comptoken", programId: compto_program_id_pubkey }],
+        [user_data.toAccount(), user_identity.toAccount(), user_wallet.toAccount(), global_data.toAccount()]
+    );
+    const client = context.banksClient;
+    const payer = context.payer;
+    const blockhash = context.lastBlockhash;
+    const keys = [
+        //  User's Data Account stores the settled balance and when interest was last paid
+        { pubkey: user_data.address, isSigner: false, isWritable: false },
+        //  User's Comptoken Wallet holds the current balance
+        { pubkey: user_wallet.address, isSigner: false, isWritable: false },
+        //  Comptoken Global Data stores interest data
+        { pubkey: global_data.address, isSigner: false, isWritable: false },
+        //  User's Identity stores when UBI was last paid to any of the owner's wallets
+        { pubkey: user_identity.address, isSigner: false, isWritable: false },
+    ];
+
+    const ixs = [new TransactionInstruction({ programId: compto_program_id_pubkey, keys, data: Buffer.from([Instruction.PREVIEW_OWED_COMPTOKENS]) })];
+    const tx = new Transaction();
+    tx.recentBlockhash = blockhash;
+    tx.add(...ixs);
+    tx.sign(payer);
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME));
+    const result = await client.simulateTransaction(tx);
+    console.log("computeUnitsConsumed: %d", result.meta.computeUnitsConsumed);
+
+    const data = result.meta.returnData.data;
+    const dataView = new DataView(data.buffer.slice(data.byteOffset));
+    // 3 already accrued, and 50% interest on the 2 held for the whole day
+    Assert.assertEqual(dataView.getBigUint64(0, true), 4n, "owed interest");
+    Assert.assertEqual(dataView.getBigUint64(8, true), 0n, "owed ubi");
+
+    // previewing changes nothing
+    const account = await client.getAccount(user_data.address);
+    Assert.assertNotNull(account);
+    const finalUserData = UserDataAccount.fromAccountInfoBytes(user_data.address, account);
+    Assert.assertEqual(finalUserData.lastInterestPayoutDate, user_data.lastInterestPayoutDate, "last interest payout date unchanged");
+    Assert.assertEqual(finalUserData.accruedInterest, 3n, "accrued interest unchanged");
+}
+
+(async () => { await test_previewOwedComptokens(); })();
@@ -0,0 +1,103 @@
+import { TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
+import { PublicKey, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { Clock, start } from "solana-bankrun";
+
+import {
+    get_default_comptoken_mint, get_default_comptoken_wallet, get_default_global_data, get_default_user_data_account,
+    isArrayEqual, UserDataAccount, ValidBlockhashes,
+} from "../accounts.js";
+import { Assert } from "../assert.js";
+import { compto_program_id_pubkey, DEFAULT_DISTRIBUTION_TIME, Instruction, SEC_PER_DAY, testuser_comptoken_wallet_pubkey } from "../common.js";
+import { ComptokenProof } from "../comptoken_proof.js";
+
+async function test_proofGracePeriod() {
+    let global_data_account = get_default_global_data();
+    const previousBlockhash = Uint8Array.from({ length: 32 }, (v, i) => 3 * i);
+    // the valid blockhash changed at the start of today
+    global_data_account.validBlockhashes = new ValidBlockhashes(
+        { blockhash: global_data_account.validBlockhashes.announcedBlockhash, time: global_data_account.validBlockhashes.announcedBlockhashTime },
+        { blockhash: global_data_account.validBlockhashes.validBlockhash, time: DEFAULT_DISTRIBUTION_TIME },
+        { blockhash: previousBlockhash, time: DEFAULT_DISTRIBUTION_TIME - SEC_PER_DAY },
+    );
+    const gracePeriod = global_data_account.config.blockhashGracePeriod;
+    let mint_account = get_default_comptoken_mint();
+    let destination_comptoken_wallet = get_default_comptoken_wallet(testuser_comptoken_wallet_pubkey, PublicKey.unique());
+    let user_data_account = get_default_user_data_account(destination_comptoken_wallet.address);
+
+    const context = await start(
+        [{ name: "comptoken", programId: compto_program_id_pubkey }],
+        [
+            mint_account.toAccount(),
+            global_data_account.toAccount(),
+            destination_comptoken_wallet.toAccount(),
+            user_data_account.toAccount(),
+        ]
+    );
+
+    const client = context.banksClient;
+    const payer = context.payer;
+    const keys = [
+        { pubkey: mint_account.address, isSigner: false, isWritable: true },
+        { pubkey: destination_comptoken_wallet.address, isSigner: false, isWritable: true },
+        { pubkey: global_data_account.address, isSigner: false, isWritable: true },
+        { pubkey: user_data_account.address, isSigner: false, isWritable: true },
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+    ];
+
+    let slot = 1n;
+    /**
+     * @param {Uint8Array} blockhash
+     * @returns {Promise<{ proof: ComptokenProof, tx: Transaction }>}
+     */
+    async function makeSubmission(blockhash) {
+        let proof = new ComptokenProof(destination_comptoken_wallet.address, blockhash);
+        proof.mine();
+        const data = Buffer.concat([Buffer.from([Instruction.COMPTOKEN_MINT]), proof.serializeData()]);
+        // each transaction needs a new blockhash, otherwise it would be a duplicate
+        context.warpToSlot(++slot);
+        const tx = new Transaction();
+        tx.recentBlockhash = (await client.getLatestBlockhash())[0];
+        tx.add(new TransactionInstruction({ programId: compto_program_id_pubkey, keys, data }));
+        tx.sign(payer);
+        return { proof, tx };
+    }
+
+    async function getUserData() {
+        const account = await client.getAccount(user_data_account.address);
+        Assert.assertNotNull(account);
+        return UserDataAccount.fromAccountInfoBytes(user_data_account.address, account);
+    }
+
+    // a proof against the previous blockhash is accepted during the grace period
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_DISTRIBUTION_TIME + gracePeriod / 2n));
+    let { proof, tx } = await makeSubmission(previousBlockhash);
+    await client.processTransaction(tx);
+    let userData = await getUserData();
+    // the proofs are kept with the global blockhash they were mined against, whatever order they arrive in
+    Assert.assert(isArrayEqual(userData.previousBlockhash, previousBlockhash), "proof against the previous blockhash is stored");
+    Assert.assertEqual(userData.previousLength, 1n, "proof is stored with the previous proofs");
+    Assert.assert(isArrayEqual(userData.proofs[0], proof.hash), "user data has stored the proof submitted");
+    const validBlockhash = global_data_account.validBlockhashes.validBlockhash;
+    Assert.assert(isArrayEqual(userData.recentBlockhash, validBlockhash), "recent proofs are for the valid blockhash");
+
+    // a proof against the new valid blockhash keeps the previous proofs for duplicate checks
+    ({ proof, tx } = await makeSubmission(validBlockhash));
+    await client.processTransaction(tx);
+    userData = await getUserData();
+    Assert.assert(isArrayEqual(userData.previousBlockhash, previousBlockhash), "previous blockhash is kept during the grace period");
+    Assert.assertEqual(userData.previousLength, 1n, "previous proofs are kept during the grace period");
+    Assert.assertEqual(userData.length, 1n, "proof against the valid blockhash is stored");
+
+    // resubmitting the proof against the previous blockhash is still a duplicate
+    ({ tx } = await makeSubmission(previousBlockhash));
+    let result = await client.simulateTransaction(tx);
+    Assert.assertNotNull(result.result, "duplicate proof against the previous blockhash is rejected");
+
+    // once the grace period is over the previous blockhash is rejected
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_DISTRIBUTION_TIME + gracePeriod + 1n));
+    ({ tx } = await makeSubmission(previousBlockhash));
+    result = await client.simulateTransaction(tx);
+    Assert.assertNotNull(result.result, "proof against the previous blockhash is rejected after the grace period");
+}
+
+(async () => { await test_proofGracePeriod(); })();
@@ -4,7 +4,7 @@ import { Clock, start } from "solana-bankrun";
 
 import {
     get_default_comptoken_mint, get_default_comptoken_wallet, get_default_global_data, get_default_user_data_account,
-    isArrayEqual, MintAccount, TokenAccount, UserDataAccount,
+    GlobalDataAccount, isArrayEqual, MintAccount, TokenAccount, UserDataAccount,
 } from "../accounts.js";
 import { Assert } from "../assert.js";
 import { compto_program_id_pubkey, DEFAULT_START_TIME, Instruction, testuser_comptoken_wallet_pubkey } from "../common.js";
@@ -14,8 +14,7 @@ async function test_proofSubmission() {
     let global_data_account = get_default_global_data();
     let mint_account = get_default_comptoken_mint();
     let destination_comptoken_wallet = get_default_comptoken_wallet(testuser_comptoken_wallet_pubkey, PublicKey.unique());
-    const user_data_pda = PublicKey.findProgramAddressSync([destination_comptoken_wallet.address.toBytes()], compto_program_id_pubkey)[0];
-    let user_data_account = get_default_user_data_account(user_data_pda);
+    let user_data_account = get_default_user_data_account(destination_comptoken_wallet.address);
 
     const context = await start(
         [{ name: "comptoken", programId: compto_program_id_pubkey }],
@@ -37,7 +36,7 @@ async function test_proofSubmission() {
         // will store minted comptoken
         { pubkey: destination_comptoken_wallet.address, isSigner: false, isWritable: true },
         // stores the current valid blockhashes
-        { pubkey: global_data_account.address, isSigner: false, isWritable: false },
+        { pubkey: global_data_account.address, isSigner: false, isWritable: true },
         // stores the proof to prevent duplicate submissions
         { pubkey: user_data_account.address, isSigner: false, isWritable: true },
         // for the actual minting
@@ -59,6 +58,10 @@ async function test_proofSubmission() {
     context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME));
     const meta = await client.processTransaction(tx);
 
+    console.log("logMessages: %s", meta.logMessages);
+    console.log("computeUnitsConsumed: %d", meta.computeUnitsConsumed);
+    console.log("returnData: %s", meta.returnData);
+
     let account = await client.getAccount(mint_account.address);
     Assert.assertNotNull(account);
     const finalMintAccount = MintAccount.fromAccountInfoBytes(mint_account.address, account);
@@ -75,6 +78,18 @@ async function test_proofSubmission() {
     Assert.assert(isArrayEqual(finalUserDataAccount.recentBlockhash, global_data_account.validBlockhashes.validBlockhash), "user datas recent blockhash is the valid blockhash");
     Assert.assertEqual(finalUserDataAccount.length, user_data_account.length + 1n, "user data has stored a proof");
     Assert.assert(isArrayEqual(finalUserDataAccount.proofs[0], proof.hash), "user data has stored the proof submitted");
+    Assert.assertEqual(
+        finalUserDataAccount.lastSettledBalance, finalDestinationComptokenWallet.amount, "mined comptokens earn interest from today"
+    );
+
+    account = await client.getAccount(global_data_account.address);
+    Assert.assertNotNull(account);
+    const finalGlobalData = GlobalDataAccount.fromAccountInfoBytes(global_data_account.address, account);
+    const minted = finalMintAccount.supply - mint_account.supply;
+    Assert.assertEqual(finalGlobalData.dailyDistributionData.minedSinceLastDistribution, minted, "mining is counted for the next distribution");
+    Assert.assertEqual(finalGlobalData.ledger.totalMined, minted, "mining is recorded in the ledger");
+    Assert.assertEqual(finalGlobalData.dailyStats.proofsSinceLastDistribution, 1n, "the proof is counted for the day's stats");
+    Assert.assertEqual(finalGlobalData.dailyStats.minersSinceLastDistribution, 1n, "the wallet's first proof today counts it as a miner");
 }
 
 (async () => { await test_proofSubmission(); })();
@@ -0,0 +1,109 @@
+import { TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
+import { PublicKey, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { Clock, start } from "solana-bankrun";
+
+import {
+    get_default_comptoken_mint, get_default_comptoken_wallet, get_default_extra_account_metas_account,
+    get_default_global_data, get_default_unpaid_interest_bank, get_default_unpaid_ubi_bank,
+    get_default_user_data_account, get_default_user_identity_account, TokenAccount, UserDataAccount,
+} from "../accounts.js";
+import { Assert } from "../assert.js";
+import {
+    compto_program_id_pubkey, compto_transfer_hook_id_pubkey, DEFAULT_DISTRIBUTION_TIME, DEFAULT_START_TIME,
+    Instruction, SEC_PER_DAY, testuser_comptoken_wallet_pubkey,
+} from "../common.js";
+import { ComptokenProof } from "../comptoken_proof.js";
+
+async function test_proofSubmissionSettlement() {
+    let global_data_account = get_default_global_data();
+    global_data_account.dailyDistributionData.historicInterests[0] = 0.5;
+    global_data_account.dailyDistributionData.oldestInterest = 1n;
+    let mint_account = get_default_comptoken_mint();
+    mint_account.supply = 146_004n;
+    let destination_comptoken_wallet = get_default_comptoken_wallet(testuser_comptoken_wallet_pubkey, PublicKey.unique());
+    destination_comptoken_wallet.amount = 4n;
+    let user_identity = get_default_user_identity_account(destination_comptoken_wallet.nominalOwner);
+    let user_data_account = get_default_user_data_account(destination_comptoken_wallet.address, user_identity.address);
+    // a day of interest is owed on the 4 comptokens held since the last settlement
+    user_data_account.lastInterestPayoutDate = DEFAULT_DISTRIBUTION_TIME - SEC_PER_DAY;
+    user_data_account.lastSettledBalance = 4n;
+    let interest_bank = get_default_unpaid_interest_bank();
+    interest_bank.amount = 146_000n;
+    let ubi_bank = get_default_unpaid_ubi_bank();
+    let extra_account_metas_account = get_default_extra_account_metas_account();
+
+    const context = await start(
+        [
+            { name: "comptoken", programId: compto_program_id_pubkey },
+            { name: "comptoken_transfer_hook", programId: compto_transfer_hook_id_pubkey },
+        ],
+        [
+            mint_account.toAccount(),
+            global_data_account.toAccount(),
+            destination_comptoken_wallet.toAccount(),
+            user_data_account.toAccount(),
+            user_identity.toAccount(),
+            interest_bank.toAccount(),
+            ubi_bank.toAccount(),
+            extra_account_metas_account.toAccount(),
+        ]
+    );
+
+    const client = context.banksClient;
+    const payer = context.payer;
+    const blockhash = context.lastBlockhash;
+    const keys = [
+        // will mint some comptokens
+        { pubkey: mint_account.address, isSigner: false, isWritable: true },
+        // will store minted comptoken
+        { pubkey: destination_comptoken_wallet.address, isSigner: false, isWritable: true },
+        // stores the current valid blockhashes
+        { pubkey: global_data_account.address, isSigner: false, isWritable: true },
+        // stores the proof to prevent duplicate submissions
+        { pubkey: user_data_account.address, isSigner: false, isWritable: true },
+        // for the actual minting
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+        // the rest pay out owed interest and UBI before minting
+        { pubkey: interest_bank.address, isSigner: false, isWritable: true },
+        { pubkey: ubi_bank.address, isSigner: false, isWritable: true },
+        { pubkey: extra_account_metas_account.address, isSigner: false, isWritable: false },
+        { pubkey: compto_transfer_hook_id_pubkey, isSigner: false, isWritable: false },
+        { pubkey: compto_program_id_pubkey, isSigner: false, isWritable: false },
+        { pubkey: PublicKey.findProgramAddressSync([interest_bank.address.toBytes()], compto_program_id_pubkey)[0], isSigner: false, isWritable: true },
+        { pubkey: PublicKey.findProgramAddressSync([ubi_bank.address.toBytes()], compto_program_id_pubkey)[0], isSigner: false, isWritable: true },
+        { pubkey: user_identity.address, isSigner: false, isWritable: true },
+    ];
+
+    let proof = new ComptokenProof(destination_comptoken_wallet.address, global_data_account.validBlockhashes.validBlockhash);
+    proof.mine();
+    let data = Buffer.concat([
+        Buffer.from([Instruction.COMPTOKEN_MINT]),
+        proof.serializeData(),
+    ]);
+
+    const ixs = [new TransactionInstruction({ programId: compto_program_id_pubkey, keys, data })];
+    const tx = new Transaction();
+    tx.recentBlockhash = blockhash;
+    tx.add(...ixs);
+    tx.sign(payer);
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME));
+    const meta = await client.processTransaction(tx);
+
+    console.log("logMessages: %s", meta.logMessages);
+    console.log("computeUnitsConsumed: %d", meta.computeUnitsConsumed);
+
+    let account = await client.getAccount(destination_comptoken_wallet.address);
+    Assert.assertNotNull(account);
+    const finalDestinationComptokenWallet = TokenAccount.fromAccountInfoBytes(destination_comptoken_wallet.address, account);
+    // 2 comptokens of interest and 2 mined
+    Assert.assertEqual(finalDestinationComptokenWallet.amount, 8n, "interest is paid before minting");
+
+    account = await client.getAccount(user_data_account.address);
+    Assert.assertNotNull(account);
+    const finalUserDataAccount = UserDataAccount.fromAccountInfoBytes(user_data_account.address, account);
+    Assert.assertEqual(finalUserDataAccount.lastInterestPayoutDate, DEFAULT_DISTRIBUTION_TIME, "wallet is current");
+    Assert.assertEqual(finalUserDataAccount.accruedInterest, 0n, "nothing is left accrued");
+    Assert.assertEqual(finalUserDataAccount.lastSettledBalance, 8n, "paid and mined comptokens earn interest from today");
+}
+
+(async () => { await test_proofSubmissionSettlement(); })();
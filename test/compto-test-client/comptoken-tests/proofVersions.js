@@ -0,0 +1,98 @@
+import { TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
+import { PublicKey, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { Clock, start } from "solana-bankrun";
+
+import {
+    get_default_comptoken_mint, get_default_comptoken_wallet, get_default_global_data, get_default_user_data_account,
+    isArrayEqual, UserDataAccount,
+} from "../accounts.js";
+import { Assert } from "../assert.js";
+import { compto_program_id_pubkey, DEFAULT_START_TIME, Instruction, ProofScheme, testuser_comptoken_wallet_pubkey } from "../common.js";
+import { ComptokenProof, ProofVersion } from "../comptoken_proof.js";
+
+async function test_proofVersions() {
+    let global_data_account = get_default_global_data();
+    // v1 proofs are accepted until the start time
+    global_data_account.config.v1ProofDeadline = DEFAULT_START_TIME;
+    let mint_account = get_default_comptoken_mint();
+    let destination_comptoken_wallet = get_default_comptoken_wallet(testuser_comptoken_wallet_pubkey, PublicKey.unique());
+    let user_data_account = get_default_user_data_account(destination_comptoken_wallet.address);
+
+    const context = await start(
+        [{ name: "comptoken", programId: compto_program_id_pubkey }],
+        [
+            mint_account.toAccount(),
+            global_data_account.toAccount(),
+            destination_comptoken_wallet.toAccount(),
+            user_data_account.toAccount(),
+        ]
+    );
+
+    const client = context.banksClient;
+    const payer = context.payer;
+    const keys = [
+        { pubkey: mint_account.address, isSigner: false, isWritable: true },
+        { pubkey: destination_comptoken_wallet.address, isSigner: false, isWritable: true },
+        { pubkey: global_data_account.address, isSigner: false, isWritable: true },
+        { pubkey: user_data_account.address, isSigner: false, isWritable: true },
+        { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+    ];
+    const validBlockhash = global_data_account.validBlockhashes.validBlockhash;
+
+    let slot = 1n;
+    /**
+     * @param {number} version
+     * @param {PublicKey} programId
+     * @returns {Promise<{ proof: ComptokenProof, tx: Transaction }>}
+     */
+    async function makeSubmission(version, programId = compto_program_id_pubkey) {
+        let proof = new ComptokenProof(destination_comptoken_wallet.address, validBlockhash, ProofScheme.SHA256, version, programId);
+        proof.mine();
+        const data = Buffer.concat([Buffer.from([Instruction.COMPTOKEN_MINT]), proof.serializeData()]);
+        // each transaction needs a new blockhash, otherwise it would be a duplicate
+        context.warpToSlot(++slot);
+        const tx = new Transaction();
+        tx.recentBlockhash = (await client.getLatestBlockhash())[0];
+        tx.add(new TransactionInstruction({ programId: compto_program_id_pubkey, keys, data }));
+        tx.sign(payer);
+        return { proof, tx };
+    }
+
+    async function getUserData() {
+        const account = await client.getAccount(user_data_account.address);
+        Assert.assertNotNull(account);
+        return UserDataAccount.fromAccountInfoBytes(user_data_account.address, account);
+    }
+
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME));
+
+    // a v2 proof is bound to the program it was mined for
+    let { tx } = await makeSubmission(ProofVersion.V2, PublicKey.unique());
+    let result = await client.simulateTransaction(tx);
+    Assert.assertNotNull(result.result, "v2 proof for another program is rejected");
+
+    let proof;
+    ({ proof, tx } = await makeSubmission(ProofVersion.V2));
+    await client.processTransaction(tx);
+    let userData = await getUserData();
+    Assert.assert(isArrayEqual(userData.proofs[0], proof.hash), "v2 proof is stored");
+
+    // v1 proofs are still accepted during the transition period
+    ({ proof, tx } = await makeSubmission(ProofVersion.V1));
+    await client.processTransaction(tx);
+    userData = await getUserData();
+    Assert.assertEqual(userData.length, 2n, "v1 proof is stored during the transition period");
+
+    // and rejected after it
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME + 1n));
+    ({ tx } = await makeSubmission(ProofVersion.V1));
+    result = await client.simulateTransaction(tx);
+    Assert.assertNotNull(result.result, "v1 proof is rejected after the transition period");
+
+    ({ tx } = await makeSubmission(ProofVersion.V2));
+    await client.processTransaction(tx);
+    userData = await getUserData();
+    Assert.assertEqual(userData.length, 3n, "v2 proof is still stored after the transition period");
+}
+
+(async () => { await test_proofVersions(); })();
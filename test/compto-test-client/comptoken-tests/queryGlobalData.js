@@ -0,0 +1,86 @@
+import { Transaction, TransactionInstruction } from "@solana/web3.js";
+import { Clock, start } from "solana-bankrun";
+
+import { get_default_global_data, LEBytesToDoubleArray } from "../accounts.js";
+import { Assert } from "../assert.js";
+import {
+    compto_program_id_pubkey, DEFAULT_BLOCKHASH_GRACE_PERIOD, DEFAULT_DISTRIBUTION_BOUNTY, DEFAULT_START_TIME,
+    global_data_account_pubkey, Instruction, QUERY_VERSION, QuerySelector,
+} from "../common.js";
+
+async function test_queryGlobalData() {
+    let globalData = get_default_global_data();
+    // a full year of interests, 1 being the oldest
+    globalData.dailyDistributionData.historicInterests = Array.from({ length: 365 }, (v, i) => i + 1);
+    globalData.dailyDistributionData.oldestInterest = 0n;
+
+    const context = await start([{ name: "comptoken", programId: compto_program_id_pubkey }], [globalData.toAccount()]);
+    const client = context.banksClient;
+    const payer = context.payer;
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME));
+
+    /**
+     * @param {number} selector
+     * @param {number} page
+     * @returns {Promise<Transaction>}
+     */
+    async function makeQuery(selector, page) {
+        const keys = [
+            // the global data is only read
+            { pubkey: global_data_account_pubkey, isSigner: false, isWritable: false },
+        ];
+        const data = Buffer.alloc(4);
+        data.writeUInt8(Instruction.QUERY_GLOBAL_DATA, 0);
+        data.writeUInt8(selector, 1);
+        data.writeUInt16LE(page, 2);
+        const tx = new Transaction();
+        tx.recentBlockhash = (await client.getLatestBlockhash())[0];
+        tx.add(new TransactionInstruction({ programId: compto_program_id_pubkey, keys, data }));
+        tx.sign(payer);
+        return tx;
+    }
+
+    /**
+     * @param {number} selector
+     * @param {number} page
+     * @returns {Promise<{ selector: number, page: number, pageCount: number, records: Buffer }>}
+     */
+    async function query(selector, page) {
+        const result = await client.simulateTransaction(await makeQuery(selector, page));
+        console.log("computeUnitsConsumed: %d", result.meta.computeUnitsConsumed);
+
+        const returnData = Buffer.from(result.meta.returnData.data);
+        Assert.assertEqual(returnData.readUInt8(0), QUERY_VERSION, "query version");
+        return {
+            selector: returnData.readUInt8(1),
+            page: returnData.readUInt16LE(2),
+            pageCount: returnData.readUInt16LE(4),
+            records: returnData.subarray(6),
+        };
+    }
+
+    const parameters = await query(QuerySelector.PARAMETERS, 0);
+    Assert.assertEqual(parameters.selector, QuerySelector.PARAMETERS, "selector");
+    Assert.assertEqual(parameters.pageCount, 1, "parameters fit on one page");
+    Assert.assertEqual(parameters.records.readBigUInt64LE(0), DEFAULT_DISTRIBUTION_BOUNTY, "distribution bounty");
+    Assert.assertEqual(parameters.records.readBigInt64LE(8), DEFAULT_BLOCKHASH_GRACE_PERIOD, "blockhash grace period");
+
+    // the history is more than fits in return data, so it is split over pages of whole interests
+    let interests = [];
+    const first = await query(QuerySelector.INTEREST_HISTORY, 0);
+    Assert.assertEqual(first.pageCount, 3, "interest history pages");
+    for (let page = 0; page < first.pageCount; ++page) {
+        const { page: index, records } = page == 0 ? first : await query(QuerySelector.INTEREST_HISTORY, page);
+        Assert.assertEqual(index, page, "page index");
+        Assert.assertEqual(records.length % 8, 0, "pages hold whole interests");
+        interests.push(...LEBytesToDoubleArray(records));
+    }
+    Assert.assertEqual(interests.length, 365, "every interest is returned");
+    Assert.assertEqual(interests[0], 365, "most recent first");
+    Assert.assertEqual(interests[364], 1, "oldest last");
+
+    const result = await client.simulateTransaction(await makeQuery(QuerySelector.INTEREST_HISTORY, first.pageCount));
+    Assert.assertNotNull(result.result, "pages past the end are rejected");
+}
+
+(async () => { await test_queryGlobalData(); })();
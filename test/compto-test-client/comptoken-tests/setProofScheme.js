@@ -0,0 +1,63 @@
+import { Keypair, LAMPORTS_PER_SOL, SystemProgram, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { Clock, start } from "solana-bankrun";
+
+import { get_default_global_data, GlobalDataAccount } from "../accounts.js";
+import { Assert } from "../assert.js";
+import { compto_program_id_pubkey, DEFAULT_START_TIME, global_data_account_pubkey, Instruction, ProofScheme } from "../common.js";
+
+async function test_setProofScheme() {
+    const admin = Keypair.generate();
+    let global_data_account = get_default_global_data();
+    global_data_account.config.admin = admin.publicKey;
+
+    const context = await start(
+        [{ name: "comptoken", programId: compto_program_id_pubkey }],
+        [
+            global_data_account.toAccount(),
+            {
+                address: admin.publicKey,
+                info: { lamports: LAMPORTS_PER_SOL, data: new Uint8Array(0), owner: SystemProgram.programId, executable: false },
+            },
+        ]
+    );
+
+    const client = context.banksClient;
+    const payer = context.payer;
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME));
+
+    /**
+     * @param {Keypair} signer
+     * @returns {Promise<Transaction>}
+     */
+    async function makeSetProofScheme(signer) {
+        const keys = [
+            // only the admin may change the proof scheme
+            { pubkey: signer.publicKey, isSigner: true, isWritable: true },
+            // stores the proof schemes
+            { pubkey: global_data_account_pubkey, isSigner: false, isWritable: true },
+        ];
+        const data = Buffer.from([Instruction.SET_PROOF_SCHEME, ProofScheme.KECCAK256]);
+        const tx = new Transaction();
+        tx.recentBlockhash = (await client.getLatestBlockhash())[0];
+        tx.add(new TransactionInstruction({ programId: compto_program_id_pubkey, keys, data }));
+        tx.sign(payer, signer);
+        return tx;
+    }
+
+    let result = await client.simulateTransaction(await makeSetProofScheme(Keypair.generate()));
+    Assert.assertNotNull(result.result, "non admin is rejected");
+
+    const meta = await client.processTransaction(await makeSetProofScheme(admin));
+
+    console.log("logMessages: %s", meta.logMessages);
+    console.log("computeUnitsConsumed: %d", meta.computeUnitsConsumed);
+    console.log("returnData: %s", meta.returnData);
+
+    const account = await client.getAccount(global_data_account_pubkey);
+    Assert.assertNotNull(account);
+    const finalGlobalData = GlobalDataAccount.fromAccountInfoBytes(global_data_account_pubkey, account);
+    Assert.assertEqual(finalGlobalData.validBlockhashes.nextProofScheme, ProofScheme.KECCAK256, "next proof scheme is set");
+    Assert.assertEqual(finalGlobalData.validBlockhashes.validProofScheme, ProofScheme.SHA256, "valid proof scheme is unchanged until the rollover");
+}
+
+(async () => { await test_setProofScheme(); })();
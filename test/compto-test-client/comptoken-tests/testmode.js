@@ -0,0 +1,101 @@
+import { PublicKey, SYSVAR_SLOT_HASHES_PUBKEY, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { Clock, start } from "solana-bankrun";
+
+import {
+    get_default_comptoken_wallet, get_default_global_data, get_default_user_data_account,
+    get_default_user_identity_account, GlobalDataAccount, isArrayEqual, numAsDoubleToLEBytes, UserDataAccount, UserIdentityAccount,
+} from "../accounts.js";
+import { Assert } from "../assert.js";
+import {
+    compto_program_id_pubkey, DEFAULT_DISTRIBUTION_TIME, DEFAULT_START_TIME, Instruction, SEC_PER_DAY,
+    testuser_comptoken_wallet_pubkey,
+} from "../common.js";
+
+async function test_testmode() {
+    const user_wallet = get_default_comptoken_wallet(testuser_comptoken_wallet_pubkey, PublicKey.unique());
+    const user_identity = get_default_user_identity_account(user_wallet.nominalOwner);
+    const user_data = get_default_user_data_account(user_wallet.address, user_identity.address);
+    const global_data = get_default_global_data();
+
+    const context = await start(
+        [{ name: "comptoken", programId: compto_program_id_pubkey }],
+        [user_data.toAccount(), user_identity.toAccount(), user_wallet.toAccount(), global_data.toAccount()]
+    );
+    const client = context.banksClient;
+    const payer = context.payer;
+    const blockhash = context.lastBlockhash;
+    // mid day, when the blockhashes would not normally change
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME));
+
+    // a user who hasn't claimed for 200 days
+    const lastInterestPayoutDate = DEFAULT_DISTRIBUTION_TIME - 200n * SEC_PER_DAY;
+    let setPayoutDateData = Buffer.alloc(9);
+    setPayoutDateData.writeUInt8(Instruction.TEST_SET_LAST_INTEREST_PAYOUT_DATE, 0);
+    setPayoutDateData.writeBigInt64LE(lastInterestPayoutDate, 1);
+    const interests = [0.25, 0.5];
+
+    const ixs = [
+        new TransactionInstruction({
+            programId: compto_program_id_pubkey,
+            keys: [
+                { pubkey: user_data.address, isSigner: false, isWritable: true },
+                { pubkey: user_wallet.address, isSigner: false, isWritable: false },
+                { pubkey: global_data.address, isSigner: false, isWritable: false },
+            ],
+            data: setPayoutDateData,
+        }),
+        new TransactionInstruction({
+            programId: compto_program_id_pubkey,
+            keys: [
+                { pubkey: user_identity.address, isSigner: false, isWritable: true },
+                { pubkey: global_data.address, isSigner: false, isWritable: false },
+            ],
+            data: Buffer.from([Instruction.TEST_SET_VERIFIED_HUMAN, 1]),
+        }),
+        new TransactionInstruction({
+            programId: compto_program_id_pubkey,
+            keys: [{ pubkey: global_data.address, isSigner: false, isWritable: true }],
+            data: Buffer.from([Instruction.TEST_INJECT_HISTORIC_INTERESTS, ...interests.flatMap((interest) => numAsDoubleToLEBytes(interest))]),
+        }),
+        new TransactionInstruction({
+            programId: compto_program_id_pubkey,
+            keys: [
+                { pubkey: global_data.address, isSigner: false, isWritable: true },
+                { pubkey: SYSVAR_SLOT_HASHES_PUBKEY, isSigner: false, isWritable: false },
+            ],
+            data: Buffer.from([Instruction.TEST_FORCE_BLOCKHASH_ROTATION]),
+        }),
+    ];
+    const tx = new Transaction();
+    tx.recentBlockhash = blockhash;
+    tx.add(...ixs);
+    tx.sign(payer);
+    const meta = await client.processTransaction(tx);
+
+    console.log("logMessages: %s", meta.logMessages);
+    console.log("computeUnitsConsumed: %d", meta.computeUnitsConsumed);
+
+    let account = await client.getAccount(user_data.address);
+    Assert.assertNotNull(account);
+    const finalUserData = UserDataAccount.fromAccountInfoBytes(user_data.address, account);
+    Assert.assertEqual(finalUserData.lastInterestPayoutDate, lastInterestPayoutDate, "last interest payout date");
+
+    account = await client.getAccount(user_identity.address);
+    Assert.assertNotNull(account);
+    const finalUserIdentity = UserIdentityAccount.fromAccountInfoBytes(user_identity.address, account);
+    Assert.assert(finalUserIdentity.isVerifiedHuman, "is verified human");
+
+    account = await client.getAccount(global_data.address);
+    Assert.assertNotNull(account);
+    const finalGlobalData = GlobalDataAccount.fromAccountInfoBytes(global_data.address, account);
+    const oldestInterest = global_data.dailyDistributionData.oldestInterest;
+    Assert.assertEqual(finalGlobalData.dailyDistributionData.oldestInterest, oldestInterest + 2n, "interests were injected");
+    Assert.assertEqual(finalGlobalData.dailyDistributionData.historicInterests[Number(oldestInterest)], 0.25, "older interest");
+    Assert.assertEqual(finalGlobalData.dailyDistributionData.historicInterests[Number(oldestInterest) + 1], 0.5, "newer interest");
+    const initialBlockhashes = global_data.validBlockhashes;
+    const finalBlockhashes = finalGlobalData.validBlockhashes;
+    Assert.assert(!isArrayEqual(finalBlockhashes.validBlockhash, initialBlockhashes.validBlockhash), "valid blockhash rotated");
+    Assert.assert(isArrayEqual(finalBlockhashes.previousValidBlockhash, initialBlockhashes.validBlockhash), "old blockhash is kept");
+}
+
+(async () => { await test_testmode(); })();
@@ -0,0 +1,93 @@
+import { TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
+import { Keypair, LAMPORTS_PER_SOL, SystemProgram, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { Clock, start } from "solana-bankrun";
+
+import {
+    ExtensionType, get_default_comptoken_mint, get_default_global_data, MintAccount, stringAsLEBytes, TLV, TokenMetadata,
+} from "../accounts.js";
+import { Assert } from "../assert.js";
+import {
+    compto_program_id_pubkey, comptoken_mint_pubkey, DEFAULT_NAME, DEFAULT_START_TIME, DEFAULT_SYMBOL, DEFAULT_URI,
+    global_data_account_pubkey, Instruction,
+} from "../common.js";
+
+async function test_updateComptokenMetadata() {
+    const admin = Keypair.generate();
+    let global_data_account = get_default_global_data();
+    global_data_account.config.admin = admin.publicKey;
+    let mint_account = get_default_comptoken_mint()
+        .addExtension(TLV.metadataPointer(comptoken_mint_pubkey, global_data_account_pubkey))
+        .addExtension(TLV.tokenMetadata(
+            new TokenMetadata(global_data_account_pubkey, comptoken_mint_pubkey, DEFAULT_NAME, DEFAULT_SYMBOL, DEFAULT_URI)
+        ));
+
+    const context = await start(
+        [{ name: "comptoken", programId: compto_program_id_pubkey }],
+        [
+            global_data_account.toAccount(),
+            {
+                address: admin.publicKey,
+                info: { lamports: LAMPORTS_PER_SOL, data: new Uint8Array(0), owner: SystemProgram.programId, executable: false },
+            },
+        ]
+    );
+
+    const client = context.banksClient;
+    const payer = context.payer;
+    const rent = await client.getRent();
+    // the mint only holds the rent for its current size, so the admin has to pay for a longer uri
+    mint_account.lamports = await rent.minimumBalance(BigInt(mint_account.getSize()));
+    const mint = mint_account.toAccount();
+    context.setAccount(mint.address, mint.info);
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME));
+
+    const newUri = DEFAULT_URI + "?version=2";
+    /**
+     * @param {Keypair} signer
+     * @returns {Promise<Transaction>}
+     */
+    async function makeUpdate(signer) {
+        const keys = [
+            // the admin, who pays for any extra space the metadata needs
+            { pubkey: signer.publicKey, isSigner: true, isWritable: true },
+            // the mint, which stores the metadata
+            { pubkey: comptoken_mint_pubkey, isSigner: false, isWritable: true },
+            // the metadata update authority, which stores the admin
+            { pubkey: global_data_account_pubkey, isSigner: false, isWritable: false },
+            // needed to pay for the extra space
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            // the token program that stores the metadata
+            { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+        ];
+        // 1 byte for the instruction, 1 byte for the field (2 is the uri), then the new value
+        const data = Buffer.from([Instruction.UPDATE_COMPTOKEN_METADATA, 2, ...stringAsLEBytes(newUri)]);
+        const tx = new Transaction();
+        tx.recentBlockhash = (await client.getLatestBlockhash())[0];
+        tx.add(new TransactionInstruction({ programId: compto_program_id_pubkey, keys, data }));
+        tx.sign(payer, signer);
+        return tx;
+    }
+
+    // only the admin may update the metadata
+    let result = await client.simulateTransaction(await makeUpdate(Keypair.generate()));
+    Assert.assertNotNull(result.result, "non admin is rejected");
+
+    const meta = await client.processTransaction(await makeUpdate(admin));
+
+    console.log("logMessages: %s", meta.logMessages);
+    console.log("computeUnitsConsumed: %d", meta.computeUnitsConsumed);
+    console.log("returnData: %s", meta.returnData);
+
+    const account = await client.getAccount(comptoken_mint_pubkey);
+    Assert.assertNotNull(account);
+    Assert.assertEqual(account.lamports, await rent.minimumBalance(BigInt(account.data.length)), "mint is still rent exempt");
+    const finalMint = MintAccount.fromAccountInfoBytes(comptoken_mint_pubkey, account);
+    const metadata = TokenMetadata.fromBytes(
+        finalMint.extensions.find((extension) => extension.type === ExtensionType.TokenMetadata).value
+    );
+    Assert.assertEqual(metadata.uri, newUri, "uri is updated");
+    Assert.assertEqual(metadata.name, DEFAULT_NAME, "name is unchanged");
+    Assert.assertEqual(metadata.symbol, DEFAULT_SYMBOL, "symbol is unchanged");
+}
+
+(async () => { await test_updateComptokenMetadata(); })();
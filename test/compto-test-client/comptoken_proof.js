@@ -1,27 +1,83 @@
 import { TOKEN_2022_PROGRAM_ID } from '@solana/spl-token';
 import { PublicKey, Transaction, TransactionInstruction, sendAndConfirmTransaction } from '@solana/web3.js';
+import { blake3 } from "@noble/hashes/blake3";
+import { keccak_256 } from "@noble/hashes/sha3";
 import { assert } from "console";
-import { createHash } from "crypto";
+import { createHash, randomBytes } from "crypto";
 
-import { Instruction, bs58, compto_program_id_pubkey, comptoken_mint_pubkey, global_data_account_pubkey } from "./common.js";
+import { Instruction, ProofScheme, bs58, compto_program_id_pubkey, comptoken_mint_pubkey, global_data_account_pubkey } from "./common.js";
 
 const MIN_NUM_ZEROED_BITS = 3;
+const PROOF_DOMAIN = Buffer.from("Comptoken Proof");
+
+// Ensure these remain consistent with comptoken_proof.rs
+export const ProofVersion = {
+    V1: 1,
+    V2: 2,
+};
+
+// Ensure these remain consistent with proof_scheme.rs
+const HASH_FUNCTIONS = {
+    [ProofScheme.SHA256]: (data) => createHash("sha256").update(data).digest(),
+    [ProofScheme.KECCAK256]: (data) => Buffer.from(keccak_256(data)),
+    [ProofScheme.BLAKE3]: (data) => Buffer.from(blake3(data)),
+};
 
 // Ensure changes to this class remain consistent with comptoken_proof.rs
+// MAGIC NUMBER: ensure this remains consistent with CHALLENGE_SLOT_HASH_COUNT in constants.rs
+export const CHALLENGE_SLOT_HASH_COUNT = 8;
+
+/**
+ * Rederives an announced blockhash the way valid_blockhashes.rs does
+ * @param {Uint8Array} previousChallenge the previous announced blockhash
+ * @param {Uint8Array} slotHashesData the data of the slot hashes sysvar when the blockhash was promoted
+ * @param {bigint} firstSlot the first slot the announcement committed to
+ * @returns {Buffer | null} null if the committed slots haven't all been produced
+ */
+export function deriveChallenge(previousChallenge, slotHashesData, firstSlot) {
+    const dataView = new DataView(slotHashesData.buffer, slotHashesData.byteOffset);
+    const len = Number(dataView.getBigUint64(0, true));
+    // each entry is a u64 slot followed by its hash, most recent first
+    let produced = 0;
+    while (produced < len && dataView.getBigUint64(8 + 40 * produced, true) >= firstSlot) {
+        ++produced;
+    }
+    if (produced < CHALLENGE_SLOT_HASH_COUNT) {
+        return null;
+    }
+    let hasher = createHash("sha256");
+    hasher.update(previousChallenge);
+    for (let i = produced - CHALLENGE_SLOT_HASH_COUNT; i < produced; ++i) {
+        hasher.update(slotHashesData.subarray(8 + 40 * i, 48 + 40 * i));
+    }
+    return hasher.digest();
+}
+
 export class ComptokenProof {
     pubkey; // PublicKey
     recentBlockHash; // Uint8Array
     nonce; // uint_64
     hash; // buffer
+    scheme; // ProofScheme
+    version; // ProofVersion
+    extraNonce; // [u8; 32], lets parallel miners search different spaces
+    programId; // PublicKey, v2 proofs are only valid for this program
 
     /**
      * 
      * @param {PublicKey} pubkey 
      * @param {Uint8Array} recentBlockHash
+     * @param {number} scheme the ProofScheme the blockhash is mined with
+     * @param {number} version the ProofVersion layout
+     * @param {PublicKey} programId
      */
-    constructor(pubkey, recentBlockHash) {
+    constructor(pubkey, recentBlockHash, scheme = ProofScheme.SHA256, version = ProofVersion.V2, programId = compto_program_id_pubkey) {
         this.pubkey = pubkey;
         this.recentBlockHash = recentBlockHash;
+        this.scheme = scheme;
+        this.version = version;
+        this.programId = programId;
+        this.extraNonce = version === ProofVersion.V2 ? randomBytes(32) : Buffer.alloc(32);
         this.nonce = Buffer.alloc(8);
         this.hash = this.generateHash();
     }
@@ -31,11 +87,19 @@ export class ComptokenProof {
      * @returns {Buffer}
      */
     generateHash() {
-        let hasher = createHash("sha256");
-        hasher.update(this.pubkey.toBuffer());
-        hasher.update(this.recentBlockHash);
-        hasher.update(this.nonce);
-        return hasher.digest();
+        if (this.version === ProofVersion.V1) {
+            return HASH_FUNCTIONS[this.scheme](Buffer.concat([this.pubkey.toBuffer(), this.recentBlockHash, this.nonce]));
+        }
+        return HASH_FUNCTIONS[this.scheme](Buffer.concat([
+            PROOF_DOMAIN,
+            Buffer.from([this.version]),
+            this.programId.toBuffer(),
+            comptoken_mint_pubkey.toBuffer(),
+            this.pubkey.toBuffer(),
+            this.recentBlockHash,
+            this.nonce,
+            this.extraNonce,
+        ]));
     }
 
     /**
@@ -75,18 +139,29 @@ export class ComptokenProof {
      * @returns {Buffer}
      */
     serializeData() {
+        if (this.version === ProofVersion.V1) {
+            let buffer = Buffer.concat([
+                this.recentBlockHash,
+                this.nonce,
+                this.hash,
+            ]);
+            assert(buffer.length == 72);
+            return buffer;
+        }
         let buffer = Buffer.concat([
+            Buffer.from([this.version]),
             this.recentBlockHash,
             this.nonce,
+            this.extraNonce,
             this.hash,
         ]);
-        assert(buffer.length == 72);
+        assert(buffer.length == 105);
         return buffer;
     }
 }
 
-export async function mintComptokens(connection, destination_pubkey, temp_keypair, current_block) {
-    let proof = new ComptokenProof(destination_pubkey, bs58.decode(current_block));
+export async function mintComptokens(connection, destination_pubkey, temp_keypair, current_block, scheme = ProofScheme.SHA256) {
+    let proof = new ComptokenProof(destination_pubkey, bs58.decode(current_block), scheme);
     proof.mine();
     let data = Buffer.concat([
         Buffer.from([Instruction.COMPTOKEN_MINT]),
@@ -11,20 +11,30 @@ import {
 } from "@solana/web3.js";
 
 import {
-    AuthorityType,
     TOKEN_2022_PROGRAM_ID,
-    setAuthority,
-    unpackMint,
+    createAccount,
 } from '@solana/spl-token';
 
+import { stringAsLEBytes } from './accounts.js';
 import {
+    DEFAULT_BLOCKHASH_GRACE_PERIOD,
+    DEFAULT_DAY_LENGTH,
+    DEFAULT_DAY_OFFSET,
+    DEFAULT_DISTRIBUTION_BOUNTY,
+    DEFAULT_NAME,
+    DEFAULT_SYMBOL,
+    DEFAULT_URI,
     Instruction,
     bs58,
+    compto_extra_account_metas_account_pubkey,
     compto_program_id_pubkey,
+    compto_transfer_hook_id_pubkey,
+    comptoken_mint_keypair,
     comptoken_mint_pubkey,
     global_data_account_pubkey,
     interest_bank_account_pubkey,
     me_keypair,
+    testuser_comptoken_wallet_num_arr,
     testuser_comptoken_wallet_pubkey,
     ubi_bank_account_pubkey,
 } from './common.js';
@@ -48,11 +58,12 @@ let connection = new Connection('http://localhost:8899', 'recent');
 (async () => {
     await airdrop(testUser_keypair.publicKey);
     await createGlobalDataAccount();
-    await setMintAuthorityIfNeeded();
+    await createTestUserComptokenWallet();
     await testMint();
+    await createUserIdentity();
     await createUserDataAccount();
-    let current_block = (await getValidBlockHashes()).current_block;
-    await mintComptokens(connection, testuser_comptoken_wallet_pubkey, testUser_keypair, current_block);
+    let { current_block, proof_scheme } = await getValidBlockHashes();
+    await mintComptokens(connection, testuser_comptoken_wallet_pubkey, testUser_keypair, current_block, proof_scheme);
     await dailyDistributionEvent();
     await getOwedComptokens();
 })();
@@ -64,35 +75,25 @@ async function airdrop(pubkey) {
     console.log("Airdrop confirmed");
 }
 
-async function setMintAuthorityIfNeeded() {
-    const info = await connection.getAccountInfo(comptoken_mint_pubkey, "confirmed");
-    const unpackedMint = unpackMint(comptoken_mint_pubkey, info, TOKEN_2022_PROGRAM_ID);
-    if (unpackedMint.mintAuthority.toString() == global_data_account_pubkey.toString()) {
-        console.log("Mint Authority already set, skipping setAuthority Transaction");
-    } else {
-        console.log("Mint Authority not set, setting Authority");
-        await setMintAuthority(unpackedMint.mintAuthority);
-    }
-}
-
-async function setMintAuthority(current_mint_authority_pubkey) {
-    let me_signer = { publicKey: me_keypair.publicKey, secretKey: me_keypair.secretKey }
-    let new_mint_authority = global_data_account_pubkey;
-    const res = await setAuthority(
+// the mint is created when the program is initialized, so the test user's wallet can only be created afterwards
+async function createTestUserComptokenWallet() {
+    const testuser_comptoken_wallet_keypair = Keypair.fromSecretKey(new Uint8Array(testuser_comptoken_wallet_num_arr));
+    await createAccount(
         connection,
-        me_signer,
+        testUser_keypair,
         comptoken_mint_pubkey,
-        current_mint_authority_pubkey,
-        AuthorityType.MintTokens,
-        new_mint_authority,
-        undefined,
+        testUser_keypair.publicKey,
+        testuser_comptoken_wallet_keypair,
         undefined,
         TOKEN_2022_PROGRAM_ID
     );
+    console.log("created test user comptoken wallet");
 }
 
 async function testMint() {
-    let data = Buffer.from([Instruction.TEST]);
+    let data = Buffer.alloc(9);
+    data.writeUInt8(Instruction.TEST, 0);
+    data.writeBigUInt64LE(2n, 1);
     let keys = [
         // communicates to the token program which mint (and therefore which mint authority)
         // to mint the tokens from
@@ -117,18 +118,23 @@ async function testMint() {
 }
 
 async function createGlobalDataAccount() {
-    // MAGIC NUMBER: CHANGE NEEDS TO BE REFLECTED IN comptoken.rs
-    const GLOBAL_DATA_SIZE = 3032;
-    const globalDataRentExemptAmount = await connection.getMinimumBalanceForRentExemption(GLOBAL_DATA_SIZE);
-    const interestBankRentExemptAmount = await connection.getMinimumBalanceForRentExemption(256);
-    const ubiBankRentExemptAmount = await connection.getMinimumBalanceForRentExemption(256);
-    console.log("Rent exempt amount: ", globalDataRentExemptAmount);
-    // 1 byte for instruction 3 x 8 bytes for rent exemptions
-    let data = Buffer.alloc(25);
+    // 1 byte for instruction 8 bytes for the distribution bounty 8 bytes for the blockhash grace period
+    // 8 bytes for the v1 proof deadline 8 bytes for the day length 8 bytes for the day offset
+    // followed by the name, symbol, and uri of the token metadata
+    let data = Buffer.alloc(41);
     data.writeUInt8(Instruction.INITIALIZE_STATIC_ACCOUNT, 0);
-    data.writeBigInt64LE(BigInt(globalDataRentExemptAmount), 1);
-    data.writeBigInt64LE(BigInt(interestBankRentExemptAmount), 9);
-    data.writeBigInt64LE(BigInt(ubiBankRentExemptAmount), 17);
+    data.writeBigUInt64LE(DEFAULT_DISTRIBUTION_BOUNTY, 1);
+    data.writeBigInt64LE(DEFAULT_BLOCKHASH_GRACE_PERIOD, 9);
+    // only v2 proofs are accepted
+    data.writeBigInt64LE(0n, 17);
+    data.writeBigInt64LE(DEFAULT_DAY_LENGTH, 25);
+    data.writeBigInt64LE(DEFAULT_DAY_OFFSET, 33);
+    data = Buffer.concat([
+        data,
+        Buffer.from(stringAsLEBytes(DEFAULT_NAME)),
+        Buffer.from(stringAsLEBytes(DEFAULT_SYMBOL)),
+        Buffer.from(stringAsLEBytes(DEFAULT_URI)),
+    ]);
     console.log("data: ", data);
     let keys = [
         // the payer of the rent for the account
@@ -139,14 +145,18 @@ async function createGlobalDataAccount() {
         { pubkey: interest_bank_account_pubkey, isSigner: false, isWritable: true },
         // the address of the ubi bank account to be created
         { pubkey: ubi_bank_account_pubkey, isSigner: false, isWritable: true },
-        // the comptoken mint account
-        { pubkey: comptoken_mint_pubkey, isSigner: false, isWritable: false },
+        // the comptoken mint to be created, a new keypair which keys the rest of the instance
+        { pubkey: comptoken_mint_pubkey, isSigner: true, isWritable: true },
         // needed because compto program interacts with the system program to create the account
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
         // the token program that will mint the tokens when instructed by the mint authority
         { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
         // program will pull a recent hash from slothashes sysvar if a new valid blockhash is needed.  
         { pubkey: SYSVAR_SLOT_HASHES_PUBKEY, isSigner: false, isWritable: false },
+        // the transfer hook program, which stores the extra account metas for transfers
+        { pubkey: compto_transfer_hook_id_pubkey, isSigner: false, isWritable: false },
+        // the address of the extra account metas account to be created
+        { pubkey: compto_extra_account_metas_account_pubkey, isSigner: false, isWritable: true },
     ];
     let createGlobalDataAccountTransaction = new Transaction();
     createGlobalDataAccountTransaction.add(
@@ -156,16 +166,39 @@ async function createGlobalDataAccount() {
             data: data,
         }),
     );
-    let createGlobalDataAccountResult = await sendAndConfirmTransaction(connection, createGlobalDataAccountTransaction, [testUser_keypair, testUser_keypair]);
+    let createGlobalDataAccountResult = await sendAndConfirmTransaction(connection, createGlobalDataAccountTransaction, [testUser_keypair, comptoken_mint_keypair]);
     console.log("createGlobalDataAccount transaction confirmed", createGlobalDataAccountResult);
 }
 
-async function createUserDataAccount() {
-    // MAGIC NUMBER: CHANGE NEEDS TO BE REFLECTED IN user_data.rs
-    const PROOF_STORAGE_MIN_SIZE = 88;
-    const rentExemptAmount = await connection.getMinimumBalanceForRentExemption(PROOF_STORAGE_MIN_SIZE);
-    console.log("Rent exempt amount: ", rentExemptAmount);
+function getTestUserIdentity() {
+    return PublicKey.findProgramAddressSync([Buffer.from("User Identity"), comptoken_mint_pubkey.toBytes(), testUser_keypair.publicKey.toBytes()], compto_program_id_pubkey)[0];
+}
+
+async function createUserIdentity() {
+    let keys = [
+        // the owner of the comptoken wallet, who pays for the account
+        { pubkey: testUser_keypair.publicKey, isSigner: true, isWritable: true },
+        // the identity account to be created
+        { pubkey: getTestUserIdentity(), isSigner: false, isWritable: true },
+        // system account is used to create the account
+        { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        // identities are per comptoken instance
+        { pubkey: global_data_account_pubkey, isSigner: false, isWritable: false },
+    ];
+    let data = Buffer.from([Instruction.CREATE_USER_IDENTITY]);
+    let createUserIdentityTransaction = new Transaction();
+    createUserIdentityTransaction.add(
+        new TransactionInstruction({
+            keys: keys,
+            programId: compto_program_id_pubkey,
+            data: data,
+        }),
+    );
+    let createUserIdentityResult = await sendAndConfirmTransaction(connection, createUserIdentityTransaction, [testUser_keypair]);
+    console.log("createUserIdentity transaction confirmed", createUserIdentityResult);
+}
 
+async function createUserDataAccount() {
     let user_data_account = PublicKey.findProgramAddressSync([testuser_comptoken_wallet_pubkey.toBytes()], compto_program_id_pubkey)[0];
 
     let createKeys = [
@@ -177,12 +210,15 @@ async function createUserDataAccount() {
         { pubkey: testuser_comptoken_wallet_pubkey, isSigner: false, isWritable: false },
         // system account is used to create the account
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        // the payers identity, which the user data links to
+        { pubkey: getTestUserIdentity(), isSigner: false, isWritable: false },
+        // the global data holds the config the wallet and identity are checked against
+        { pubkey: global_data_account_pubkey, isSigner: false, isWritable: false },
     ];
-    // 1 byte for the instruction, 8 bytes for the rent exempt amount, 8 bytes for the proof storage min size
-    let createData = Buffer.alloc(17);
+    // 1 byte for the instruction, 8 bytes for the proof capacity
+    let createData = Buffer.alloc(9);
     createData.writeUInt8(Instruction.CREATE_USER_DATA_ACCOUNT, 0);
-    createData.writeBigInt64LE(BigInt(rentExemptAmount), 1);
-    createData.writeBigInt64LE(BigInt(PROOF_STORAGE_MIN_SIZE), 9);
+    createData.writeBigUInt64LE(1n, 1);
     console.log("createData: ", createData);
     let createUserDataAccountTransaction = new Transaction();
     createUserDataAccountTransaction.add(
@@ -213,6 +249,10 @@ async function dailyDistributionEvent() {
         { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
         // program will pull a recent hash from slothashes sysvar if a new valid blockhash is needed.  
         { pubkey: SYSVAR_SLOT_HASHES_PUBKEY, isSigner: false, isWritable: false },
+        // comptoken token account that receives the distribution bounty
+        { pubkey: testuser_comptoken_wallet_pubkey, isSigner: false, isWritable: true },
+        // the bounty receiver's user data, settled so the bounty earns interest from today
+        { pubkey: PublicKey.findProgramAddressSync([testuser_comptoken_wallet_pubkey.toBytes()], compto_program_id_pubkey)[0], isSigner: false, isWritable: true },
     ];
     let dailyDistributionEventTransaction = new Transaction();
     dailyDistributionEventTransaction.add(
@@ -252,7 +292,8 @@ async function getValidBlockHashes() {
     let resultBytes = base64.toByteArray(resultData);
     let currentBlockB58 = bs58.encode(resultBytes.slice(0, 32));
     let announcedBlockB58 = bs58.encode(resultBytes.slice(32, 64));
-    let validBlockHashes = { current_block: currentBlockB58, announced_block: announcedBlockB58, };
+    // the scheme to mine the current block with, then the one the announced block will use
+    let validBlockHashes = { current_block: currentBlockB58, announced_block: announcedBlockB58, proof_scheme: resultBytes[64], next_proof_scheme: resultBytes[65] };
     console.log("Valid Block Hashes: ", validBlockHashes);
     return validBlockHashes;
 }
@@ -272,13 +313,25 @@ async function getOwedComptokens() {
         //  Comptoken Mint
         { pubkey: comptoken_mint_pubkey, isSigner: false, isWritable: false },
         //  Comptoken Global Data (also mint authority)
-        { pubkey: global_data_account_pubkey, isSigner: false, isWritable: false },
+        { pubkey: global_data_account_pubkey, isSigner: false, isWritable: true },
         //  Comptoken Interest Bank 
         { pubkey: interest_bank_account_pubkey, isSigner: false, isWritable: true },
         //  Comptoken UBI Bank
         { pubkey: ubi_bank_account_pubkey, isSigner: false, isWritable: true },
         //  Token 2022 Program
         { pubkey: TOKEN_2022_PROGRAM_ID, isSigner: false, isWritable: false },
+        //  Extra Account Metas
+        { pubkey: compto_extra_account_metas_account_pubkey, isSigner: false, isWritable: false },
+        //  Transfer Hook Program
+        { pubkey: compto_transfer_hook_id_pubkey, isSigner: false, isWritable: false },
+        //  Compto Program
+        { pubkey: compto_program_id_pubkey, isSigner: false, isWritable: false },
+        //  Interest Bank Data (does not exist)
+        { pubkey: PublicKey.findProgramAddressSync([interest_bank_account_pubkey.toBytes()], compto_program_id_pubkey)[0], isSigner: false, isWritable: true },
+        //  UBI Bank Data (does not exist)
+        { pubkey: PublicKey.findProgramAddressSync([ubi_bank_account_pubkey.toBytes()], compto_program_id_pubkey)[0], isSigner: false, isWritable: true },
+        //  User's Identity
+        { pubkey: getTestUserIdentity(), isSigner: false, isWritable: true },
     ];
     let getValidBlockhashesTransaction = new Transaction();
     getValidBlockhashesTransaction.add(
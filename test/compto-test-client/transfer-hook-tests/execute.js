@@ -6,11 +6,13 @@ import {
     get_default_comptoken_mint,
     get_default_comptoken_wallet,
     get_default_extra_account_metas_account,
+    get_default_global_data,
     get_default_user_data_account,
-    TokenAccount
+    TokenAccount,
+    UserDataAccount
 } from "../accounts.js";
 import { Assert } from "../assert.js";
-import { compto_program_id_pubkey, compto_transfer_hook_id_pubkey, DEFAULT_START_TIME, MINT_DECIMALS } from "../common.js";
+import { compto_program_id_pubkey, compto_transfer_hook_id_pubkey, DEFAULT_START_TIME, global_data_account_pubkey, MINT_DECIMALS } from "../common.js";
 
 async function test_execute() {
     console.log("test execute")
@@ -19,8 +21,8 @@ async function test_execute() {
     let user1 = get_default_comptoken_wallet(PublicKey.unique(), user1_owner.publicKey);
     user1.amount = 1n;
     let user2 = get_default_comptoken_wallet(PublicKey.unique(), PublicKey.unique());
-    let user1_data = get_default_user_data_account(PublicKey.findProgramAddressSync([user1.address.toBytes()], compto_program_id_pubkey)[0]);
-    let user2_data = get_default_user_data_account(PublicKey.findProgramAddressSync([user2.address.toBytes()], compto_program_id_pubkey)[0]);
+    let user1_data = get_default_user_data_account(user1.address);
+    let user2_data = get_default_user_data_account(user2.address);
     let extraAccountMetaAccount = get_default_extra_account_metas_account();
 
     const context = await start(
@@ -35,6 +37,7 @@ async function test_execute() {
             extraAccountMetaAccount.toAccount(),
             user1_data.toAccount(),
             user2_data.toAccount(),
+            get_default_global_data().toAccount(),
         ]
     );
 
@@ -53,8 +56,10 @@ async function test_execute() {
         { pubkey: extraAccountMetaAccount.address, isSigner: false, isWritable: false },
         // our transfer hook keys
         { pubkey: compto_program_id_pubkey, isSigner: false, isWritable: false },
-        { pubkey: user1_data.address, isSigner: false, isWritable: false },
-        { pubkey: user2_data.address, isSigner: false, isWritable: false },
+        { pubkey: user1_data.address, isSigner: false, isWritable: true },
+        { pubkey: user2_data.address, isSigner: false, isWritable: true },
+        // the global data, which holds the config
+        { pubkey: global_data_account_pubkey, isSigner: false, isWritable: false },
         // transfer hook program
         { pubkey: compto_transfer_hook_id_pubkey, isSigner: false, isWritable: false },
     ]
@@ -88,6 +93,13 @@ async function test_execute() {
     let finalUser2 = TokenAccount.fromAccountInfoBytes(user2.address, account);
     Assert.assertEqual(finalUser2.amount, 1n);
 
+    // both wallets are settled, so the received comptokens earn interest from the day they arrive
+    account = await client.getAccount(user1_data.address);
+    Assert.assertNotNull(account);
+    Assert.assertEqual(UserDataAccount.fromAccountInfoBytes(user1_data.address, account).lastSettledBalance, 0n, "sender's settled balance");
+    account = await client.getAccount(user2_data.address);
+    Assert.assertNotNull(account);
+    Assert.assertEqual(UserDataAccount.fromAccountInfoBytes(user2_data.address, account).lastSettledBalance, 1n, "receiver's settled balance");
 }
 
 (async () => { await test_execute(); })();
\ No newline at end of file
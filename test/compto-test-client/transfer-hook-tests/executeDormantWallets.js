@@ -0,0 +1,119 @@
+import { TOKEN_2022_PROGRAM_ID, TokenInstruction, transferCheckedInstructionData } from "@solana/spl-token";
+import { Keypair, PublicKey, Transaction, TransactionInstruction } from "@solana/web3.js";
+import { Clock, start } from "solana-bankrun";
+
+import {
+    get_default_comptoken_mint,
+    get_default_comptoken_wallet,
+    get_default_extra_account_metas_account,
+    get_default_global_data,
+    get_default_user_data_account,
+    TokenAccount,
+    UserDataAccount
+} from "../accounts.js";
+import { Assert } from "../assert.js";
+import {
+    compto_program_id_pubkey, compto_transfer_hook_id_pubkey, DEFAULT_DISTRIBUTION_TIME, DEFAULT_START_TIME,
+    global_data_account_pubkey, MINT_DECIMALS, SEC_PER_DAY
+} from "../common.js";
+
+/**
+ * a transfer of 1 comptoken between two wallets whose user data last settled on the given days
+ * @param {bigint} senderPayoutDate
+ * @param {bigint} receiverPayoutDate
+ */
+async function setupTransfer(senderPayoutDate, receiverPayoutDate) {
+    let comptoken_mint = get_default_comptoken_mint();
+    const sender_owner = Keypair.generate();
+    let sender = get_default_comptoken_wallet(PublicKey.unique(), sender_owner.publicKey);
+    sender.amount = 1n;
+    let receiver = get_default_comptoken_wallet(PublicKey.unique(), PublicKey.unique());
+    let sender_data = get_default_user_data_account(sender.address);
+    sender_data.lastInterestPayoutDate = senderPayoutDate;
+    let receiver_data = get_default_user_data_account(receiver.address);
+    receiver_data.lastInterestPayoutDate = receiverPayoutDate;
+    let extraAccountMetaAccount = get_default_extra_account_metas_account();
+
+    const context = await start(
+        [
+            { name: "comptoken", programId: compto_program_id_pubkey },
+            { name: "comptoken_transfer_hook", programId: compto_transfer_hook_id_pubkey },
+        ],
+        [
+            sender.toAccount(),
+            comptoken_mint.toAccount(),
+            receiver.toAccount(),
+            extraAccountMetaAccount.toAccount(),
+            sender_data.toAccount(),
+            receiver_data.toAccount(),
+            get_default_global_data().toAccount(),
+        ]
+    );
+
+    const client = context.banksClient;
+    const payer = context.payer;
+    const keys = [
+        // transfer keys
+        { pubkey: sender.address, isSigner: false, isWritable: true },
+        { pubkey: comptoken_mint.address, isSigner: false, isWritable: false },
+        { pubkey: receiver.address, isSigner: false, isWritable: true },
+        { pubkey: sender_owner.publicKey, isSigner: true, isWritable: false },
+        // transfer hook api keys
+        { pubkey: extraAccountMetaAccount.address, isSigner: false, isWritable: false },
+        // our transfer hook keys
+        { pubkey: compto_program_id_pubkey, isSigner: false, isWritable: false },
+        { pubkey: sender_data.address, isSigner: false, isWritable: true },
+        { pubkey: receiver_data.address, isSigner: false, isWritable: true },
+        // the global data, which holds the config
+        { pubkey: global_data_account_pubkey, isSigner: false, isWritable: false },
+        // transfer hook program
+        { pubkey: compto_transfer_hook_id_pubkey, isSigner: false, isWritable: false },
+    ];
+
+    const data = Buffer.alloc(transferCheckedInstructionData.span);
+    transferCheckedInstructionData.encode(
+        {
+            instruction: TokenInstruction.TransferChecked,
+            amount: 1n,
+            MINT_DECIMALS,
+        },
+        data
+    );
+    const tx = new Transaction();
+    tx.recentBlockhash = context.lastBlockhash;
+    tx.add(new TransactionInstruction({ programId: TOKEN_2022_PROGRAM_ID, keys, data }));
+    tx.feePayer = payer.publicKey;
+    tx.sign(payer, sender_owner);
+    context.setClock(new Clock(0n, 0n, 0n, 0n, DEFAULT_START_TIME));
+    return { client, tx, receiver, receiver_data };
+}
+
+async function test_executeDormantWallets() {
+    console.log("test execute dormant wallets")
+    const dormant = DEFAULT_DISTRIBUTION_TIME - 2n * SEC_PER_DAY;
+
+    // the receiver hasn't claimed since before the last distribution, which costs it nothing
+    let { client, tx, receiver, receiver_data } = await setupTransfer(DEFAULT_DISTRIBUTION_TIME, dormant);
+    const meta = await client.processTransaction(tx);
+    console.log("logMessages: %s", meta.logMessages);
+    console.log("computeUnitsConsumed: %d", meta.computeUnitsConsumed);
+    let account = await client.getAccount(receiver.address);
+    Assert.assertNotNull(account);
+    Assert.assertEqual(TokenAccount.fromAccountInfoBytes(receiver.address, account).amount, 1n, "receiver has the comptoken");
+    account = await client.getAccount(receiver_data.address);
+    Assert.assertNotNull(account);
+    const finalReceiverData = UserDataAccount.fromAccountInfoBytes(receiver_data.address, account);
+    Assert.assertEqual(finalReceiverData.lastInterestPayoutDate, DEFAULT_DISTRIBUTION_TIME, "receiver is settled up to the last distribution");
+    Assert.assertEqual(finalReceiverData.lastSettledBalance, 1n, "the received comptoken earns interest from today");
+
+    // a dormant sender would lose the interest on what it sent, so it has to claim first
+    ({ client, tx } = await setupTransfer(dormant, DEFAULT_DISTRIBUTION_TIME));
+    const result = await client.simulateTransaction(tx);
+    Assert.assertNotNull(result.result, "transfer from a dormant wallet is rejected");
+    Assert.assert(
+        result.meta.logMessages.some((message) => message.includes("wallet must claim what it is owed first")),
+        "sender must claim first"
+    );
+}
+
+(async () => { await test_executeDormantWallets(); })();
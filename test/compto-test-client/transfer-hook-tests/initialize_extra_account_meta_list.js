@@ -1,9 +1,9 @@
 import { Keypair, SystemProgram, Transaction, TransactionInstruction, } from "@solana/web3.js";
 import { Clock, start } from "solana-bankrun";
 
-import { ExtraAccountMetaAccount, get_default_comptoken_mint, get_default_extra_account_metas_account } from "../accounts.js";
+import { AddressConfig, BIG_NUMBER, ExtraAccountMeta, ExtraAccountMetaAccount, get_default_comptoken_mint, get_default_extra_account_metas_account } from "../accounts.js";
 import { Assert } from "../assert.js";
-import { compto_extra_account_metas_account_pubkey, compto_transfer_hook_id_pubkey, DEFAULT_START_TIME, } from "../common.js";
+import { compto_extra_account_metas_account_pubkey, compto_program_id_pubkey, compto_transfer_hook_id_pubkey, DEFAULT_START_TIME, } from "../common.js";
 
 async function test_initializeExtraAccountMetaList() {
     let comptoken_mint = get_default_comptoken_mint();
@@ -14,6 +14,11 @@ async function test_initializeExtraAccountMetaList() {
         [{ name: "comptoken_transfer_hook", programId: compto_transfer_hook_id_pubkey }],
         [
             comptoken_mint.toAccount(),
+            // the mint authority would be the global data, so the hook takes the comptoken program from its owner
+            {
+                address: mint_authority.publicKey,
+                info: { lamports: BIG_NUMBER, data: new Uint8Array(0), owner: compto_program_id_pubkey, executable: false },
+            },
         ]
     );
 
@@ -57,6 +62,8 @@ async function test_initializeExtraAccountMetaList() {
     const finalMetaListAccount = ExtraAccountMetaAccount.fromAccountInfoBytes(compto_extra_account_metas_account_pubkey, account);
     // comptoken program id
     const accountMetaList = get_default_extra_account_metas_account()
+    // the last meta is the global data, which is the mint authority
+    accountMetaList.extraAccountMetas[3] = new ExtraAccountMeta(new AddressConfig(AddressConfig.Types.LITERAL, mint_authority.publicKey), false, false);
     Assert.assert(finalMetaListAccount.address.equals(accountMetaList.address), "address isn't correct");
     Assert.assertEqual(finalMetaListAccount.extraAccountMetas.length, accountMetaList.extraAccountMetas.length, "length isn't correct");
     let zipped = finalMetaListAccount.extraAccountMetas.map((v, i) => [v, accountMetaList.extraAccountMetas[i]]);
@@ -39,12 +39,8 @@ def checkIfProgamIdExists():
 def getProgramId():
     return run(f"solana address -k target/deploy/comptoken-keypair.json")
 
-def createToken():
-    run(f"{SPL_TOKEN_CMD} create-token -v --decimals {MINT_DECIMALS} --output json > {COMPTOKEN_MINT_JSON}")
-
-def createComptoAccount():
-    generateTestUser()
-    run(f"{SPL_TOKEN_CMD} create-account {getTokenAddress()} {TEST_USER_ACCOUNT_JSON}")
+def getTransferHookId():
+    return run(f"solana address -k target/deploy/comptoken_transfer_hook-keypair.json")
 
 def getPubkey(path: Path) -> str:
     return run(f"solana-keygen pubkey {path}")
@@ -97,7 +93,7 @@ def checkIfTokenAddressExists() -> bool:
     return COMPTOKEN_MINT_JSON.exists()
 
 def getTokenAddress():
-    return (json.loads(COMPTOKEN_MINT_JSON.read_text()).get("commandOutput").get("address"))
+    return json.loads(COMPTOKEN_MINT_JSON.read_text()).get("address")
 
 def runTestClient():
     return run("node --trace-warnings compto-test-client/test_client.js", TEST_PATH)
@@ -105,15 +101,9 @@ def runTestClient():
 if __name__ == "__main__":
     # create cache if it doesn't exist
     run(f"[ -d {CACHE_PATH} ] || mkdir {CACHE_PATH} ")
-    run(f"[ -d {COMPTOKEN_GENERATED_PATH} ] || mkdir {COMPTOKEN_GENERATED_PATH} ")
-    run(f"[ -d {TRANSFER_HOOK_GENERATED_PATH} ] || mkdir {TRANSFER_HOOK_GENERATED_PATH} ")
-    # If ProgramId doesn't exist, we need to build WITHOUT the testmode feature.
-    # This is because the static seed in testmode depends on ProgramId and ProgramId
-    # is generated on the first build.
-    print("Checking if Comptoken ProgramId exists...")
-    if not checkIfProgamIdExists():
-        print("Creating Comptoken ProgramId...")
-        run("cargo build-sbf", PROJECT_PATH)
+    # the addresses are configured when the program is initialized, so it only needs to be built once
+    print("Building...")
+    build()
     print("Creating Validator...")
     with BackgroundProcess(
         "solana-test-validator --reset",
@@ -125,20 +115,15 @@ if __name__ == "__main__":
         print("Checking Validator Ready...")
         waitTillValidatorReady(validator)
         print("Validator Ready")
-        createToken()
         programId = getProgramId()
-        globalDataPDA = setGlobalDataPDA(programId)
-        interestBankPDA = setInterestBankPDA(programId)
-        UBIBankPDA = setUBIBankPDA(programId)
-        comptoken_id = getTokenAddress()
-        print("Checking Compto Program for hardcoded Comptoken Address and static seed...")
-        generateComptokenAddressFile(
-            globalDataPDA["bumpSeed"], interestBankPDA["bumpSeed"], UBIBankPDA["bumpSeed"], comptoken_id
-        )
-        print("Creating Token Account...")
-        createComptoAccount()
-        print("Building...")
-        build()
+        # the mint is created by the comptoken program when it is initialized, a new mint is a new instance
+        mint = setMint()
+        setGlobalDataPDA(programId, mint)
+        setInterestBankPDA(programId, mint)
+        setUBIBankPDA(programId, mint)
+        setExtraAccountMetasPDA(getTransferHookId(), mint)
+        # the test client creates the test user's token account once the mint exists
+        generateTestUser()
         print("Deploying...")
         deployIfNeeded()
         print("Running Test Client...")